@@ -1,39 +1,55 @@
 use crate::{
-    create_local_repository_path, display::*, github_client::GitHubClientImpl, github_models::*,
-    repository_id::PartialRepoId, FullRepoId, StarredRepository,
+    cli::{workspace::ProjectAction, OutputFormat},
+    display::*,
+    find_project_paths,
+    github_client::GitHubClientImpl,
+    github_models::*,
+    output::Output,
+    process,
+    repository_id::{IssueId, PartialRepoId},
+    types::{BuildRef, TaskFilter, TaskSort},
+    FullRepoId, StarredRepository,
 };
 use anyhow::{bail, Context, Error};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use console::Term;
-use dialoguer::Confirm;
+use dialoguer::FuzzySelect;
 use futures::{
     future,
-    stream::{LocalBoxStream, TryStreamExt},
-    FutureExt, Stream,
+    stream::{LocalBoxStream, StreamExt, TryStreamExt},
+    FutureExt,
+};
+use git2::{
+    build::RepoBuilder, Cred, CredentialType, FetchOptions, ProxyOptions, PushOptions,
+    RemoteCallbacks,
 };
-use git2::{build::RepoBuilder, Cred, FetchOptions, RemoteCallbacks};
-use http::header::HeaderName;
-use octocrab::Octocrab;
 use sekret::Secret;
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde::Serialize;
 use std::{
-    collections::HashMap,
-    env, fmt,
+    borrow::Cow,
+    env,
     io::Write,
-    os::unix::prelude::CommandExt,
     path::{Path, PathBuf},
     process::Command,
     time::Duration,
 };
-use tokio::{fs, task};
-use tokio_stream::wrappers::ReadDirStream;
+use tokio::task;
 
-#[derive(PartialEq, Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug)]
 pub struct AppConfig<'a> {
     pub github_username: &'a str,
     pub github_token: Secret<&'a str>,
+    pub github_api_url: Option<&'a str>,
+    /// Proxy of GitHub API and git requests.
+    pub proxy: Option<&'a str>,
     pub workspace_root_dir: &'a Path,
+    /// Directories where projects are searched, besides the workspace.
+    pub project_roots: &'a [PathBuf],
+    /// Depth of directories searched for projects.
+    pub project_depth: usize,
+    pub output: &'a Output,
+    pub limit: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -41,7 +57,44 @@ pub struct App<'a, GitHubClient> {
     github_username: &'a str,
     workspace_root_dir_path: &'a Path,
     pub github_client: GitHubClient,
-    my_workspace_dir_path: PathBuf,
+    /// Directories where projects are searched.
+    project_roots: Vec<PathBuf>,
+    project_depth: usize,
+    output: &'a Output,
+    limit: Option<usize>,
+}
+
+/// Starred repository, as exported by [App::export_starred_repositories].
+#[derive(Serialize, Debug)]
+struct StarExport {
+    name: String,
+    owner: String,
+    description: Option<String>,
+    language: Option<String>,
+    stars: Option<u32>,
+    pushed_at: Option<DateTime<Utc>>,
+    url: Option<String>,
+}
+
+impl From<GhRepository> for StarExport {
+    fn from(x: GhRepository) -> Self {
+        Self {
+            name: x.name,
+            owner: x.owner.map(|x| x.login).unwrap_or_default(),
+            description: x.description,
+            language: x.language.and_then(|x| x.as_str().map(ToOwned::to_owned)),
+            stars: x.stargazers_count,
+            pushed_at: x.pushed_at,
+            url: x.html_url.map(String::from),
+        }
+    }
+}
+
+/// Project in my workspace, as printed by [App::list_projects].
+#[derive(Serialize, Debug)]
+struct ProjectEntry<'a> {
+    name: Cow<'a, str>,
+    path: &'a Path,
 }
 
 impl<'a> App<'a, GitHubClientImpl> {
@@ -49,17 +102,33 @@ impl<'a> App<'a, GitHubClientImpl> {
         AppConfig {
             github_username,
             github_token,
+            github_api_url,
+            proxy,
             workspace_root_dir,
+            project_roots,
+            project_depth,
+            output,
+            limit,
         }: AppConfig<'a>,
     ) -> Result<Self, Error> {
-        let github_client =
-            crate::github_client::GitHubClientImpl::new(github_token.map(ToOwned::to_owned))?;
-        let my_workspace_dir_path = workspace_root_dir.join(github_username);
+        let github_client = crate::github_client::GitHubClientImpl::new(
+            github_token.map(ToOwned::to_owned),
+            github_api_url,
+            proxy,
+        )?;
+        let project_roots = [workspace_root_dir]
+            .into_iter()
+            .chain(project_roots.iter().map(PathBuf::as_path))
+            .map(ToOwned::to_owned)
+            .collect();
         let s = Self {
             github_username,
             workspace_root_dir_path: workspace_root_dir,
             github_client,
-            my_workspace_dir_path,
+            project_roots,
+            project_depth,
+            output,
+            limit,
         };
         Ok(s)
     }
@@ -69,154 +138,123 @@ impl<'a, GitHubClient> App<'a, GitHubClient>
 where
     GitHubClient: self::GitHubClient<'a>,
 {
-    pub async fn view_repository_settings(
-        &'a self,
-        repo_id: PartialRepoId,
-    ) -> Result<(), Error> {
-        let repo_id = repo_id.complete(self.github_username);
-        let repo = self.github_client.get_repository(repo_id).await?;
-        let settings = repo.extract_repository_settings()?;
-        println!("{}", settings);
-        Ok(())
-    }
-
-    pub async fn copy_repository_settings(
-        &self,
-        from: PartialRepoId,
-        to: PartialRepoId,
-    ) -> Result<(), Error> {
-        let from = from.complete(self.github_username);
-        let to = to.complete(self.github_username);
-
-        let client = create_client()?;
-
-        let get_settings = |repo_id: FullRepoId| {
-            let client = client.clone();
-            let FullRepoId { owner, name } = repo_id;
-            async move {
-                let repo = client.repos(owner, name).get().await?;
-                let settings = repo.extract_repository_settings()?;
-                Result::<_, Error>::Ok(settings)
-            }
+    pub async fn list_starred_repositories(&'a self, jsonl: bool) -> Result<(), Error> {
+        let mut out = self.output;
+        let repos = self
+            .github_client
+            .list_stared_repositories()
+            .take(self.limit.unwrap_or(usize::MAX));
+        let format = if jsonl {
+            OutputFormat::Ndjson
+        } else {
+            out.format()
         };
-
-        let old_settings = get_settings(to.clone()).await?;
-        let new_settings = get_settings(from.clone()).await?;
-        let diff = RepositorySettingsDiff::new(&old_settings, &new_settings);
-
-        println!("{}", diff);
-
-        if !Confirm::new()
-            .with_prompt("Apply settings?")
-            .default(false)
-            .show_default(true)
-            .wait_for_newline(true)
-            .interact()?
-        {
+        if format == OutputFormat::Json {
+            let repos: Vec<_> = repos.try_collect().await?;
+            out.write_records(&repos)?;
+            return Ok(());
+        }
+        if format == OutputFormat::Ndjson {
+            repos
+                .try_for_each(|repo| {
+                    let r = serde_json::to_string(&repo)
+                        .map_err(Error::from)
+                        .and_then(|x| writeln!(out, "{}", x).map_err(Into::into));
+                    future::ready(r)
+                })
+                .await?;
             return Ok(());
         }
-
-        let _: HashMap<String, Value> = {
-            let FullRepoId { owner, name } = to;
-            client
-                .patch(format!("repos/{owner}/{name}"), Some(&new_settings))
-                .await?
-        };
-
-        Ok(())
-    }
-
-    pub async fn list_starred_repositories(&'a self) -> Result<(), Error> {
-        let repos = self.github_client.list_stared_repositories();
         repos
             .map_ok(StarredRepository)
-            .try_for_each(|repo| {
-                println!("{}", repo);
-                future::ok(())
-            })
+            .try_for_each(|repo| future::ready(writeln!(out, "{}", repo).map_err(Into::into)))
             .await?;
         Ok(())
     }
 
-    pub async fn browse_upstream_repository(
-        &'a self,
-        repo_id: Option<PartialRepoId>,
-    ) -> Result<(), Error> {
-        let repo_id = match repo_id {
-            Some(repo_id) => repo_id.complete(self.github_username),
-            None => get_repo_id_for_cwd().await?,
-        };
-
-        let repo = self.github_client.get_repository(repo_id.clone()).await?;
-
-        let url = {
-            if !repo.fork.unwrap_or_default() {
-                bail!("Repository {repo_id} is not a fork.")
+    /// Exports starred repositories as CSV, or as JSON when the output format is JSON.
+    ///
+    /// Repositories are written as their pages arrive instead of after fetching all of them.
+    pub async fn export_starred_repositories(&'a self) -> Result<(), Error> {
+        let mut out = self.output;
+        let mut repos = self
+            .github_client
+            .list_stared_repositories()
+            .take(self.limit.unwrap_or(usize::MAX))
+            .map_ok(StarExport::from);
+
+        let format = out.format();
+        let mut csv = csv::Writer::from_writer(out);
+        if format == OutputFormat::Json {
+            writeln!(out, "[")?;
+        }
+        let mut first = true;
+        while let Some(record) = repos.try_next().await? {
+            match format {
+                OutputFormat::Table | OutputFormat::Csv => csv.serialize(&record)?,
+                OutputFormat::Json => {
+                    if !first {
+                        writeln!(out, ",")?;
+                    }
+                    write!(out, "  {}", serde_json::to_string(&record)?)?;
+                }
+                OutputFormat::Ndjson => writeln!(out, "{}", serde_json::to_string(&record)?)?,
             }
-            repo.parent
-                .and_then(|x| x.html_url)
-                .expect("Forked repository should have the HTML URL to its parent repository.")
-        };
-
-        Command::new("xdg-open").arg(url.as_str()).status()?;
-
+            first = false;
+        }
+        csv.flush()?;
+        if format == OutputFormat::Json {
+            if !first {
+                writeln!(out)?;
+            }
+            writeln!(out, "]")?;
+        }
         Ok(())
     }
 
-    pub async fn clone_repository(&'a self, repo_id: PartialRepoId) -> Result<(), Error> {
-        let repo_id = repo_id.complete(self.github_username);
-
-        let repo_info = self.github_client.get_repository(repo_id.clone()).await?;
-
-        let ssh_url = repo_info
-            .ssh_url
-            .ok_or_else(|| Error::msg("Expecting repository to have ssh url, but was not."))?;
-
-        let upstream_url = match repo_info.parent {
-            Some(upstream) => upstream
-                .ssh_url
-                .ok_or_else(|| {
-                    Error::msg("Expecting upstream repository to have ssh url, but was not.")
-                })?
-                .into(),
-            None => None,
+    /// Opens what the target refers to.
+    ///
+    /// Opens repository page of the current directory when there is no target, the issue page
+    /// for `:owner?/:name#:number`, the repository page for `:owner/:name`, and otherwise
+    /// edits the project with that name.
+    pub async fn open(&'a self, target: Option<String>) -> Result<(), Error> {
+        let target = match target {
+            Some(x) => x,
+            None => {
+                let repo_id = get_repo_id_for_cwd().await?;
+                return process::open_url(&self.get_repository_html_url(repo_id).await?);
+            }
         };
-
-        let workspace_home = self.workspace_root_dir_path;
-        let path = create_local_repository_path(workspace_home, &repo_id);
-        println!(
-            "Cloning {repo_id} repository to {path}.",
-            path = path.display()
-        );
-        let repo = RepoBuilder::new()
-            .fetch_options(create_fetch_options())
-            .clone(&ssh_url, &path)
-            .context("Failed to clone repository.")?;
-
-        if let Some(upstream_url) = upstream_url {
-            let mut remote = repo
-                .remote("upstream", &upstream_url)
-                .context("Failed to add upstream remote.")?;
-            let mut options = {
-                let mut opts = create_fetch_options();
-                opts.prune(git2::FetchPrune::On);
-                opts
-            };
-            remote
-                .fetch(
-                    &["+refs/heads/*:refs/remotes/origin/*"],
-                    Some(&mut options),
-                    None,
-                )
-                .context("Failed to fetch upstream.")?;
+        if target.contains('#') {
+            let IssueId { repo, number } = target.parse()?;
+            let repo_id = repo.complete(self.github_username);
+            let html_url = self.get_repository_html_url(repo_id).await?;
+            process::open_url(&format!("{html_url}/issues/{number}"))
+        } else if target.contains('/') {
+            let repo_id: FullRepoId = target.parse()?;
+            process::open_url(&self.get_repository_html_url(repo_id).await?)
+        } else {
+            self.edit_project(&target).await
         }
+    }
 
-        Ok(())
+    /// Gets URL of the page of a repository from the API, to also work with GitHub Enterprise
+    /// Server.
+    async fn get_repository_html_url(&'a self, repo_id: FullRepoId) -> Result<String, Error> {
+        let repo = self.github_client.get_repository(repo_id.clone()).await?;
+        let html_url = repo
+            .html_url
+            .with_context(|| format!("Repository {repo_id} has no HTML URL."))?;
+        Ok(html_url.as_str().trim_end_matches('/').to_owned())
     }
 
+    /// Prints build status of a commit until all of its check runs complete.
+    ///
+    /// The commit is the latest commit of the default branch, or the one the reference points to.
     pub async fn poll_repository_build_status(
         &'a self,
         repo_id: Option<PartialRepoId>,
+        build_ref: Option<BuildRef>,
     ) -> Result<(), Error> {
         let mut out = Term::buffered_stdout();
 
@@ -227,17 +265,34 @@ where
             .unwrap_or_else(|| get_repo_id_for_cwd().boxed())
             .await?;
 
-        writeln!(out, "{repo_id}\n")?;
+        match &build_ref {
+            Some(x) => writeln!(out, "{repo_id} {x}\n")?,
+            None => writeln!(out, "{repo_id}\n")?,
+        }
         out.flush()?;
 
-        let commit = self
-            .github_client
-            .list_repository_commits(&repo_id)
-            .try_next()
-            .await?
-            .ok_or_else(|| {
-                Error::msg(format!("Repository {repo_id} doesn't have a commit yet."))
-            })?;
+        let commit = match build_ref {
+            None => self
+                .github_client
+                .list_repository_commits(&repo_id)
+                .try_next()
+                .await?
+                .ok_or_else(|| {
+                    Error::msg(format!("Repository {repo_id} doesn't have a commit yet."))
+                })?,
+            Some(BuildRef::GitRef(gitref)) => {
+                self.github_client.get_commit(&repo_id, &gitref).await?
+            }
+            Some(BuildRef::PullRequest(number)) => {
+                let pr = self
+                    .github_client
+                    .get_pull_request(&repo_id, number)
+                    .await?;
+                self.github_client
+                    .get_commit(&repo_id, &pr.head.sha)
+                    .await?
+            }
+        };
 
         writeln!(out, "{}", CommitInfo::from_github_commit(&commit))?;
         out.flush()?;
@@ -264,15 +319,28 @@ where
         Ok(())
     }
 
-    pub async fn list_projects(&self) -> Result<(), Error> {
-        let mut out = Term::buffered_stdout();
+    /// Prints local projects, only the ones matching a name when there's one.
+    pub async fn list_projects(&self, name: Option<&str>) -> Result<(), Error> {
+        let mut out = self.output;
 
-        let projects: Vec<_> = self.get_projects().await?.try_collect().await?;
+        let projects = self.get_projects()?;
+        let projects: Vec<_> = match name {
+            Some(name) => match_projects(&projects, name),
+            None => projects.iter().map(PathBuf::as_path).collect(),
+        };
+        let projects: Vec<_> = projects
+            .into_iter()
+            .filter_map(|path| {
+                let name = path.file_name()?.to_string_lossy();
+                Some(ProjectEntry { name, path })
+            })
+            .collect();
 
+        if out.write_records(&projects)? {
+            return Ok(());
+        }
         for project in projects {
-            if let Some(name) = project.file_name() {
-                writeln!(&mut out, "{}", name.to_string_lossy())?;
-            }
+            writeln!(&mut out, "{}", project.name)?;
         }
         out.flush()?;
 
@@ -280,191 +348,320 @@ where
     }
 
     pub async fn edit_project(&self, project_name: &str) -> Result<(), Error> {
-        let editor = env::var("SHUB_EDITOR")?;
         let path = self.get_project_path(project_name).await?;
-        let error = Command::new(editor).arg(path).exec();
-        Err(error.into())
+        edit_path(&path)
+    }
+
+    /// Prompts to pick a local project, then prints its path or edits it.
+    pub async fn pick_project(&self, action: ProjectAction) -> Result<(), Error> {
+        let mut out = self.output;
+
+        let projects = self.get_projects()?;
+        // paths relative to their roots tell projects of the same name apart
+        let items: Vec<_> = projects
+            .iter()
+            .map(|path| {
+                let relative = self
+                    .project_roots
+                    .iter()
+                    .find_map(|root| path.strip_prefix(root).ok())
+                    .unwrap_or(path);
+                relative.display().to_string()
+            })
+            .collect();
+        let selection = FuzzySelect::new()
+            .with_prompt("Project")
+            .items(&items)
+            .interact_opt()?;
+        let path = match selection {
+            Some(idx) => &projects[idx],
+            None => return Ok(()),
+        };
+
+        match action {
+            ProjectAction::Print => writeln!(out, "{}", path.display())?,
+            ProjectAction::Edit => edit_path(path)?,
+        }
+        Ok(())
     }
 
     pub async fn print_project_path(&self, project_name: &str) -> Result<(), Error> {
         let path = self.get_project_path(project_name).await?;
-        println!("{}", path.display());
+        let mut out = self.output;
+        writeln!(out, "{}", path.display())?;
         Ok(())
     }
 
-    async fn get_projects(
-        &self,
-    ) -> Result<impl Stream<Item = Result<PathBuf, std::io::Error>>, Error> {
-        Ok(
-            ReadDirStream::new(fs::read_dir(&self.my_workspace_dir_path).await?).try_filter_map(
-                |entry| {
-                    future::ok(
-                        Some(entry.path()).and_then(|x| if x.is_dir() { Some(x) } else { None }),
-                    )
-                },
-            ),
-        )
+    fn get_projects(&self) -> Result<Vec<PathBuf>, Error> {
+        let paths =
+            task::block_in_place(|| find_project_paths(&self.project_roots, self.project_depth))?;
+        Ok(paths)
     }
 
     async fn get_project_path(&self, project_name: &str) -> Result<PathBuf, Error> {
-        self.get_projects()
-            .await?
-            .try_filter(|path| {
-                future::ready(
-                    path.file_name()
-                        .and_then(|x| x.to_str())
-                        .map(|x| x == project_name)
-                        .unwrap_or_default(),
+        let projects = self.get_projects()?;
+        match match_projects(&projects, project_name)[..] {
+            [] => bail!("project `{project_name}` does not exists"),
+            [path] => Ok(path.to_owned()),
+            ref paths => {
+                let paths: Vec<_> = paths.iter().map(|x| x.display().to_string()).collect();
+                bail!(
+                    "project `{project_name}` is ambiguous, it matches {}",
+                    paths.join(", ")
                 )
-            })
-            .try_next()
-            .await?
-            .ok_or_else(|| Error::msg(format!("project `{project_name}` does not exists")))
+            }
+        }
     }
 
-    pub async fn list_my_tasks(&'a self) -> Result<(), Error> {
-        let mut out = Term::buffered_stdout();
-
-        let issues: Vec<_> = self.github_client.list_user_issues().try_collect().await?;
+    /// Prints tasks as a table, or grouped by repository with `board`.
+    pub async fn list_my_tasks(&'a self, filter: TaskFilter, board: bool) -> Result<(), Error> {
+        let mut out = self.output;
+
+        let limit = self.limit.unwrap_or(usize::MAX);
+        let (mut issues, review_requests): (Vec<_>, Vec<_>) = future::try_join(
+            self.github_client
+                .list_user_issues(&filter)
+                .take(limit)
+                .try_collect(),
+            self.github_client
+                .list_review_requests(&filter)
+                .take(limit)
+                .try_collect(),
+        )
+        .await?;
+        // pull requests assigned to me stay as they are
+        for x in review_requests {
+            if !issues.iter().any(|y: &GhIssue| y.inner.id == x.inner.id) {
+                issues.push(x);
+            }
+        }
+        match filter.sort {
+            Some(TaskSort::Updated) => {
+                issues.sort_by(|a, b| b.inner.updated_at.cmp(&a.inner.updated_at))
+            }
+            Some(TaskSort::Created) => {
+                issues.sort_by(|a, b| b.inner.created_at.cmp(&a.inner.created_at))
+            }
+            // best matches of both searches can't be compared, assigned ones come first
+            None => (),
+        }
+        issues.truncate(limit);
 
-        write!(out, "{}", TaskInfos::from_github_issues(&issues))?;
-        out.flush()?;
+        let tasks = TaskInfos::from_github_issues(&issues);
+        if self.output.write_records(tasks.infos())? {
+            return Ok(());
+        }
+        if board {
+            write!(out, "{}", TaskBoard::from_github_issues(&issues))?;
+        } else {
+            write!(out, "{}", tasks)?;
+        }
 
         Ok(())
     }
 }
 
-fn create_fetch_options<'a>() -> FetchOptions<'a> {
-    let mut opts = FetchOptions::new();
-    opts.remote_callbacks(create_remote_callbacks());
-    opts
+/// Opens `SHUB_EDITOR` to a path, in place of shub.
+fn edit_path(path: &Path) -> Result<(), Error> {
+    let editor = env::var("SHUB_EDITOR").context("SHUB_EDITOR is not set.")?;
+    let mut cmd = Command::new(editor);
+    cmd.arg(path);
+    process::hand_over(cmd)
 }
 
-fn create_remote_callbacks<'a>() -> RemoteCallbacks<'a> {
-    let mut cbs = RemoteCallbacks::new();
-    cbs.credentials(|_url, username_from_url, _credential_type| {
-        let username = username_from_url.unwrap_or("git");
-        Cred::ssh_key_from_agent(username)
-    });
-    cbs
-}
+/// Finds projects matching a name.
+///
+/// Projects named exactly the name match. Otherwise, ignoring case, the closest projects match:
+/// the ones named the name, then starting with it, then containing it, and then containing its
+/// characters in order.
+fn match_projects<'p>(projects: &'p [PathBuf], name: &str) -> Vec<&'p Path> {
+    let project_name = |x: &Path| {
+        x.file_name()
+            .map(|x| x.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    let exact: Vec<_> = projects
+        .iter()
+        .filter(|x| project_name(x) == name)
+        .map(PathBuf::as_path)
+        .collect();
+    if !exact.is_empty() {
+        return exact;
+    }
 
-trait ExtractRepositorySettings {
-    fn extract_repository_settings(&self) -> Result<RepositorySettings, Error>;
+    let name = name.to_lowercase();
+    let is_subsequence = |x: &str| {
+        let mut chars = x.chars();
+        name.chars().all(|c| chars.any(|x| x == c))
+    };
+    // lower ranks are closer
+    let rank = |x: &str| {
+        if x == name {
+            Some(0)
+        } else if x.starts_with(&name) {
+            Some(1)
+        } else if x.contains(&name) {
+            Some(2)
+        } else if is_subsequence(x) {
+            Some(3)
+        } else {
+            None
+        }
+    };
+    let ranked: Vec<_> = projects
+        .iter()
+        .filter_map(|x| Some((rank(&project_name(x).to_lowercase())?, x.as_path())))
+        .collect();
+    let closest = ranked.iter().map(|(rank, _)| *rank).min();
+    ranked
+        .into_iter()
+        .filter(|(rank, _)| Some(*rank) == closest)
+        .map(|(_, x)| x)
+        .collect()
 }
 
-#[derive(Deserialize, Serialize, PartialEq, Copy, Clone, Debug)]
-struct RepositorySettings {
-    allow_rebase_merge: bool,
-    allow_squash_merge: bool,
-    allow_auto_merge: bool,
-    delete_branch_on_merge: bool,
-    allow_merge_commit: bool,
-}
+#[cfg(test)]
+#[test]
+fn test_match_projects() {
+    let projects: Vec<_> = [
+        "/ws/kafji/shub",
+        "/ws/kafji/shub-web",
+        "/ws/kafji/sekret",
+        "/ws/rust-lang/rust",
+        "/ws/kafji/Rust-notes",
+    ]
+    .into_iter()
+    .map(PathBuf::from)
+    .collect();
+    let names = |name: &str| -> Vec<_> {
+        match_projects(&projects, name)
+            .into_iter()
+            .map(|x| x.to_str().unwrap())
+            .collect()
+    };
 
-macro_rules! write_key {
-    ($w:expr, $this:expr, $key:ident) => {{
-        let val = $this.$key;
-        write!($w, "{key:>25} = {val:5}\n", key = stringify!($key))
-    }};
+    assert_eq!(names("shub"), ["/ws/kafji/shub"]);
+    assert_eq!(names("SHUB"), ["/ws/kafji/shub"]);
+    assert_eq!(names("shub-"), ["/ws/kafji/shub-web"]);
+    assert_eq!(names("rust"), ["/ws/rust-lang/rust"]);
+    assert_eq!(names("notes"), ["/ws/kafji/Rust-notes"]);
+    assert_eq!(names("skt"), ["/ws/kafji/sekret"]);
+    assert_eq!(names("sh"), ["/ws/kafji/shub", "/ws/kafji/shub-web"]);
+    assert!(names("xyz").is_empty());
 }
 
-impl fmt::Display for RepositorySettings {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write_key!(f, self, allow_rebase_merge)?;
-        write_key!(f, self, allow_squash_merge)?;
-        write_key!(f, self, allow_auto_merge)?;
-        write_key!(f, self, delete_branch_on_merge)?;
-        write_key!(f, self, allow_merge_commit)?;
-        Ok(())
+/// Clones a repository to a path, adding its upstream as `upstream` remote if it's a fork.
+///
+/// The token authenticates HTTPS URLs, SSH URLs authenticate with the SSH agent.
+pub(crate) fn clone_to(
+    url: &str,
+    upstream_url: Option<&str>,
+    path: &Path,
+    token: Option<&str>,
+    proxy: Option<&str>,
+) -> Result<git2::Repository, Error> {
+    let repo = RepoBuilder::new()
+        .fetch_options(create_fetch_options_with_token(token, proxy))
+        .clone(url, path)
+        .context("Failed to clone repository.")?;
+
+    if let Some(upstream_url) = upstream_url {
+        let mut remote = repo
+            .remote("upstream", upstream_url)
+            .context("Failed to add upstream remote.")?;
+        let mut options = {
+            let mut opts = create_fetch_options_with_token(token, proxy);
+            opts.prune(git2::FetchPrune::On);
+            opts
+        };
+        remote
+            .fetch(
+                &["+refs/heads/*:refs/remotes/upstream/*"],
+                Some(&mut options),
+                None,
+            )
+            .context("Failed to fetch upstream.")?;
     }
-}
 
-macro_rules! extract_key {
-    ($repo:expr, $key:ident) => {
-        $repo
-            .$key
-            .ok_or_else(|| Error::msg(format!("Missing value for key `{}`.", stringify!($key))))
-    };
+    Ok(repo)
 }
 
-impl ExtractRepositorySettings for GhRepository {
-    fn extract_repository_settings(&self) -> Result<RepositorySettings, Error> {
-        let repo = self;
-        let s = RepositorySettings {
-            allow_rebase_merge: extract_key!(repo, allow_rebase_merge)?,
-            allow_squash_merge: extract_key!(repo, allow_squash_merge)?,
-            allow_auto_merge: extract_key!(repo, allow_auto_merge)?,
-            delete_branch_on_merge: extract_key!(repo, delete_branch_on_merge)?,
-            allow_merge_commit: extract_key!(repo, allow_merge_commit)?,
-        };
-        Ok(s)
-    }
+pub(crate) fn create_fetch_options<'a>(proxy: Option<&str>) -> FetchOptions<'a> {
+    create_fetch_options_with_token(None, proxy)
 }
 
-#[derive(PartialEq, Clone, Debug)]
-struct RepositorySettingsDiff<'a> {
-    old: &'a RepositorySettings,
-    new: &'a RepositorySettings,
+pub(crate) fn create_fetch_options_with_token<'a>(
+    token: Option<&'a str>,
+    proxy: Option<&str>,
+) -> FetchOptions<'a> {
+    let mut opts = FetchOptions::new();
+    opts.remote_callbacks(create_remote_callbacks(token));
+    opts.proxy_options(create_proxy_options(proxy));
+    opts
 }
 
-impl<'a> RepositorySettingsDiff<'a> {
-    fn new(old: &'a RepositorySettings, new: &'a RepositorySettings) -> Self {
-        Self { old, new }
-    }
+pub(crate) fn create_push_options<'a>(proxy: Option<&str>) -> PushOptions<'a> {
+    let mut cbs = create_remote_callbacks(None);
+    // rejected updates are reported here instead of as an error of the push call
+    cbs.push_update_reference(|refname, status| match status {
+        Some(status) => Err(git2::Error::from_str(&format!(
+            "failed to push {refname}, {status}"
+        ))),
+        None => Ok(()),
+    });
+    let mut opts = PushOptions::new();
+    opts.remote_callbacks(cbs);
+    opts.proxy_options(create_proxy_options(proxy));
+    opts
 }
 
-macro_rules! diff_key {
-    ($w:expr, $this:expr, $key:ident) => {{
-        let old = $this.old.$key;
-        let new = $this.new.$key;
-        write!(
-            $w,
-            "{key:>25} = {old:>5} -> {new:<5}\n",
-            key = stringify!($key)
-        )
-    }};
+/// Uses the given proxy, or detects it from git configuration and proxy environment variables.
+fn create_proxy_options<'a>(proxy: Option<&str>) -> ProxyOptions<'a> {
+    let mut opts = ProxyOptions::new();
+    match proxy {
+        Some(url) => opts.url(url),
+        None => opts.auto(),
+    };
+    opts
 }
 
-impl fmt::Display for RepositorySettingsDiff<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        diff_key!(f, self, allow_rebase_merge)?;
-        diff_key!(f, self, allow_squash_merge)?;
-        diff_key!(f, self, allow_auto_merge)?;
-        diff_key!(f, self, delete_branch_on_merge)?;
-        diff_key!(f, self, allow_merge_commit)?;
-        Ok(())
-    }
+/// Authenticates SSH with the SSH agent, and HTTPS with the token or with git credential
+/// helpers when there's no token.
+fn create_remote_callbacks(token: Option<&str>) -> RemoteCallbacks<'_> {
+    let mut cbs = RemoteCallbacks::new();
+    // libgit2 asks again when credentials are rejected, fail instead of asking forever
+    let mut userpass_tried = false;
+    cbs.credentials(move |url, username_from_url, credential_type| {
+        if credential_type.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if userpass_tried {
+                return Err(git2::Error::from_str("credentials were rejected"));
+            }
+            userpass_tried = true;
+            return match token {
+                Some(token) => Cred::userpass_plaintext("x-access-token", token),
+                None => {
+                    Cred::credential_helper(&git2::Config::open_default()?, url, username_from_url)
+                }
+            };
+        }
+        let username = username_from_url.unwrap_or("git");
+        Cred::ssh_key_from_agent(username)
+    });
+    cbs
 }
 
-async fn get_repo_id_for_cwd() -> Result<FullRepoId, Error> {
+pub(crate) async fn get_repo_id_for_cwd() -> Result<FullRepoId, Error> {
     task::block_in_place(|| {
         let repo = git2::Repository::discover(".")?;
         let origin = repo.find_remote("origin")?;
-        let url = origin.url().unwrap();
-        let start = url.find(':').unwrap();
-        let end = url.find(".git").unwrap();
-        let repo_id = &url[start + 1..end];
-        let repo_id = repo_id.parse()?;
-        Ok(repo_id)
+        let url = origin
+            .url()
+            .context("URL of origin remote is not valid UTF-8.")?;
+        FullRepoId::from_remote_url(url)
+            .with_context(|| format!("Origin remote `{url}` is not a repository URL."))
     })
 }
 
-#[deprecated]
-fn create_client() -> Result<Octocrab, Error> {
-    let user_agent = concat!(
-        env!("CARGO_PKG_NAME"),
-        concat!("/", env!("CARGO_PKG_VERSION"))
-    )
-    .to_owned();
-    let token = env::var("SHUB_TOKEN")?;
-    let client = Octocrab::builder()
-        .add_header(HeaderName::from_static("user-agent"), user_agent)
-        .personal_token(token)
-        .build()?;
-    Ok(client)
-}
-
 #[async_trait]
 pub trait GitHubClient<'a> {
     fn list_stared_repositories(&'a self) -> LocalBoxStream<'a, Result<GhRepository, Error>>;
@@ -486,8 +683,40 @@ pub trait GitHubClient<'a> {
     where
         'a: 'b;
 
+    /// https://docs.github.com/en/rest/commits/commits#get-a-commit
+    async fn get_commit<'b>(
+        &'a self,
+        repo_id: &'b FullRepoId,
+        gitref: &'b str,
+    ) -> Result<GhCommit, Error>
+    where
+        'a: 'b;
+
+    /// https://docs.github.com/en/rest/pulls/pulls#get-a-pull-request
+    async fn get_pull_request<'b>(
+        &'a self,
+        repo_id: &'b FullRepoId,
+        number: u64,
+    ) -> Result<GhPullRequest, Error>
+    where
+        'a: 'b;
+
     async fn get_repository(&'a self, repo_id: FullRepoId) -> Result<GhRepository, Error>;
 
-    /// https://docs.github.com/en/rest/reference/issues#list-user-account-issues-assigned-to-the-authenticated-user
-    fn list_user_issues(&'a self) -> LocalBoxStream<'a, Result<GhIssue, Error>>;
+    /// Searches open issues and pull requests assigned to current user.
+    ///
+    /// https://docs.github.com/en/rest/search#search-issues-and-pull-requests
+    fn list_user_issues(
+        &'a self,
+        filter: &TaskFilter,
+    ) -> LocalBoxStream<'a, Result<GhIssue, Error>>;
+
+    /// Searches open pull requests requesting review of current user, marked as
+    /// [GhIssue::review_requested]. Nothing when the filter is for issues.
+    ///
+    /// https://docs.github.com/en/rest/search#search-issues-and-pull-requests
+    fn list_review_requests(
+        &'a self,
+        filter: &TaskFilter,
+    ) -> LocalBoxStream<'a, Result<GhIssue, Error>>;
 }
@@ -1,26 +1,88 @@
 use crate::{
     app::{App, AppConfig},
-    app_env::AppEnv,
+    app_env::{config_dir, AppEnv},
+    cassette::Cassette,
     cli::*,
+    config::{load_api_settings, load_config, ApiSettings, Config},
+    error::ShubError,
+    github_client::GitHubClientImpl,
     github_client2::GithubClient2,
+    github_models::GhNewRepository,
+    output::Output,
+    types::{BuildRef, TaskFilter, TaskKind, TaskSort},
 };
-use anyhow::{Error, Result};
-use sekret::Secret;
-use std::{env, path::PathBuf};
+use anyhow::{bail, Error, Result};
 use tracing::debug;
 
 /// Run application.
-pub async fn start() -> Result<(), Error> {
+pub async fn start() -> Result<(), ShubError> {
     let cmd = cli();
 
-    let username = env::var("SHUB_USERNAME")?;
-    let github_token = Secret(env::var("SHUB_TOKEN")?);
-    let workspace_root_dir: PathBuf = env::var("WORKSPACE_HOME")?.into();
+    // auth commands manage the token, run them before loading the configuration that needs it
+    if let Command::Auth { cmd: auth_cmd } = &cmd.cmd {
+        let ApiSettings { api_url, proxy } =
+            load_api_settings(&config_dir()?, cmd.profile.as_deref()).map_err(ShubError::config)?;
+        let (api_url, proxy) = (api_url.as_deref(), proxy.as_deref());
+        let output = Output::new(cmd.out.clone(), cmd.format);
+        let result = match auth_cmd {
+            auth::Command::Login => crate::commands::auth::login(&output, api_url, proxy).await,
+            auth::Command::Status => {
+                crate::commands::auth::print_status(&output, api_url, proxy).await
+            }
+        };
+        let finished = output.finish();
+        result?;
+        finished?;
+        return Ok(());
+    }
+
+    // completions run on every tab, without the configuration and token of other commands
+    match &cmd.cmd {
+        Command::Completions { shell } => {
+            return Ok(crate::commands::completions::print_completions(*shell)?)
+        }
+        Command::CompleteNames { kind } => {
+            return Ok(crate::commands::completions::print_names(
+                *kind,
+                cmd.profile.as_deref(),
+            )?)
+        }
+        _ => (),
+    }
+
+    let Config {
+        profile,
+        github_username: username,
+        github_token,
+        github_api_url,
+        proxy,
+        workspace_root_dir,
+        project_roots,
+        project_depth,
+    } = load_config(&config_dir()?, cmd.profile.as_deref()).map_err(ShubError::config)?;
+    let github_api_url = github_api_url.as_deref();
+    let proxy = proxy.as_deref();
+
+    let output = Output::new(cmd.out.clone(), cmd.format);
+
+    crate::display::set_emoji_shortcodes(!cmd.no_emoji);
+    match cmd.color {
+        ColorChoice::Auto => (),
+        ColorChoice::Always => console::set_colors_enabled(true),
+        ColorChoice::Never => console::set_colors_enabled(false),
+    }
+    crate::display::check_time_settings()?;
 
     let cfg = AppConfig {
         github_username: &username,
         github_token: github_token.as_ref().map(|x| x.as_str()),
+        github_api_url,
+        proxy,
         workspace_root_dir: &workspace_root_dir,
+        project_roots: &project_roots,
+        project_depth,
+        output: &output,
+        limit: cmd.limit,
     };
 
     debug!(?cfg, ?cmd, "Starting.");
@@ -29,40 +91,527 @@ pub async fn start() -> Result<(), Error> {
 
     let app_env = {
         let token = github_token.as_ref().map(|x| x.as_str());
-        let ghc = GithubClient2::new(token)?;
-        AppEnv::new(&username, ghc)?
+        let ghc = GithubClient2::new(token, github_api_url, proxy)?;
+        AppEnv::new(
+            &username,
+            github_token.as_ref().map(|x| x.as_str()),
+            &workspace_root_dir,
+            proxy,
+            profile.as_deref(),
+            ghc,
+            cmd.dry_run,
+            &output,
+            cmd.limit,
+        )?
+    };
+
+    let result = match Cassette::from_env()? {
+        Some(cassette) => cassette.scope(run(cmd.cmd, &app, app_env)).await,
+        None => run(cmd.cmd, &app, app_env).await,
     };
 
-    match cmd.cmd {
+    // write output even when the command fails, e.g. audit reports listing violations, but
+    // report the command error over an output error
+    let finished = output.finish();
+    result?;
+    finished?;
+
+    debug!("Exit.");
+    Ok(())
+}
+
+async fn run<'a>(
+    cmd: Command,
+    app: &'a App<'a, GitHubClientImpl>,
+    app_env: AppEnv<'a>,
+) -> Result<(), Error> {
+    match cmd {
         Command::R { cmd } => match cmd {
-            repos::Command::Clone { repo } => app.clone_repository(repo).await?,
-            repos::Command::BrowseUpstream { repo } => app.browse_upstream_repository(repo).await?,
-            repos::Command::BuildStatus { repo } => app.poll_repository_build_status(repo).await?,
-            repos::Command::ViewSettings { repo } => app.view_repository_settings(repo).await?,
-            repos::Command::CopySettings { from, to } => {
-                app.copy_repository_settings(from, to).await?
+            repos::Command::Clone { repos, jobs, https } => {
+                crate::commands::clone::clone_repositories(&app_env, repos, jobs, https).await?
+            }
+            repos::Command::Fork { repo } => {
+                crate::commands::clone::fork_repository(app_env, repo).await?
+            }
+            repos::Command::Create {
+                name,
+                private,
+                description,
+                license,
+                gitignore,
+                settings_from,
+            } => {
+                let new_repo = GhNewRepository {
+                    name: &name,
+                    description: description.as_deref(),
+                    private,
+                    auto_init: license.is_some() || gitignore.is_some(),
+                    license_template: license.as_deref(),
+                    gitignore_template: gitignore.as_deref(),
+                };
+                crate::commands::clone::create_repository(app_env, new_repo, settings_from).await?
+            }
+            repos::Command::Ensure { repo } => {
+                crate::commands::clone::ensure_repository(app_env, repo).await?
+            }
+            repos::Command::Ls {
+                ty,
+                sort,
+                direction,
+                org,
+                no_commit,
+            } => {
+                crate::commands::repos::print_owned_repositories(
+                    app_env, org, ty, sort, direction, no_commit,
+                )
+                .await?
+            }
+            repos::Command::Browse {
+                repo,
+                upstream,
+                prs,
+                issues,
+                actions,
+                settings,
+            } => {
+                use crate::commands::repos::RepoPage;
+                let page = if upstream {
+                    RepoPage::Upstream
+                } else if prs {
+                    RepoPage::PullRequests
+                } else if issues {
+                    RepoPage::Issues
+                } else if actions {
+                    RepoPage::Actions
+                } else if settings {
+                    RepoPage::Settings
+                } else {
+                    RepoPage::Home
+                };
+                crate::commands::repos::browse_repository(app_env, repo, page).await?
+            }
+            repos::Command::BrowseUpstream { repo } => {
+                crate::commands::repos::browse_repository(
+                    app_env,
+                    repo,
+                    crate::commands::repos::RepoPage::Upstream,
+                )
+                .await?
+            }
+            repos::Command::BuildStatus { repo, build_ref } => {
+                app.poll_repository_build_status(repo, build_ref).await?
+            }
+            repos::Command::ViewSettings { repo } => {
+                crate::commands::settings::view_repository_settings(app_env, repo).await?
+            }
+            repos::Command::CopySettings {
+                from,
+                to,
+                all,
+                include_topics,
+            } => match to {
+                Some(to) if !all => {
+                    crate::commands::settings::copy_repository_settings(
+                        app_env,
+                        from,
+                        to,
+                        include_topics,
+                    )
+                    .await?
+                }
+                _ => {
+                    crate::commands::settings::copy_repository_settings_to_all(app_env, from)
+                        .await?
+                }
+            },
+            repos::Command::SaveProfile { repo, profile } => {
+                crate::commands::settings::save_settings_profile(app_env, repo, &profile).await?
+            }
+            repos::Command::ApplyProfile { repo, profile } => {
+                crate::commands::settings::apply_settings_profile(app_env, repo, &profile).await?
+            }
+            repos::Command::SyncLabels {
+                to,
+                from,
+                from_file,
+                prune,
+            } => {
+                crate::commands::labels::sync_labels(app_env, from, from_file.as_deref(), to, prune)
+                    .await?
+            }
+            repos::Command::Keys { cmd } => match cmd {
+                repos::KeysCommand::Ls { repo } => {
+                    crate::commands::keys::print_deploy_keys(app_env, repo).await?
+                }
+                repos::KeysCommand::Add {
+                    repo,
+                    title,
+                    key_file,
+                    write,
+                } => {
+                    crate::commands::keys::add_deploy_key(app_env, repo, &title, &key_file, write)
+                        .await?
+                }
+                repos::KeysCommand::Rm { repo, id } => {
+                    crate::commands::keys::remove_deploy_key(app_env, repo, id).await?
+                }
+            },
+            repos::Command::Hooks { cmd } => match cmd {
+                repos::HooksCommand::Ls { repo } => {
+                    crate::commands::hooks::print_hooks(app_env, repo).await?
+                }
+                repos::HooksCommand::Add {
+                    repo,
+                    url,
+                    event,
+                    secret,
+                } => crate::commands::hooks::add_hook(app_env, repo, url, &event, secret).await?,
+                repos::HooksCommand::Rm { repo, id } => {
+                    crate::commands::hooks::remove_hook(app_env, repo, id).await?
+                }
+                repos::HooksCommand::Ping { repo, id } => {
+                    crate::commands::hooks::ping_hook(app_env, repo, id).await?
+                }
+            },
+            repos::Command::Collab { cmd } => match cmd {
+                repos::CollabCommand::Ls { repo } => {
+                    crate::commands::collaborators::print_collaborators(app_env, repo).await?
+                }
+                repos::CollabCommand::Add {
+                    repo,
+                    user,
+                    permission,
+                } => {
+                    crate::commands::collaborators::add_collaborator(
+                        app_env, repo, &user, permission,
+                    )
+                    .await?
+                }
+                repos::CollabCommand::Rm { repo, user } => {
+                    crate::commands::collaborators::remove_collaborator(app_env, repo, &user)
+                        .await?
+                }
+                repos::CollabCommand::Invitations { repo } => {
+                    crate::commands::collaborators::print_invitations(app_env, repo).await?
+                }
+            },
+            repos::Command::Codeowners { repo } => {
+                crate::commands::codeowners::validate_codeowners(app_env, repo).await?
+            }
+            repos::Command::Size { repo } => {
+                crate::commands::repos::print_repository_size(app_env, repo).await?
+            }
+            repos::Command::Summary { repo } => {
+                crate::commands::repos::print_repository_summary(app_env, repo).await?
+            }
+            repos::Command::Archive { repo } => {
+                crate::commands::repos::set_repository_archived(app_env, repo, true).await?
+            }
+            repos::Command::Unarchive { repo } => {
+                crate::commands::repos::set_repository_archived(app_env, repo, false).await?
+            }
+            repos::Command::Rename { repo, new_name } => {
+                crate::commands::repos::rename_repository(app_env, repo, &new_name).await?
+            }
+            repos::Command::Delete {
+                repo,
+                yes,
+                local,
+                force,
+            } => {
+                crate::commands::repos::delete_repository(app_env, repo, yes, local, force).await?
+            }
+            repos::Command::Topics { repo, add, remove } => {
+                crate::commands::topics::edit_topics(app_env, repo, add, remove).await?
+            }
+            repos::Command::Readme { repo } => {
+                crate::commands::repos::print_readme(app_env, repo).await?
+            }
+            repos::Command::Changelog { repo } => {
+                crate::commands::repos::print_changelog(app_env, repo).await?
+            }
+            repos::Command::RerunChecks {
+                repo,
+                build_ref,
+                watch,
+            } => {
+                let dry_run = app_env.dry_run;
+                let sha =
+                    crate::commands::runs::rerun_failed_checks(app_env, repo.clone(), build_ref)
+                        .await?;
+                if let Some(sha) = sha.filter(|_| watch && !dry_run) {
+                    app.poll_repository_build_status(Some(repo), Some(BuildRef::GitRef(sha)))
+                        .await?
+                }
+            }
+            repos::Command::RunLogs {
+                repo,
+                run_id,
+                output,
+                job,
+            } => {
+                crate::commands::runs::download_run_logs(app_env, repo, run_id, output, job).await?
+            }
+            repos::Command::CleanRuns {
+                repo,
+                keep,
+                older_than,
+            } => {
+                crate::commands::runs::clean_workflow_runs(app_env, repo, keep, older_than).await?
+            }
+            repos::Command::Deps { repo } => {
+                crate::commands::deps::print_dependencies(app_env, repo).await?
+            }
+            repos::Command::Dependents { repo } => {
+                crate::commands::deps::print_dependents(app_env, repo).await?
+            }
+            repos::Command::RenameBranch { repo, old, new } => {
+                crate::commands::rename_branch::rename_branch(app_env, repo, &old, &new).await?
             }
         },
-        Command::D { update, watch } => {
-            if update {
-                crate::commands::dashboard::update_dashboard(app_env).await?
-            } else {
-                crate::commands::dashboard::print_dashboard(app_env).await?
+        Command::Auth { .. } => unreachable!("auth commands are run on start"),
+        Command::Audit { cmd } => match cmd {
+            audit::Command::Settings { baseline, profile } => {
+                crate::commands::audit::audit_settings(app_env, baseline, profile.as_deref())
+                    .await?
+            }
+            audit::Command::Policy { file } => {
+                crate::commands::audit::audit_policy(app_env, file).await?
             }
+        },
+        Command::D {
+            cmd,
+            update,
+            csv,
+            fail_only,
+            active,
+            watch,
+            interval,
+            sort,
+            columns,
+            org,
+        } => match cmd {
+            Some(dashboard::Command::Pin { repo }) => {
+                crate::commands::dashboard::pin_repository(app_env, repo, true).await?
+            }
+            Some(dashboard::Command::Unpin { repo }) => {
+                crate::commands::dashboard::pin_repository(app_env, repo, false).await?
+            }
+            Some(dashboard::Command::Ignore { repo }) => {
+                crate::commands::dashboard::ignore_repository(app_env, repo, true).await?
+            }
+            Some(dashboard::Command::Unignore { repo }) => {
+                crate::commands::dashboard::ignore_repository(app_env, repo, false).await?
+            }
+            Some(dashboard::Command::Ignored) => {
+                crate::commands::dashboard::print_ignored_repositories(app_env).await?
+            }
+            Some(dashboard::Command::History { repo }) => {
+                crate::commands::dashboard::print_build_status_history(app_env, repo).await?
+            }
+            None => {
+                let filter = crate::commands::dashboard::DashboardFilter { fail_only, active };
+                let view = crate::commands::dashboard::DashboardView { columns, sort };
+                if watch {
+                    crate::commands::dashboard::watch_dashboard(
+                        app_env,
+                        org,
+                        filter,
+                        view,
+                        interval.into(),
+                    )
+                    .await?
+                } else if update {
+                    crate::commands::dashboard::update_dashboard(app_env, org, csv, filter, view)
+                        .await?
+                } else {
+                    crate::commands::dashboard::print_dashboard(app_env, org, csv, filter, view)
+                        .await?
+                }
+            }
+        },
+        Command::Feed { cmd } => match cmd {
+            feed::Command::Following => {
+                crate::commands::feed::print_following_feed(app_env).await?
+            }
+        },
+        Command::Limits => crate::commands::limits::print_rate_limits(app_env).await?,
+        Command::N { cmd } => match cmd {
+            notifications::Command::Ls => {
+                crate::commands::notifications::print_notifications(app_env).await?
+            }
+            notifications::Command::Done { id, all } => {
+                crate::commands::notifications::mark_notifications_read(app_env, id, all).await?
+            }
+        },
+        Command::Open { target } => app.open(target).await?,
+        Command::Pr { cmd } => match cmd {
+            pulls::Command::Ls { repo } => {
+                crate::commands::pulls::print_pull_requests(app_env, repo).await?
+            }
+            pulls::Command::Create {
+                title,
+                body,
+                base,
+                draft,
+            } => {
+                crate::commands::pulls::create_pull_request(app_env, title, body, base, draft)
+                    .await?
+            }
+            pulls::Command::Checkout { repo, number } => {
+                crate::commands::pulls::checkout_pull_request(app_env, repo, number).await?
+            }
+            pulls::Command::Conflicts { repo } => {
+                crate::commands::pulls::print_pull_request_conflicts(app_env, repo).await?
+            }
+        },
+        Command::Serve { metrics, addr } => {
+            if !metrics {
+                bail!("Nothing to serve, pass --metrics.")
+            }
+            crate::commands::serve::serve_metrics(app_env, addr).await?
         }
         Command::S { cmd } => match cmd {
-            stars::Command::Ls => app.list_starred_repositories().await?,
+            stars::Command::Audit {
+                update,
+                years,
+                jobs,
+                unstar,
+            } => crate::commands::stars::audit_stars(app_env, update, years, jobs, unstar).await?,
+            stars::Command::Clone {
+                update,
+                multi,
+                jobs,
+                https,
+            } => {
+                crate::commands::stars::clone_starred_repositories(
+                    app_env, update, multi, jobs, https,
+                )
+                .await?
+            }
+            stars::Command::Pick { update, action } => {
+                crate::commands::stars::pick_star(app_env, update, action).await?
+            }
+            stars::Command::Export => app.export_starred_repositories().await?,
+            stars::Command::Import { username } => {
+                crate::commands::stars::import_stars(app_env, &username).await?
+            }
+            stars::Command::Ls {
+                jsonl,
+                cached,
+                refresh,
+            } => {
+                if cached || refresh {
+                    crate::commands::stars::print_cached_stars(app_env, refresh, jsonl).await?
+                } else {
+                    app.list_starred_repositories(jsonl).await?
+                }
+            }
+            stars::Command::Search { query } => {
+                crate::commands::stars::search_stars(app_env, &query.join(" ")).await?
+            }
+            stars::Command::Rm { repo, filter } => {
+                crate::commands::stars::unstar_repositories(app_env, repo, filter).await?
+            }
+            stars::Command::Stats { update, top } => {
+                crate::commands::stars::print_star_stats(app_env, update, top).await?
+            }
         },
         Command::T { cmd } => match cmd {
-            tasks::Command::Ls => app.list_my_tasks().await?,
+            tasks::Command::Ls {
+                prs_only,
+                issues_only,
+                repo,
+                sort,
+                board,
+            } => {
+                let kind = if prs_only {
+                    Some(TaskKind::PullRequest)
+                } else if issues_only {
+                    Some(TaskKind::Issue)
+                } else {
+                    None
+                };
+                let filter = TaskFilter {
+                    kind,
+                    repo: repo.map(|x| x.complete(app_env.github_username).to_string()),
+                    sort: sort.map(|x| match x {
+                        tasks::TaskSort::Updated => TaskSort::Updated,
+                        tasks::TaskSort::Created => TaskSort::Created,
+                    }),
+                };
+                app.list_my_tasks(filter, board).await?
+            }
+            tasks::Command::New {
+                repo,
+                title,
+                body,
+                label,
+                assignee,
+                current: _,
+            } => {
+                crate::commands::tasks::create_issue(app_env, repo, &title, body, &label, assignee)
+                    .await?
+            }
+            tasks::Command::View { issue } => {
+                crate::commands::tasks::view_issue(app_env, issue).await?
+            }
+            tasks::Command::Assign {
+                issue,
+                user,
+                unassign,
+            } => crate::commands::tasks::assign_issue(app_env, issue, user, unassign).await?,
+            tasks::Command::Label { issue, add, rm } => {
+                crate::commands::tasks::label_issue(app_env, issue, add, rm).await?
+            }
+            tasks::Command::Close { issue } => {
+                crate::commands::tasks::close_issue(app_env, issue).await?
+            }
         },
+        Command::Backup { dest, wikis, jobs } => {
+            crate::commands::backup::backup_repositories(app_env, &dest, wikis, jobs).await?
+        }
+        Command::Undo => crate::commands::undo::undo_last_mutation(app_env).await?,
         Command::W { cmd } => match cmd {
-            workspace::Command::Ls => app.list_projects().await?,
+            workspace::Command::Ls { name } => app.list_projects(name.as_deref()).await?,
             workspace::Command::Edit { name } => app.edit_project(&name).await?,
             workspace::Command::Locate { name } => app.print_project_path(&name).await?,
+            workspace::Command::Pick { action } => app.pick_project(action).await?,
+            workspace::Command::Doctor => {
+                crate::commands::doctor::check_workspace_layout(app_env).await?
+            }
+            workspace::Command::Diverged => {
+                crate::commands::workspace::print_diverged_projects(app_env).await?
+            }
+            workspace::Command::PushAll => {
+                crate::commands::workspace::push_all_projects(app_env).await?
+            }
+            workspace::Command::Languages => {
+                crate::commands::languages::print_languages(app_env).await?
+            }
+            workspace::Command::Status => {
+                crate::commands::workspace::print_projects_status(app_env).await?
+            }
+            workspace::Command::Sync { ff } => {
+                crate::commands::workspace::sync_projects(app_env, ff).await?
+            }
+            workspace::Command::CloneAll {
+                owner,
+                include_forks,
+                jobs,
+                https,
+            } => {
+                crate::commands::clone::clone_all_repositories(
+                    app_env,
+                    owner,
+                    include_forks,
+                    jobs,
+                    https,
+                )
+                .await?
+            }
         },
     };
 
-    debug!("Exit.");
     Ok(())
 }
@@ -1,9 +1,13 @@
 //! Defines application environment.
 
-use crate::{database::Database, github_client2::GithubClient2};
+use crate::{database::Database, github_client2::GithubClient2, output::Output};
 use anyhow::Error;
 use directories_next::BaseDirs;
-use std::fs;
+use sekret::Secret;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 /// File system safe application name.
 const APP_NAME: &'static str = "shub";
@@ -13,28 +17,84 @@ pub struct AppEnv<'a> {
     /// Username of current user.
     pub github_username: &'a str,
 
+    /// GitHub token, for git operations over HTTPS.
+    pub github_token: Secret<&'a str>,
+
+    /// Root directory of local projects.
+    pub workspace_root_dir: &'a Path,
+
+    /// Proxy of GitHub API and git requests.
+    pub proxy: Option<&'a str>,
+
     /// Github client.
     pub github_client: GithubClient2,
 
     pub database: Database,
+
+    /// Directory for application files, e.g. database and configs.
+    pub config_dir: PathBuf,
+
+    /// Database of the selected profile, in the config directory.
+    pub database_path: PathBuf,
+
+    /// Print instead of doing mutating operations.
+    pub dry_run: bool,
+
+    /// Destination of primary output.
+    pub output: &'a Output,
+
+    /// Maximum number of items of listings.
+    pub limit: Option<usize>,
+}
+
+/// Gets directory for application files, creating it if it doesn't exist.
+pub fn config_dir() -> Result<PathBuf, Error> {
+    let config_dir = BaseDirs::new()
+        .map(|x| x.config_dir().to_owned())
+        .map(|x| x.join(APP_NAME))
+        .expect("failed to get config dir");
+    fs::create_dir_all(&config_dir)?;
+    Ok(config_dir)
+}
+
+/// Gets path of the database of a profile, profiles don't share cached GitHub data and mutations.
+pub fn database_path(config_dir: &Path, profile: Option<&str>) -> PathBuf {
+    match profile {
+        Some(x) => config_dir.join(format!("{APP_NAME}-{x}.db")),
+        None => config_dir.join(format!("{APP_NAME}.db")),
+    }
 }
 
 impl<'a> AppEnv<'a> {
     /// Creates application environment.
-    pub fn new(github_username: &'a str, github_client: GithubClient2) -> Result<Self, Error> {
-        let config_dir = BaseDirs::new()
-            .map(|x| x.config_dir().to_owned())
-            .map(|x| x.join(APP_NAME))
-            .expect("failed to get config dir");
-        fs::create_dir_all(&config_dir)?;
-        let db = {
-            let path = config_dir.join("shub.db");
-            crate::database::Database::new(&path)?
-        };
+    pub fn new(
+        github_username: &'a str,
+        github_token: Secret<&'a str>,
+        workspace_root_dir: &'a Path,
+        proxy: Option<&'a str>,
+        profile: Option<&str>,
+        github_client: GithubClient2,
+        dry_run: bool,
+        output: &'a Output,
+        limit: Option<usize>,
+    ) -> Result<Self, Error> {
+        let config_dir = config_dir()?;
+        let database_path = database_path(&config_dir, profile);
+        let db = crate::database::Database::new(&database_path)?;
+        // opened after the database is migrated
+        let cache = crate::database::HttpCache::new(&database_path)?;
         Ok(Self {
             github_username,
-            github_client,
+            github_token,
+            workspace_root_dir,
+            proxy,
+            github_client: github_client.with_dry_run(dry_run).with_cache(cache),
             database: db,
+            config_dir,
+            database_path,
+            dry_run,
+            output,
+            limit,
         })
     }
 }
@@ -0,0 +1,373 @@
+//! Defines cassettes, GitHub API interactions recorded to a JSON file and replayed in place of
+//! GitHub.
+//!
+//! With `SHUB_CASSETTE` set to a file, responses of GitHub are recorded to it. With
+//! `SHUB_CASSETTE_MODE=replay` too, they're replayed from it instead of sending the requests,
+//! e.g. to reproduce a bug report offline. Tests replay cassettes to exercise pagination and
+//! rate limit handling without network access.
+//!
+//! Bodies are recorded as base64, they may be binary, e.g. archives of workflow run logs.
+
+use anyhow::{Context, Error};
+use http::{header, StatusCode};
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+tokio::task_local! {
+    static CASSETTE: Arc<Cassette>;
+}
+
+/// Request and its response.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct Interaction {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, with = "base64_body")]
+    pub body: Vec<u8>,
+}
+
+mod base64_body {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(body: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(body))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        base64::decode(text).map_err(de::Error::custom)
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum CassetteMode {
+    Record,
+    Replay,
+}
+
+/// Interactions being recorded, or the ones left to replay.
+#[derive(Debug)]
+pub struct Cassette {
+    mode: CassetteMode,
+    /// File the recorded interactions are written to.
+    path: Option<PathBuf>,
+    interactions: Mutex<Vec<Interaction>>,
+}
+
+impl Cassette {
+    /// Creates a cassette recording interactions to a file, replacing its content.
+    pub fn record(path: &Path) -> Self {
+        Self {
+            mode: CassetteMode::Record,
+            path: Some(path.to_owned()),
+            interactions: Default::default(),
+        }
+    }
+
+    /// Creates a cassette replaying interactions, each one is replayed once in the given order.
+    pub fn replay(interactions: Vec<Interaction>) -> Self {
+        Self {
+            mode: CassetteMode::Replay,
+            path: None,
+            interactions: Mutex::new(interactions),
+        }
+    }
+
+    /// Creates a cassette replaying interactions recorded to a file.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read cassette {}.", path.display()))?;
+        let interactions = serde_json::from_str(&text)
+            .with_context(|| format!("Failed to parse cassette {}.", path.display()))?;
+        Ok(Self::replay(interactions))
+    }
+
+    /// Creates a cassette following `SHUB_CASSETTE` and `SHUB_CASSETTE_MODE`, `None` when
+    /// there's no cassette.
+    pub fn from_env() -> Result<Option<Self>, Error> {
+        let path = match env::var_os("SHUB_CASSETTE") {
+            Some(x) => PathBuf::from(x),
+            None => return Ok(None),
+        };
+        let cassette = match env::var("SHUB_CASSETTE_MODE").as_deref() {
+            Ok("replay") => Self::load(&path)?,
+            Ok("record") | Err(_) => Self::record(&path),
+            Ok(x) => anyhow::bail!(
+                "Unknown cassette mode `{}`, expected `record` or `replay`.",
+                x
+            ),
+        };
+        Ok(Some(cassette))
+    }
+
+    /// Runs a future with the cassette, requests to GitHub made by the future go through it.
+    pub async fn scope<F: Future>(self, f: F) -> F::Output {
+        CASSETTE.scope(Arc::new(self), f).await
+    }
+
+    /// Returns the cassette of the current task.
+    pub(crate) fn current() -> Option<Arc<Self>> {
+        CASSETTE.try_with(Arc::clone).ok()
+    }
+
+    pub(crate) fn is_replaying(&self) -> bool {
+        self.mode == CassetteMode::Replay
+    }
+
+    /// Returns the response of the first interaction left matching the request.
+    ///
+    /// Without one, the response is an error response naming the request.
+    pub(crate) fn replay_response(&self, method: &Method, url: &Url) -> reqwest::Response {
+        let interaction = {
+            let mut interactions = self.interactions.lock().expect("cassette lock poisoned");
+            interactions
+                .iter()
+                .position(|x| x.method == method.as_str() && x.url == url.as_str())
+                .map(|idx| interactions.remove(idx))
+        };
+        let interaction = interaction.unwrap_or_else(|| Interaction {
+            method: method.to_string(),
+            url: url.to_string(),
+            status: StatusCode::NOT_IMPLEMENTED.as_u16(),
+            headers: [(
+                header::CONTENT_TYPE.to_string(),
+                "application/json".to_owned(),
+            )]
+            .into(),
+            body: json!({
+                "message": format!("No recorded response for {method} {url}"),
+                "documentation_url": "",
+            })
+            .to_string()
+            .into_bytes(),
+        });
+        let mut builder = http::Response::builder().status(interaction.status);
+        for (name, value) in &interaction.headers {
+            builder = builder.header(name, value);
+        }
+        builder
+            .body(interaction.body)
+            .expect("recorded headers should be valid")
+            .into()
+    }
+
+    /// Records a response and writes the recorded interactions, returns a response with the
+    /// same body.
+    pub(crate) async fn record_response(
+        &self,
+        method: &Method,
+        url: &Url,
+        response: reqwest::Response,
+    ) -> Result<reqwest::Response, Error> {
+        let status = response.status();
+        let headers: BTreeMap<_, _> = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_owned())))
+            .collect();
+        let body = response.bytes().await?;
+        let interaction = Interaction {
+            method: method.to_string(),
+            url: url.to_string(),
+            status: status.as_u16(),
+            headers: headers.clone(),
+            body: body.to_vec(),
+        };
+
+        let text = {
+            let mut interactions = self.interactions.lock().expect("cassette lock poisoned");
+            interactions.push(interaction);
+            serde_json::to_string_pretty(&*interactions)?
+        };
+        if let Some(path) = &self.path {
+            fs::write(path, text)
+                .with_context(|| format!("Failed to write cassette {}.", path.display()))?;
+        }
+
+        let mut builder = http::Response::builder().status(status);
+        for (name, value) in &headers {
+            builder = builder.header(name, value);
+        }
+        Ok(builder.body(body.to_vec())?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        github_client2::GithubClient2,
+        github_models::{GhDeployKey, GhWorkflowRun},
+        repository_id::FullRepoId,
+    };
+    use futures::TryStreamExt;
+    use sekret::Secret;
+
+    const API_URL: &str = "https://api.github.test/";
+
+    fn interaction(url: &str, status: u16, headers: &[(&str, &str)], body: &str) -> Interaction {
+        Interaction {
+            method: "GET".to_owned(),
+            url: format!("{API_URL}{url}"),
+            status,
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    fn deploy_key(id: u64) -> String {
+        json!({
+            "id": id,
+            "key": "ssh-ed25519 AAAA",
+            "title": format!("key {id}"),
+            "read_only": true,
+            "created_at": "2022-08-01T00:00:00Z",
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_replay_pages() {
+        let route = "repos/kafji/shub/keys?per_page=100";
+        let next = format!("<{API_URL}{route}&page=2>; rel=\"next\"");
+        let cassette = Cassette::replay(vec![
+            interaction(
+                route,
+                200,
+                &[("content-type", "application/json"), ("link", &next)],
+                &format!("[{}]", deploy_key(1)),
+            ),
+            interaction(
+                &format!("{route}&page=2"),
+                200,
+                &[("content-type", "application/json")],
+                &format!("[{}]", deploy_key(2)),
+            ),
+        ]);
+        let gh_client = GithubClient2::new(Secret("token"), Some(API_URL), None).unwrap();
+        let repo_id = FullRepoId {
+            owner: "kafji".to_owned(),
+            name: "shub".to_owned(),
+        };
+
+        let keys: Vec<GhDeployKey> = cassette
+            .scope(gh_client.list_deploy_keys(&repo_id).try_collect())
+            .await
+            .unwrap();
+        let ids: Vec<_> = keys.iter().map(|x| x.id).collect();
+        assert_eq!(ids, [1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_workflow_run_pages() {
+        let route = "repos/kafji/shub/actions/runs?per_page=100";
+        let next = format!("<{API_URL}{route}&page=2>; rel=\"next\"");
+        let runs = |id: u64| {
+            json!({
+                "total_count": 2,
+                "workflow_runs": [{
+                    "id": id,
+                    "name": "CI",
+                    "head_branch": "main",
+                    "status": "completed",
+                    "conclusion": "success",
+                    "created_at": "2022-08-01T00:00:00Z",
+                }],
+            })
+            .to_string()
+        };
+        let cassette = Cassette::replay(vec![
+            interaction(
+                route,
+                200,
+                &[("content-type", "application/json"), ("link", &next)],
+                &runs(1),
+            ),
+            interaction(
+                &format!("{route}&page=2"),
+                200,
+                &[("content-type", "application/json")],
+                &runs(2),
+            ),
+        ]);
+        let gh_client = GithubClient2::new(Secret("token"), Some(API_URL), None).unwrap();
+        let repo_id = FullRepoId {
+            owner: "kafji".to_owned(),
+            name: "shub".to_owned(),
+        };
+
+        let runs: Vec<GhWorkflowRun> = cassette
+            .scope(gh_client.list_workflow_runs(&repo_id).try_collect())
+            .await
+            .unwrap();
+        let ids: Vec<_> = runs.iter().map(|x| x.id).collect();
+        assert_eq!(ids, [1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_rate_limited() {
+        let route = "repos/kafji/shub/keys?per_page=100";
+        let cassette = Cassette::replay(vec![
+            interaction(route, 429, &[("retry-after", "0")], ""),
+            interaction(
+                route,
+                200,
+                &[("content-type", "application/json")],
+                &format!("[{}]", deploy_key(1)),
+            ),
+        ]);
+        let gh_client = GithubClient2::new(Secret("token"), Some(API_URL), None).unwrap();
+        let repo_id = FullRepoId {
+            owner: "kafji".to_owned(),
+            name: "shub".to_owned(),
+        };
+
+        let keys: Vec<GhDeployKey> = cassette
+            .scope(gh_client.list_deploy_keys(&repo_id).try_collect())
+            .await
+            .unwrap();
+        assert_eq!(keys.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_missing() {
+        let cassette = Cassette::replay(Vec::new());
+        let gh_client = GithubClient2::new(Secret("token"), Some(API_URL), None).unwrap();
+        let repo_id = FullRepoId {
+            owner: "kafji".to_owned(),
+            name: "shub".to_owned(),
+        };
+
+        let err = cassette
+            .scope(gh_client.get_latest_commit(&repo_id))
+            .await
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("No recorded response for GET"));
+    }
+
+    #[test]
+    fn test_binary_body() {
+        // a zip archive, invalid as UTF-8
+        let body = vec![0x50, 0x4b, 0x03, 0x04, 0xff, 0xfe, 0x00];
+        let mut x = interaction("repos/kafji/shub/actions/runs/1/logs", 200, &[], "");
+        x.body = body.clone();
+
+        let text = serde_json::to_string(&x).unwrap();
+        let x: Interaction = serde_json::from_str(&text).unwrap();
+        assert_eq!(x.body, body);
+    }
+}
@@ -1,24 +1,200 @@
-use crate::repository_id::PartialRepoId;
-use clap::{Parser, Subcommand};
+use crate::repository_id::{IssueId, PartialRepoId};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::{net::SocketAddr, path::PathBuf};
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about)]
 pub struct Cli {
+    /// Profile of the configuration file to use, defaults to `SHUB_PROFILE` or `default_profile`.
+    #[clap(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Print requests of mutating operations to stderr instead of sending them.
+    #[clap(long, global = true)]
+    pub dry_run: bool,
+
+    /// Write output to a file instead of stdout.
+    #[clap(long, global = true, value_name = "PATH")]
+    pub out: Option<PathBuf>,
+
+    /// Stop listings after this many items, without fetching the remaining pages.
+    #[clap(long, global = true, value_name = "N")]
+    pub limit: Option<usize>,
+
+    /// Print emoji shortcodes, e.g. `:rocket:`, as is instead of as emojis.
+    #[clap(long, global = true)]
+    pub no_emoji: bool,
+
+    /// When to print colors, auto prints them when the output is a terminal.
+    #[clap(long, global = true, value_enum, default_value = "auto")]
+    pub color: ColorChoice,
+
+    /// Format of listings, `json` and `ndjson` print them as JSON.
+    #[clap(long, global = true, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+
     #[clap(subcommand)]
     pub cmd: Command,
 }
 
+/// Names completed dynamically.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum CompletionNames {
+    /// Names of local projects.
+    Projects,
+    /// Cached repositories, as `:owner/:name`.
+    Repos,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+/// Format of listings.
+#[derive(ValueEnum, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum OutputFormat {
+    /// Human readable table.
+    Table,
+    /// A JSON array.
+    Json,
+    /// A JSON object per line.
+    Ndjson,
+    /// Comma separated values with a header row, for listings of flat records.
+    Csv,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Command {
+    /// Audit related operations.
+    Audit {
+        #[clap(subcommand)]
+        cmd: audit::Command,
+    },
+
+    /// GitHub credentials related operations.
+    Auth {
+        #[clap(subcommand)]
+        cmd: auth::Command,
+    },
+
+    /// Create or update bare mirror clones of owned repositories.
+    ///
+    /// Mirrors are cloned over HTTPS with the GitHub token, to run without prompts, e.g. from
+    /// cron. Exits with an error when a mirror fails.
+    Backup {
+        /// Directory of the mirrors, as `:owner/:name.git`.
+        #[clap(long, value_name = "DIR")]
+        dest: PathBuf,
+
+        /// Mirror wikis too, as `:owner/:name.wiki.git`.
+        #[clap(long)]
+        wikis: bool,
+
+        /// Number of repositories to mirror at the same time.
+        #[clap(long, short('j'), default_value_t = 4)]
+        jobs: usize,
+    },
+
+    /// Print shell completion script.
+    ///
+    /// Bash and fish scripts also complete project names of `w` commands and repositories of `r`
+    /// commands, from the cache of the dashboard and stars.
+    Completions {
+        /// Shell to complete for.
+        #[clap(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// Print names completed by the completion script.
+    #[clap(hide = true)]
+    CompleteNames {
+        #[clap(value_enum)]
+        kind: CompletionNames,
+    },
+
     /// Print dashboard.
     D {
+        #[clap(subcommand)]
+        cmd: Option<dashboard::Command>,
+
         /// Update dashboard before printing it.
         #[clap(long)]
         update: bool,
 
-        /// Watch repository build statuses.
-        #[clap(long, short('w'))]
+        /// Print dashboard as CSV, including columns hidden in the terminal view.
+        #[clap(long)]
+        csv: bool,
+
+        /// Print only repositories whose build failed.
+        #[clap(long)]
+        fail_only: bool,
+
+        /// Also print repositories whose build is in progress, with `--fail-only`.
+        #[clap(long, requires = "fail_only")]
+        active: bool,
+
+        /// Watch repository build statuses, refreshing them until interrupted.
+        #[clap(long, short('w'), conflicts_with = "csv")]
         watch: bool,
+
+        /// Time between refreshes with `--watch`, e.g. `30s`.
+        #[clap(
+            long,
+            requires = "watch",
+            default_value = "1m",
+            value_name = "DURATION"
+        )]
+        interval: humantime::Duration,
+
+        /// Order repositories by a column instead of pinned first.
+        #[clap(long, value_enum)]
+        sort: Option<dashboard::DashboardSort>,
+
+        /// Columns to print after the repository name, separated by commas.
+        #[clap(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            default_value = "build",
+            value_name = "COLUMNS"
+        )]
+        columns: Vec<dashboard::DashboardColumn>,
+
+        /// Print dashboard of repositories of an organization instead of owned ones.
+        #[clap(long, value_name = "NAME")]
+        org: Option<String>,
+    },
+
+    /// Feed related operations.
+    Feed {
+        #[clap(subcommand)]
+        cmd: feed::Command,
+    },
+
+    /// Print GitHub API rate limits.
+    Limits,
+
+    /// Notifications related operations.
+    N {
+        #[clap(subcommand)]
+        cmd: notifications::Command,
+    },
+
+    /// Open repository page, issue page, or project editor depending on the target.
+    Open {
+        /// `:owner?/:name#:number` for an issue, `:owner/:name` for a repository, or a project
+        /// name. Defaults to the repository of the current directory.
+        target: Option<String>,
+    },
+
+    /// Pull requests related operations.
+    #[clap(visible_alias = "prs")]
+    Pr {
+        #[clap(subcommand)]
+        cmd: pulls::Command,
     },
 
     /// Repository related operations.
@@ -27,6 +203,17 @@ pub enum Command {
         cmd: repos::Command,
     },
 
+    /// Serve repository metrics over HTTP.
+    Serve {
+        /// Expose dashboard repositories metrics for Prometheus at `/metrics`.
+        #[clap(long)]
+        metrics: bool,
+
+        /// Address to listen on.
+        #[clap(long, default_value = "127.0.0.1:9184")]
+        addr: SocketAddr,
+    },
+
     /// Stars related operations.
     S {
         #[clap(subcommand)]
@@ -39,6 +226,9 @@ pub enum Command {
         cmd: tasks::Command,
     },
 
+    /// Undo the last mutation, stopping at one that can not be undone.
+    Undo,
+
     /// Workspace related operations.
     W {
         #[clap(subcommand)]
@@ -46,19 +236,298 @@ pub enum Command {
     },
 }
 
+pub mod audit {
+    use super::*;
+
+    #[derive(Subcommand, Debug)]
+    pub enum Command {
+        /// Print settings of owned repositories, marking the ones that deviate, failing when any
+        /// deviates.
+        Settings {
+            /// Repository to compare against, defaults to the settings most repositories have.
+            #[clap(long)]
+            baseline: Option<PartialRepoId>,
+
+            /// Settings profile to compare against.
+            #[clap(long, conflicts_with = "baseline")]
+            profile: Option<String>,
+        },
+
+        /// Check owned repositories against a policy file.
+        Policy {
+            /// Path to the policy file, defaults to `policy.toml` in the config directory.
+            #[clap(long)]
+            file: Option<PathBuf>,
+        },
+    }
+}
+
+pub mod auth {
+    use super::*;
+
+    #[derive(Subcommand, Debug)]
+    pub enum Command {
+        /// Verify a GitHub token and store it in the OS keyring.
+        Login,
+
+        /// Print where the GitHub token comes from and whose it is.
+        Status,
+    }
+}
+
+pub mod dashboard {
+    use super::*;
+
+    #[derive(Subcommand, Debug)]
+    pub enum Command {
+        /// Pin a repository to the top of the dashboard.
+        Pin {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// Unpin a repository.
+        Unpin {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// Exclude a repository from the dashboard.
+        Ignore {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// Include an ignored repository in the dashboard again.
+        Unignore {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// List repositories excluded from the dashboard.
+        Ignored,
+
+        /// Print recent build status changes of a repository.
+        History {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+    }
+
+    /// Column of the dashboard after the repository name.
+    #[derive(ValueEnum, PartialEq, Eq, Clone, Copy, Debug)]
+    pub enum DashboardColumn {
+        /// Build status of the default branch.
+        Build,
+        /// Open pull requests count.
+        Prs,
+        /// Count of branches not merged to the default branch.
+        Branches,
+        /// Success rate of the last finished builds.
+        Rate,
+    }
+
+    /// Order of the dashboard.
+    #[derive(ValueEnum, Clone, Copy, Debug)]
+    pub enum DashboardSort {
+        Name,
+        /// Failed builds first.
+        Build,
+        /// Most open pull requests first.
+        Prs,
+        /// Most unmerged branches first.
+        Branches,
+    }
+}
+
+pub mod feed {
+    use super::*;
+
+    #[derive(Subcommand, Debug)]
+    pub enum Command {
+        /// Print recent releases and new repositories of users I follow.
+        Following,
+    }
+}
+
+pub mod notifications {
+    use super::*;
+
+    #[derive(Subcommand, Debug)]
+    pub enum Command {
+        /// Print unread notifications.
+        Ls,
+
+        /// Mark a notification thread as read.
+        Done {
+            /// Thread id, as printed by `n ls`.
+            #[clap(required_unless_present = "all", conflicts_with = "all")]
+            id: Option<String>,
+
+            /// Mark all notifications as read.
+            #[clap(long)]
+            all: bool,
+        },
+    }
+}
+
+pub mod pulls {
+    use super::*;
+
+    #[derive(Subcommand, Debug)]
+    pub enum Command {
+        /// Print open pull requests with their author, age, and CI status.
+        Ls {
+            /// Repository identifier, defaults to the repository of the current directory.
+            repo: Option<PartialRepoId>,
+        },
+
+        /// Create a pull request from the current branch, pushing it first if needed.
+        Create {
+            /// Pull request title, edited in `$SHUB_EDITOR` with the body when not given.
+            #[clap(long, short('t'))]
+            title: Option<String>,
+
+            /// Pull request body.
+            #[clap(long, short('b'))]
+            body: Option<String>,
+
+            /// Branch to merge into, defaults to the default branch.
+            #[clap(long)]
+            base: Option<String>,
+
+            /// Create the pull request as a draft.
+            #[clap(long)]
+            draft: bool,
+        },
+
+        /// Fetch a pull request into the local clone and check it out as a new branch.
+        Checkout {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// Pull request number.
+            number: u64,
+        },
+
+        /// Print open pull requests and whether they can be merged.
+        Conflicts {
+            /// Repository identifier.
+            repo: Option<PartialRepoId>,
+        },
+    }
+}
+
 pub mod repos {
     use super::*;
+    use crate::types::BuildRef;
 
     #[derive(Subcommand, Debug)]
     pub enum Command {
-        /// Browse upstream repository of a fork.
+        /// Open a page of a repository in the browser.
+        Browse {
+            /// Repository identifier. Defaults to the repository of the current directory.
+            repo: Option<PartialRepoId>,
+
+            /// Open the upstream repository of a fork.
+            #[clap(long, group = "page")]
+            upstream: bool,
+
+            /// Open pull requests.
+            #[clap(long, group = "page")]
+            prs: bool,
+
+            /// Open issues.
+            #[clap(long, group = "page")]
+            issues: bool,
+
+            /// Open workflow runs.
+            #[clap(long, group = "page")]
+            actions: bool,
+
+            /// Open settings.
+            #[clap(long, group = "page")]
+            settings: bool,
+        },
+
+        /// Browse upstream repository of a fork, like `browse --upstream`.
         BrowseUpstream {
-            /// Repository identifier.
+            /// Repository identifier. Defaults to the repository of the current directory.
             repo: Option<PartialRepoId>,
         },
 
-        /// Clone remote repository.
+        /// Clone remote repositories.
         Clone {
+            /// Repository identifiers.
+            #[clap(required = true)]
+            repos: Vec<PartialRepoId>,
+
+            /// Number of repositories to clone at the same time.
+            #[clap(long, short('j'), default_value_t = 4)]
+            jobs: usize,
+
+            /// Clone over HTTPS with the GitHub token instead of over SSH.
+            #[clap(long)]
+            https: bool,
+        },
+
+        /// Fork a repository and clone the fork.
+        Fork {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// Create a repository and clone it.
+        Create {
+            /// Name of the repository.
+            name: String,
+
+            /// Create a private repository.
+            #[clap(long)]
+            private: bool,
+
+            /// Description of the repository.
+            #[clap(long)]
+            description: Option<String>,
+
+            /// License keyword, e.g. `mit` or `apache-2.0`.
+            #[clap(long, value_name = "KEYWORD")]
+            license: Option<String>,
+
+            /// Gitignore template, e.g. `Rust`.
+            #[clap(long, value_name = "TEMPLATE")]
+            gitignore: Option<String>,
+
+            /// Copy settings of this repository to the new repository.
+            #[clap(long, value_name = "REPO")]
+            settings_from: Option<PartialRepoId>,
+        },
+
+        /// Print owned repositories with their latest commit.
+        Ls {
+            /// Type of the repositories, defaults to owned ones.
+            #[clap(long = "type", value_enum, value_name = "TYPE")]
+            ty: Option<RepoType>,
+
+            /// Order of the repositories.
+            #[clap(long, value_enum)]
+            sort: Option<RepoSort>,
+
+            /// Direction of the order, defaults to ascending for name and descending otherwise.
+            #[clap(long, value_enum)]
+            direction: Option<SortDirection>,
+
+            /// Print repositories of an organization instead of owned ones.
+            #[clap(long, value_name = "NAME")]
+            org: Option<String>,
+
+            /// Don't fetch the latest commit of each repository.
+            #[clap(long)]
+            no_commit: bool,
+        },
+
+        /// Print path to local clone of a repository, cloning it first if necessary.
+        Ensure {
             /// Repository identifier.
             repo: PartialRepoId,
         },
@@ -67,6 +536,165 @@ pub mod repos {
         BuildStatus {
             /// Repository identifier.
             repo: Option<PartialRepoId>,
+
+            /// Branch, commit SHA, or pull request as `#N`, defaults to the latest commit of
+            /// the default branch.
+            #[clap(long = "ref", value_name = "REF")]
+            build_ref: Option<BuildRef>,
+        },
+
+        /// Print CODEOWNERS rules of a repository and report their errors.
+        Codeowners {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// Rename a branch and update local clone to follow it.
+        RenameBranch {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// Current branch name.
+            old: String,
+
+            /// New branch name.
+            new: String,
+        },
+
+        /// Print size of a repository and the largest blobs of its local clone.
+        Size {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// Print overview of a repository, its latest commit, release, and build status.
+        Summary {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// Archive a repository, making it read only.
+        Archive {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// Unarchive a repository.
+        Unarchive {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// Rename a repository, updating its local clone too.
+        Rename {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// New name of the repository.
+            new_name: String,
+        },
+
+        /// Delete a repository, after typing its full name to confirm.
+        Delete {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// Delete without confirming.
+            #[clap(long)]
+            yes: bool,
+
+            /// Delete the local clone too.
+            #[clap(long)]
+            local: bool,
+
+            /// Delete the local clone even when it has uncommitted changes, stashes, or unpushed
+            /// branches.
+            #[clap(long, requires = "local")]
+            force: bool,
+        },
+
+        /// Print topics of a repository, or add and remove its topics.
+        Topics {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// Topic to add, can be repeated.
+            #[clap(long, value_name = "TOPIC")]
+            add: Vec<String>,
+
+            /// Topic to remove, can be repeated.
+            #[clap(long, value_name = "TOPIC")]
+            remove: Vec<String>,
+        },
+
+        /// Print README of a repository.
+        Readme {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// Print changelog of a repository.
+        Changelog {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// Re-run failed jobs of the failed workflow runs of a commit.
+        RerunChecks {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// Branch, commit SHA, or pull request as `#N`, defaults to the latest commit of
+            /// the default branch.
+            #[clap(long = "ref", value_name = "REF")]
+            build_ref: Option<BuildRef>,
+
+            /// Poll build status of the commit afterwards.
+            #[clap(long)]
+            watch: bool,
+        },
+
+        /// Download logs of a workflow run and print the directory they're extracted to.
+        RunLogs {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// Workflow run id.
+            run_id: u64,
+
+            /// Directory to extract the logs to, defaults to `<name>-run-<run id>`.
+            #[clap(long, value_name = "DIR")]
+            output: Option<PathBuf>,
+
+            /// Print log of a job of the run instead.
+            #[clap(long, value_name = "ID", conflicts_with = "output")]
+            job: Option<u64>,
+        },
+
+        /// Delete completed workflow runs of a repository.
+        CleanRuns {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// Number of most recent runs to keep.
+            #[clap(long, default_value_t = 10)]
+            keep: usize,
+
+            /// Delete only runs older than this, e.g. `30days`.
+            #[clap(long, value_name = "DURATION")]
+            older_than: Option<humantime::Duration>,
+        },
+
+        /// Print dependencies of a repository from its dependency graph.
+        Deps {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// Print owned repositories depending on a repository, from their dependency graphs.
+        Dependents {
+            /// Repository identifier.
+            repo: PartialRepoId,
         },
 
         /// Print repository settings.
@@ -76,33 +704,458 @@ pub mod repos {
         },
 
         /// Copy repository settings from another repository.
+        #[clap(visible_alias = "transfer-settings")]
         CopySettings {
             /// Repository to copy the settings from.
             from: PartialRepoId,
 
             /// Repository to apply the settings to.
+            #[clap(required_unless_present = "all", conflicts_with = "all")]
+            to: Option<PartialRepoId>,
+
+            /// Apply the settings to all owned repositories that are neither forks nor archived.
+            #[clap(long)]
+            all: bool,
+
+            /// Copy topics too, replacing the topics of the target repository.
+            #[clap(long, conflicts_with = "all")]
+            include_topics: bool,
+        },
+
+        /// Save settings and topics of a repository as a settings profile in the config directory.
+        SaveProfile {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// Name of the settings profile.
+            profile: String,
+        },
+
+        /// Apply a settings profile to a repository.
+        ApplyProfile {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// Name of the settings profile.
+            profile: String,
+        },
+
+        /// Make labels of a repository match labels of another repository or of a labels file.
+        SyncLabels {
+            /// Repository to apply the labels to.
             to: PartialRepoId,
+
+            /// Repository to copy the labels from.
+            #[clap(
+                long,
+                required_unless_present = "from_file",
+                conflicts_with = "from_file"
+            )]
+            from: Option<PartialRepoId>,
+
+            /// YAML file listing labels with their name, color, and optional description.
+            #[clap(long, value_name = "PATH")]
+            from_file: Option<PathBuf>,
+
+            /// Delete labels missing from the source.
+            #[clap(long)]
+            prune: bool,
+        },
+
+        /// Deploy keys related operations.
+        Keys {
+            #[clap(subcommand)]
+            cmd: KeysCommand,
+        },
+
+        /// Webhooks related operations.
+        Hooks {
+            #[clap(subcommand)]
+            cmd: HooksCommand,
+        },
+
+        /// Collaborators related operations.
+        Collab {
+            #[clap(subcommand)]
+            cmd: CollabCommand,
+        },
+    }
+
+    #[derive(Subcommand, Debug)]
+    pub enum CollabCommand {
+        /// Print collaborators of a repository with their role.
+        Ls {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+
+        /// Invite a user to collaborate on a repository, or change the permission of a
+        /// collaborator.
+        Add {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// GitHub username.
+            user: String,
+
+            /// Permission of the user.
+            #[clap(long, value_enum, default_value = "push")]
+            permission: CollabPermission,
+        },
+
+        /// Remove a collaborator of a repository, or cancel the pending invitation of a user.
+        Rm {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// GitHub username.
+            user: String,
         },
+
+        /// Print pending invitations to collaborate on a repository.
+        Invitations {
+            /// Repository identifier.
+            repo: PartialRepoId,
+        },
+    }
+
+    #[derive(ValueEnum, Clone, Copy, Debug)]
+    pub enum CollabPermission {
+        Pull,
+        Triage,
+        Push,
+        Maintain,
+        Admin,
+    }
+
+    #[derive(Subcommand, Debug)]
+    pub enum KeysCommand {
+        /// Print deploy keys of a repository.
+        Ls {
+            /// Repository identifier, defaults to all owned repositories that are neither forks
+            /// nor archived.
+            repo: Option<PartialRepoId>,
+        },
+
+        /// Add a deploy key to a repository.
+        Add {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// Title of the key.
+            title: String,
+
+            /// Public key file, e.g. `id_ed25519.pub`.
+            key_file: PathBuf,
+
+            /// Allow the key to push.
+            #[clap(long)]
+            write: bool,
+        },
+
+        /// Remove a deploy key from a repository.
+        Rm {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// Deploy key id.
+            id: u64,
+        },
+    }
+
+    #[derive(Subcommand, Debug)]
+    pub enum HooksCommand {
+        /// Print webhooks of a repository with the status of their last delivery.
+        Ls {
+            /// Repository identifier, defaults to all owned repositories that are neither forks
+            /// nor archived.
+            repo: Option<PartialRepoId>,
+        },
+
+        /// Add a webhook delivering events of a repository as JSON.
+        Add {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// URL the events are delivered to.
+            url: String,
+
+            /// Event to deliver, can be repeated.
+            #[clap(long, value_name = "EVENT", default_value = "push")]
+            event: Vec<String>,
+
+            /// Secret the deliveries are signed with.
+            #[clap(long)]
+            secret: Option<String>,
+        },
+
+        /// Remove a webhook from a repository.
+        Rm {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// Webhook id.
+            id: u64,
+        },
+
+        /// Send a ping event to a webhook.
+        Ping {
+            /// Repository identifier.
+            repo: PartialRepoId,
+
+            /// Webhook id.
+            id: u64,
+        },
+    }
+
+    #[derive(ValueEnum, Clone, Copy, Debug)]
+    pub enum RepoSort {
+        Pushed,
+        Created,
+        Name,
+        Size,
+    }
+
+    #[derive(ValueEnum, Clone, Copy, Debug)]
+    pub enum RepoType {
+        All,
+        /// Repositories owned by me, or all repositories of the organization with `--org`.
+        Owner,
+        Public,
+        Private,
+        /// Repositories I'm a collaborator or an organization member of.
+        Member,
+    }
+
+    #[derive(ValueEnum, Clone, Copy, Debug)]
+    pub enum SortDirection {
+        Asc,
+        Desc,
     }
 }
 
 pub mod stars {
     use super::*;
+    pub use crate::commands::stars::{StarFilter, DEFAULT_AUDIT_YEARS, DEFAULT_STATS_TOP};
 
     #[derive(Subcommand, Debug)]
     pub enum Command {
+        /// Print starred repositories that are archived, deleted, or inactive, and optionally
+        /// unstar them.
+        Audit {
+            /// Refresh cached starred repositories first.
+            #[clap(long)]
+            update: bool,
+
+            /// Flag repositories not pushed in this many years.
+            #[clap(long, value_name = "N", default_value_t = DEFAULT_AUDIT_YEARS)]
+            years: u32,
+
+            /// Number of repositories to check at the same time.
+            #[clap(long, short('j'), default_value_t = 8)]
+            jobs: usize,
+
+            /// Pick flagged repositories to unstar.
+            #[clap(long)]
+            unstar: bool,
+        },
+
+        /// Pick starred repositories and clone them.
+        Clone {
+            /// Refresh cached starred repositories before picking.
+            #[clap(long)]
+            update: bool,
+
+            /// Pick more than one repository.
+            #[clap(long)]
+            multi: bool,
+
+            /// Number of repositories to clone at the same time.
+            #[clap(long, short('j'), default_value_t = 4)]
+            jobs: usize,
+
+            /// Clone over HTTPS with the GitHub token instead of over SSH.
+            #[clap(long)]
+            https: bool,
+        },
+
+        /// Pick a starred repository and print its URL or open it.
+        Pick {
+            /// Refresh cached starred repositories before picking.
+            #[clap(long)]
+            update: bool,
+
+            /// What to do with the picked repository.
+            #[clap(long, value_enum, default_value = "print")]
+            action: StarAction,
+        },
+
+        /// Export starred repositories as CSV, or as JSON with `--format json` or `ndjson`.
+        Export,
+
+        /// Star repositories starred by another user that I haven't starred yet.
+        Import {
+            /// GitHub username to import the stars of.
+            username: String,
+        },
+
         /// Print starred repositories.
-        Ls,
+        Ls {
+            /// Print each repository as a JSON object on its own line as it arrives, same as
+            /// `--format ndjson`.
+            #[clap(long)]
+            jsonl: bool,
+
+            /// Print cached starred repositories instead of fetching them.
+            #[clap(long)]
+            cached: bool,
+
+            /// Refresh cached starred repositories and print them.
+            #[clap(long)]
+            refresh: bool,
+        },
+
+        /// Unstar a repository, or the starred repositories matching a filter.
+        Rm {
+            /// Repository identifier.
+            #[clap(required_unless_present = "filter", conflicts_with = "filter")]
+            repo: Option<PartialRepoId>,
+
+            /// Unstar repositories that are `archived`, or not pushed in N years with
+            /// `inactive:N`.
+            #[clap(long)]
+            filter: Option<StarFilter>,
+        },
+
+        /// Search cached starred repositories by name, description, language, and topics.
+        Search {
+            /// Words to search for, every one of them must match.
+            #[clap(required = true)]
+            query: Vec<String>,
+        },
+
+        /// Print starred repositories counts by language, by topic, and by star age.
+        Stats {
+            /// Refresh cached starred repositories first.
+            #[clap(long)]
+            update: bool,
+
+            /// Number of languages and topics to print, the rest are counted as other.
+            #[clap(long, value_name = "N", default_value_t = DEFAULT_STATS_TOP)]
+            top: usize,
+        },
+    }
+
+    /// Action on a picked starred repository.
+    #[derive(ValueEnum, Clone, Copy, Debug)]
+    pub enum StarAction {
+        /// Print its URL.
+        Print,
+        /// Open it in the browser.
+        Open,
     }
 }
 
 pub mod tasks {
     use super::*;
 
+    #[derive(ValueEnum, Clone, Copy, Debug)]
+    pub enum TaskSort {
+        Updated,
+        Created,
+    }
+
     #[derive(Subcommand, Debug)]
     pub enum Command {
-        /// Print issues and pull requests assigned to me.
-        Ls,
+        /// Print issues and pull requests assigned to me, and pull requests requesting my review.
+        Ls {
+            /// Print only pull requests.
+            #[clap(long, conflicts_with = "issues_only")]
+            prs_only: bool,
+
+            /// Print only issues.
+            #[clap(long)]
+            issues_only: bool,
+
+            /// Print only the ones of this repository.
+            #[clap(long)]
+            repo: Option<PartialRepoId>,
+
+            /// Order of the tasks, defaults to best match.
+            #[clap(long, value_enum)]
+            sort: Option<TaskSort>,
+
+            /// Group the tasks by repository, with their labels and age.
+            #[clap(long)]
+            board: bool,
+        },
+
+        /// Create an issue, editing its body in `$SHUB_EDITOR` when it's not given.
+        #[clap(allow_missing_positional = true)]
+        New {
+            /// Repository identifier.
+            #[clap(required_unless_present = "current", conflicts_with = "current")]
+            repo: Option<PartialRepoId>,
+
+            /// Issue title.
+            title: String,
+
+            /// Issue body.
+            #[clap(long)]
+            body: Option<String>,
+
+            /// Label to add.
+            #[clap(long, value_name = "LABEL")]
+            label: Vec<String>,
+
+            /// User to assign, `me` for me.
+            #[clap(long, value_name = "USER")]
+            assignee: Option<String>,
+
+            /// Create it in the repository of the current directory.
+            #[clap(long)]
+            current: bool,
+        },
+
+        /// Print an issue or a pull request.
+        View {
+            /// Issue identifier, i.e. `:owner?/:name#:number`.
+            issue: IssueId,
+        },
+
+        /// Assign an issue or a pull request to a user.
+        Assign {
+            /// Issue identifier, i.e. `:owner?/:name#:number`.
+            issue: IssueId,
+
+            /// User to assign, defaults to me.
+            user: Option<String>,
+
+            /// Remove the user from the assignees instead.
+            #[clap(long)]
+            unassign: bool,
+        },
+
+        /// Add or remove labels of an issue or a pull request, picking them interactively when
+        /// neither is given.
+        Label {
+            /// Issue identifier, i.e. `:owner?/:name#:number`.
+            issue: IssueId,
+
+            /// Label to add.
+            #[clap(long, value_name = "LABEL")]
+            add: Vec<String>,
+
+            /// Label to remove.
+            #[clap(long, value_name = "LABEL")]
+            rm: Vec<String>,
+        },
+
+        /// Close an issue or a pull request.
+        Close {
+            /// Issue identifier, i.e. `:owner?/:name#:number`.
+            issue: IssueId,
+        },
     }
 }
 
@@ -112,19 +1165,78 @@ pub mod workspace {
     #[derive(Subcommand, Debug)]
     pub enum Command {
         /// Print local projects.
-        Ls,
+        Ls {
+            /// Print only projects matching a name.
+            name: Option<String>,
+        },
 
         /// Open editor to a project.
         Edit {
-            /// Project name.
+            /// Project name, or a part of it.
             name: String,
         },
 
         /// Print path to a project.
         Locate {
-            /// Project name.
+            /// Project name, or a part of it.
             name: String,
         },
+
+        /// Pick a project and print its path or open editor to it.
+        Pick {
+            /// What to do with the picked project.
+            #[clap(long, value_enum, default_value = "print")]
+            action: ProjectAction,
+        },
+
+        /// Check environment, local projects layout, and remotes.
+        Doctor,
+
+        /// Print local projects whose default branch differs from origin's.
+        Diverged,
+
+        /// Push current branch of local projects that have unpushed commits.
+        PushAll,
+
+        /// Print files and lines counts per language of local projects.
+        Languages,
+
+        /// Print current branch, dirty files count, and ahead/behind upstream of local projects.
+        Status,
+
+        /// Fetch origin and upstream of local projects, pruning deleted branches.
+        Sync {
+            /// Fast-forward the default branch when it has no local commits.
+            #[clap(long)]
+            ff: bool,
+        },
+
+        /// Clone all owned repositories that are not cloned yet.
+        CloneAll {
+            /// Organization to clone the repositories of instead of the current user.
+            owner: Option<String>,
+
+            /// Clone forks too.
+            #[clap(long)]
+            include_forks: bool,
+
+            /// Number of repositories to clone at the same time.
+            #[clap(long, short('j'), default_value_t = 4)]
+            jobs: usize,
+
+            /// Clone over HTTPS with the GitHub token instead of over SSH.
+            #[clap(long)]
+            https: bool,
+        },
+    }
+
+    /// Action on a picked project.
+    #[derive(ValueEnum, Clone, Copy, Debug)]
+    pub enum ProjectAction {
+        /// Print its path.
+        Print,
+        /// Open `SHUB_EDITOR` to it.
+        Edit,
     }
 }
 
@@ -0,0 +1,332 @@
+use crate::{
+    app_env::AppEnv,
+    commands::settings::{read_settings_profile, ExtractRepositorySettings, RepositorySettings},
+    github_models::GhBranchProtection,
+    repository_id::PartialRepoId,
+    types::Repository,
+};
+use anyhow::{bail, Context, Error};
+use futures::{future, TryStreamExt};
+use serde::Deserialize;
+use std::{fs, io::Write, path::PathBuf};
+use tabwriter::TabWriter;
+
+/// Prints settings of owned repositories, marking values that differ from the baseline.
+///
+/// The baseline is the settings of the given repository or settings profile, or the settings
+/// most repositories have when there is none. Fails when any repository deviates, so scheduled
+/// runs can alert about it.
+pub async fn audit_settings(
+    app_env: AppEnv<'_>,
+    baseline: Option<PartialRepoId>,
+    profile: Option<&str>,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let gh_client = &app_env.github_client;
+
+    // merge settings are not included in the listing, fetch each repository for them
+    let mut settings = gh_client
+        .list_owned_repositories()
+        .try_filter(|x| future::ready(!x.archived.unwrap_or_default()))
+        .and_then(|x| future::ready(Repository::try_from(x)))
+        .map_ok(|x| async move {
+            let settings = gh_client
+                .get_repository(&x)
+                .await?
+                .extract_repository_settings()?;
+            Result::<_, Error>::Ok((x.name, settings))
+        })
+        .try_buffered(4)
+        .try_collect::<Vec<_>>()
+        .await?;
+    settings.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let (baseline_name, baseline) = match (baseline, profile) {
+        (Some(repo_id), _) => {
+            let repo_id = repo_id.complete(app_env.github_username);
+            let settings = gh_client
+                .get_repository(&repo_id)
+                .await?
+                .extract_repository_settings()?;
+            (repo_id.to_string(), settings.to_values())
+        }
+        (None, Some(name)) => {
+            let profile = read_settings_profile(&app_env.config_dir, name)?;
+            (format!("({name})"), profile.settings.to_values())
+        }
+        (None, None) => ("(majority)".to_owned(), majority(&settings)),
+    };
+
+    let mut w = TabWriter::new(Vec::new());
+    writeln!(w, "repository\t{}", RepositorySettings::KEYS.join("\t"))?;
+    let baseline_cells: Vec<_> = baseline.iter().map(|x| x.to_string()).collect();
+    writeln!(w, "{}\t{}", baseline_name, baseline_cells.join("\t"))?;
+
+    let mut deviations = 0;
+    for (name, settings) in &settings {
+        let mut deviates = false;
+        let cells: Vec<_> = settings
+            .to_values()
+            .into_iter()
+            .zip(baseline)
+            .map(|(x, y)| {
+                if x == y {
+                    x.to_string()
+                } else {
+                    deviates = true;
+                    format!("{x}*")
+                }
+            })
+            .collect();
+        if deviates {
+            deviations += 1;
+        }
+        writeln!(w, "{}\t{}", name, cells.join("\t"))?;
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    let summary = format!(
+        "{deviations} of {} repositories deviate from {baseline_name}.",
+        settings.len()
+    );
+    if deviations > 0 {
+        bail!("{summary}")
+    }
+    writeln!(out, "\n{summary}")?;
+
+    Ok(())
+}
+
+/// Finds the value most repositories have for each setting, ties go to `true`.
+fn majority(settings: &[(String, RepositorySettings)]) -> [bool; 5] {
+    let mut trues = [0; 5];
+    for (_, x) in settings {
+        for (idx, value) in x.to_values().into_iter().enumerate() {
+            if value {
+                trues[idx] += 1;
+            }
+        }
+    }
+    trues.map(|x| x * 2 >= settings.len())
+}
+
+/// Checks owned repositories against a policy file.
+///
+/// Fails when any of the repositories violates the policy.
+pub async fn audit_policy(app_env: AppEnv<'_>, path: Option<PathBuf>) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let path = path.unwrap_or_else(|| app_env.config_dir.join("policy.toml"));
+    let policy: Policy = {
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read policy file {}.", path.display()))?;
+        toml::from_str(&text)?
+    };
+    let policy = &policy;
+    let gh_client = &app_env.github_client;
+
+    let mut results = gh_client
+        .list_owned_repositories()
+        .try_filter(|x| future::ready(!x.archived.unwrap_or_default()))
+        .and_then(|x| future::ready(Repository::try_from(x)))
+        .map_ok(|x| async move {
+            let repo = gh_client.get_repository(&x).await?;
+            let mut violations = check_settings(policy, &repo.extract_repository_settings()?);
+            violations.extend(check_topics(
+                policy,
+                repo.topics.as_deref().unwrap_or_default(),
+            ));
+            if let Some(bp_policy) = &policy.branch_protection {
+                let branch = repo.default_branch.as_deref().unwrap_or("main");
+                let protection = gh_client.get_branch_protection(&x, branch).await?;
+                violations.extend(check_branch_protection(
+                    bp_policy,
+                    branch,
+                    protection.as_ref(),
+                ));
+            }
+            Result::<_, Error>::Ok((x.name, violations))
+        })
+        .try_buffered(4)
+        .try_collect::<Vec<_>>()
+        .await?;
+    results.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut count = 0;
+    for (name, violations) in &results {
+        for violation in violations {
+            writeln!(out, "{name}: {violation}")?;
+            count += 1;
+        }
+    }
+
+    if count > 0 {
+        bail!("Found {count} policy violation(s).")
+    }
+    writeln!(
+        out,
+        "All {} repositories comply with the policy.",
+        results.len()
+    )?;
+    Ok(())
+}
+
+/// Repository policy.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct Policy {
+    /// Topics every repository must have.
+    topics: Vec<String>,
+
+    /// Required repository settings, unset keys are not checked.
+    settings: SettingsPolicy,
+
+    /// Required protection of the default branch, not checked when unset.
+    branch_protection: Option<BranchProtectionPolicy>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct SettingsPolicy {
+    allow_rebase_merge: Option<bool>,
+    allow_squash_merge: Option<bool>,
+    allow_auto_merge: Option<bool>,
+    delete_branch_on_merge: Option<bool>,
+    allow_merge_commit: Option<bool>,
+}
+
+impl SettingsPolicy {
+    /// Returns values of the settings, in the same order as [RepositorySettings::KEYS].
+    fn to_values(&self) -> [Option<bool>; 5] {
+        [
+            self.allow_rebase_merge,
+            self.allow_squash_merge,
+            self.allow_auto_merge,
+            self.delete_branch_on_merge,
+            self.allow_merge_commit,
+        ]
+    }
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct BranchProtectionPolicy {
+    required_approving_review_count: u32,
+    required_status_checks: Vec<String>,
+    strict_status_checks: bool,
+    enforce_admins: bool,
+}
+
+fn check_settings(policy: &Policy, settings: &RepositorySettings) -> Vec<String> {
+    RepositorySettings::KEYS
+        .iter()
+        .zip(policy.settings.to_values())
+        .zip(settings.to_values())
+        .filter_map(|((key, expected), actual)| match expected {
+            Some(expected) if expected != actual => {
+                Some(format!("`{key}` is {actual}, expected {expected}"))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn check_topics(policy: &Policy, topics: &[String]) -> Vec<String> {
+    policy
+        .topics
+        .iter()
+        .filter(|x| !topics.contains(x))
+        .map(|x| format!("missing topic `{x}`"))
+        .collect()
+}
+
+fn check_branch_protection(
+    policy: &BranchProtectionPolicy,
+    branch: &str,
+    protection: Option<&GhBranchProtection>,
+) -> Vec<String> {
+    let protection = match protection {
+        Some(x) => x,
+        None => return vec![format!("branch `{branch}` is not protected")],
+    };
+
+    let mut violations = Vec::new();
+
+    let review_count = protection
+        .required_pull_request_reviews
+        .as_ref()
+        .map(|x| x.required_approving_review_count)
+        .unwrap_or_default();
+    if review_count < policy.required_approving_review_count {
+        violations.push(format!(
+            "branch `{branch}` requires {review_count} approving review(s), expected at least {}",
+            policy.required_approving_review_count
+        ));
+    }
+
+    let checks = protection.required_status_checks.as_ref();
+    for context in &policy.required_status_checks {
+        let required = checks
+            .map(|x| x.contexts.contains(context))
+            .unwrap_or_default();
+        if !required {
+            violations.push(format!(
+                "branch `{branch}` does not require status check `{context}`"
+            ));
+        }
+    }
+    if policy.strict_status_checks && !checks.map(|x| x.strict).unwrap_or_default() {
+        violations.push(format!(
+            "branch `{branch}` does not require to be up to date before merging"
+        ));
+    }
+
+    let admins_enforced = protection
+        .enforce_admins
+        .as_ref()
+        .map(|x| x.enabled)
+        .unwrap_or_default();
+    if policy.enforce_admins && !admins_enforced {
+        violations.push(format!(
+            "branch `{branch}` protection is not enforced for administrators"
+        ));
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_settings() {
+        let policy: Policy = toml::from_str(
+            "
+            [settings]
+            allow_merge_commit = false
+            delete_branch_on_merge = true
+            ",
+        )
+        .unwrap();
+        let settings = RepositorySettings {
+            allow_rebase_merge: true,
+            allow_squash_merge: true,
+            allow_auto_merge: false,
+            delete_branch_on_merge: true,
+            allow_merge_commit: true,
+        };
+        assert_eq!(
+            check_settings(&policy, &settings),
+            ["`allow_merge_commit` is true, expected false"]
+        );
+    }
+
+    #[test]
+    fn test_check_unprotected_branch() {
+        let policy = BranchProtectionPolicy::default();
+        assert_eq!(
+            check_branch_protection(&policy, "main", None),
+            ["branch `main` is not protected"]
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{github_client2::GithubClient2, output::Output, token};
+use anyhow::{Context, Error};
+use dialoguer::Password;
+use sekret::Secret;
+use std::io::Write;
+
+/// Prompts for a GitHub token, verifies it, and stores it in the OS keyring.
+pub async fn login(
+    mut out: &Output,
+    api_url: Option<&str>,
+    proxy: Option<&str>,
+) -> Result<(), Error> {
+    let token = Password::new().with_prompt("GitHub token").interact()?;
+    let token = token.trim();
+    let host = token::api_host(api_url)?;
+
+    let client = GithubClient2::new(Secret(token), api_url, proxy)?;
+    let user = client
+        .get_current_user()
+        .await
+        .context("Failed to verify the token.")?;
+    token::store_keyring_token(&host, token)?;
+
+    writeln!(
+        out,
+        "Logged in as {}, the token is stored in the keyring.",
+        user.login
+    )?;
+    if let Ok((_, source)) = token::find_github_token(&host) {
+        if source != token::TokenSource::Keyring {
+            writeln!(
+                out,
+                "Note that the token from {source} takes precedence over the keyring."
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the source of the GitHub token and the user it belongs to.
+pub async fn print_status(
+    mut out: &Output,
+    api_url: Option<&str>,
+    proxy: Option<&str>,
+) -> Result<(), Error> {
+    let (token, source) = token::find_github_token(&token::api_host(api_url)?)?;
+
+    let client = GithubClient2::new(Secret(token.as_str()), api_url, proxy)?;
+    let user = client
+        .get_current_user()
+        .await
+        .with_context(|| format!("Token from {source} is not valid."))?;
+
+    writeln!(
+        out,
+        "Logged in as {} with the token from {source}.",
+        user.login
+    )?;
+
+    Ok(())
+}
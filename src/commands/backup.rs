@@ -0,0 +1,245 @@
+use crate::{
+    app::create_fetch_options_with_token,
+    app_env::AppEnv,
+    display::RelativeTime,
+    github_models::GhRepository,
+    repository_id::{FullRepoId, IsPartialRepositoryId},
+};
+use anyhow::{bail, Context, Error};
+use chrono::{DateTime, Utc};
+use futures::{future, stream, StreamExt, TryStreamExt};
+use git2::{build::RepoBuilder, FetchPrune};
+use serde::Serialize;
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tabwriter::TabWriter;
+use tokio::task;
+
+/// Refspec of mirrors, every ref of the remote as is.
+const MIRROR_REFSPEC: &str = "+refs/*:refs/*";
+
+/// Outcome of backing up a repository.
+#[derive(Serialize, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupStatus {
+    Created,
+    Updated,
+    /// A wiki that was not found, GitHub has no wiki repository until its first page.
+    Skipped,
+    Failed,
+}
+
+/// Result of backing up a repository or a wiki.
+#[derive(Serialize, Debug)]
+pub struct BackupRecord {
+    pub repository: String,
+    pub status: BackupStatus,
+    /// Time of the last successful backup.
+    pub last_backup: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+/// A mirror to create or update.
+struct Target {
+    repo_id: FullRepoId,
+    url: String,
+    path: PathBuf,
+    wiki: bool,
+}
+
+/// Creates or updates bare mirror clones of owned repositories, and of their wikis when `wikis`
+/// is set, running up to `jobs` mirrors at the same time.
+///
+/// Progress is printed to stderr, the results are printed as records. Failed mirrors do not
+/// stop the others, but make it return an error at the end.
+pub async fn backup_repositories(
+    app_env: AppEnv<'_>,
+    dest: &Path,
+    wikis: bool,
+    jobs: usize,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let username = app_env.github_username;
+
+    let repos: Vec<GhRepository> = app_env
+        .github_client
+        .list_owned_repositories()
+        .try_filter(|x| future::ready(x.owner().unwrap_or(username) == username))
+        .try_collect()
+        .await?;
+    let mut targets = Vec::new();
+    for repo in &repos {
+        targets.extend(mirror_targets(dest, repo, username, wikis)?);
+    }
+
+    if app_env.dry_run {
+        for x in &targets {
+            eprintln!("[dry-run] mirror {} to {}", x.url, x.path.display());
+        }
+        return Ok(());
+    }
+
+    let last_backups = app_env.database.get_backed_up_at()?;
+    let token = app_env.github_token.0.to_owned();
+    let proxy = app_env.proxy.map(ToOwned::to_owned);
+    let total = targets.len();
+    let mut results = stream::iter(targets)
+        .map(|x| {
+            let token = token.clone();
+            let proxy = proxy.clone();
+            async move {
+                let Target {
+                    repo_id,
+                    url,
+                    path,
+                    wiki,
+                } = x;
+                let result =
+                    task::spawn_blocking(move || mirror(&url, &path, &token, proxy.as_deref()))
+                        .await;
+                (repo_id, wiki, result)
+            }
+        })
+        .buffer_unordered(jobs.max(1));
+
+    let mut done = 0;
+    let mut records = Vec::new();
+    while let Some((repo_id, wiki, result)) = results.next().await {
+        done += 1;
+        let result = result.map_err(Error::from).and_then(|x| x);
+        let last_backup = last_backups.get(&repo_id.to_string()).copied();
+        let (status, last_backup, error) = match result {
+            Ok(status) => {
+                let backed_up_at = Utc::now();
+                app_env.database.set_backed_up(&repo_id, backed_up_at)?;
+                (status, Some(backed_up_at), None)
+            }
+            Err(err) if wiki && is_not_found(&err) => {
+                (BackupStatus::Skipped, last_backup, Some(format!("{err:#}")))
+            }
+            Err(err) => (BackupStatus::Failed, last_backup, Some(format!("{err:#}"))),
+        };
+        eprintln!("[{done}/{total}] {repo_id}: {status:?}");
+        records.push(BackupRecord {
+            last_backup,
+            repository: repo_id.to_string(),
+            status,
+            error,
+        });
+    }
+    records.sort_by(|a, b| a.repository.cmp(&b.repository));
+
+    if !app_env.output.write_records(&records)? {
+        let mut w = TabWriter::new(Vec::new());
+        for x in &records {
+            writeln!(
+                w,
+                "{}\t{:?}\t{}\t{}",
+                x.repository,
+                x.status,
+                x.last_backup
+                    .map(|x| x.since().to_string())
+                    .unwrap_or_default(),
+                x.error.as_deref().unwrap_or_default()
+            )?;
+        }
+        write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+    }
+
+    let count = |status| records.iter().filter(|x| x.status == status).count();
+    let failed = count(BackupStatus::Failed);
+    eprintln!(
+        "Backed up {total} repositories: {} created, {} updated, {} skipped, {failed} failed.",
+        count(BackupStatus::Created),
+        count(BackupStatus::Updated),
+        count(BackupStatus::Skipped),
+    );
+    if failed > 0 {
+        bail!("Failed to back up {failed} of {total} repositories.")
+    }
+
+    Ok(())
+}
+
+/// Gets mirrors of a repository, and of its wiki when `wikis` is set and it has one.
+fn mirror_targets(
+    dest: &Path,
+    repo: &GhRepository,
+    username: &str,
+    wikis: bool,
+) -> Result<Vec<Target>, Error> {
+    let owner = repo.owner().unwrap_or(username);
+    let url = repo
+        .clone_url
+        .as_ref()
+        .map(ToString::to_string)
+        .with_context(|| {
+            format!(
+                "Expecting {owner}/{} to have HTTPS url, but was not.",
+                repo.name
+            )
+        })?;
+
+    let mut targets = Vec::new();
+    if wikis && repo.has_wiki.unwrap_or_default() {
+        let name = format!("{}.wiki", repo.name);
+        targets.push(Target {
+            url: format!("{}.wiki.git", url.trim_end_matches(".git")),
+            path: dest.join(owner).join(format!("{name}.git")),
+            repo_id: FullRepoId {
+                owner: owner.to_owned(),
+                name,
+            },
+            wiki: true,
+        });
+    }
+    targets.push(Target {
+        path: dest.join(owner).join(format!("{}.git", repo.name)),
+        repo_id: FullRepoId {
+            owner: owner.to_owned(),
+            name: repo.name.clone(),
+        },
+        url,
+        wiki: false,
+    });
+    Ok(targets)
+}
+
+/// Returns true if the error is a missing remote repository.
+fn is_not_found(err: &Error) -> bool {
+    match err.chain().find_map(|x| x.downcast_ref::<git2::Error>()) {
+        // libgit2 reports the status of a failed HTTP request only in its message
+        Some(err) => {
+            err.code() == git2::ErrorCode::NotFound
+                || (err.class() == git2::ErrorClass::Http && err.message().ends_with("404"))
+        }
+        None => false,
+    }
+}
+
+/// Creates a bare mirror clone of a repository, or fetches every ref of it into an existing
+/// mirror, pruning deleted refs.
+fn mirror(url: &str, path: &Path, token: &str, proxy: Option<&str>) -> Result<BackupStatus, Error> {
+    let mut options = create_fetch_options_with_token(Some(token), proxy);
+    options.prune(FetchPrune::On);
+
+    if path.exists() {
+        let repo = git2::Repository::open_bare(path)
+            .with_context(|| format!("Failed to open mirror {}.", path.display()))?;
+        repo.find_remote("origin")?
+            .fetch(&[MIRROR_REFSPEC], Some(&mut options), None)
+            .context("Failed to fetch mirror.")?;
+        return Ok(BackupStatus::Updated);
+    }
+
+    let repo = RepoBuilder::new()
+        .bare(true)
+        .fetch_options(options)
+        .remote_create(|repo, name, url| repo.remote_with_fetch(name, url, MIRROR_REFSPEC))
+        .clone(url, path)
+        .context("Failed to clone mirror.")?;
+    repo.config()?.set_bool("remote.origin.mirror", true)?;
+    Ok(BackupStatus::Created)
+}
@@ -0,0 +1,289 @@
+use crate::{
+    app::clone_to,
+    app_env::AppEnv,
+    commands::undo::record_not_undoable,
+    create_local_repository_path,
+    github_models::{GhNewRepository, GhRepository},
+    repository_id::{FullRepoId, IsPartialRepositoryId, PartialRepoId},
+};
+use anyhow::{bail, Context, Error};
+use futures::{future, stream, StreamExt, TryStreamExt};
+use std::{io::Write, path::PathBuf, time::Duration};
+use tokio::{task, time};
+
+/// Number of times to check whether a new fork is ready before giving up.
+const FORK_READY_ATTEMPTS: u32 = 30;
+
+/// Interval between checks of whether a new fork is ready.
+const FORK_READY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Forks a repository, waits for the fork to be ready, and clones it with the forked repository
+/// as its `upstream` remote.
+pub async fn fork_repository(mut app_env: AppEnv<'_>, repo_id: PartialRepoId) -> Result<(), Error> {
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let fork = match app_env.github_client.create_fork(&repo_id).await? {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let fork_id = FullRepoId {
+        owner: fork
+            .owner
+            .map(|x| x.login)
+            .unwrap_or_else(|| app_env.github_username.to_owned()),
+        name: fork.name,
+    };
+    record_not_undoable(&mut app_env.database, &fork_id.to_string(), "fork")?;
+    eprintln!("Forked {repo_id} to {fork_id}, waiting for it to be ready.");
+
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match app_env.github_client.get_latest_commit(&fork_id).await {
+            Ok(Some(_)) => break,
+            Ok(None) | Err(_) if attempts < FORK_READY_ATTEMPTS => {
+                time::sleep(FORK_READY_INTERVAL).await
+            }
+            Ok(None) => bail!("Fork {fork_id} has no commits."),
+            Err(err) => return Err(err.context(format!("Fork {fork_id} is not ready."))),
+        }
+    }
+
+    match clone_repository(&app_env, &fork_id, false).await? {
+        Some(path) => eprintln!("Cloned {fork_id} to {}.", path.display()),
+        None => eprintln!("Skipped {fork_id}, already cloned."),
+    }
+
+    Ok(())
+}
+
+/// Creates a repository and clones it, then copies settings of a template repository to it
+/// when given one.
+pub async fn create_repository(
+    mut app_env: AppEnv<'_>,
+    new_repo: GhNewRepository<'_>,
+    settings_from: Option<PartialRepoId>,
+) -> Result<(), Error> {
+    let repo = match app_env.github_client.create_repository(&new_repo).await? {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let repo_id = FullRepoId {
+        owner: repo
+            .owner
+            .map(|x| x.login)
+            .unwrap_or_else(|| app_env.github_username.to_owned()),
+        name: repo.name,
+    };
+    record_not_undoable(
+        &mut app_env.database,
+        &repo_id.to_string(),
+        "repository creation",
+    )?;
+    eprintln!("Created {repo_id}.");
+
+    match clone_repository(&app_env, &repo_id, false).await? {
+        Some(path) => eprintln!("Cloned {repo_id} to {}.", path.display()),
+        None => eprintln!("Skipped {repo_id}, already cloned."),
+    }
+
+    if let Some(from) = settings_from {
+        super::settings::copy_repository_settings(
+            app_env,
+            from,
+            PartialRepoId {
+                owner: Some(repo_id.owner),
+                name: repo_id.name,
+            },
+            false,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Clones all repositories of the current user, or of an organization, that are not cloned yet.
+///
+/// Archived repositories are included, forks are included only when `include_forks` is set.
+pub async fn clone_all_repositories(
+    app_env: AppEnv<'_>,
+    owner: Option<String>,
+    include_forks: bool,
+    jobs: usize,
+    https: bool,
+) -> Result<(), Error> {
+    let username = app_env.github_username;
+    let repos = match owner.as_deref() {
+        None => app_env.github_client.list_owned_repositories().boxed(),
+        Some(x) if x == username => app_env.github_client.list_owned_repositories().boxed(),
+        Some(org) => app_env
+            .github_client
+            .list_organization_repositories(org)
+            .boxed(),
+    };
+    let owner = owner.as_deref().unwrap_or(username);
+    let repo_ids: Vec<_> = repos
+        .try_filter(|x| {
+            let owned = x.owner().unwrap_or(owner) == owner;
+            let a_fork = x.fork.unwrap_or_default();
+            future::ready(owned && (include_forks || !a_fork))
+        })
+        .map_ok(|x| PartialRepoId {
+            owner: Some(owner.to_owned()),
+            name: x.name,
+        })
+        // skip cloned ones here to not report them as skipped
+        .try_filter(|x| {
+            let repo_id = x.clone().complete(username);
+            let path = create_local_repository_path(app_env.workspace_root_dir, &repo_id);
+            future::ready(!path.exists())
+        })
+        .try_collect()
+        .await?;
+
+    if repo_ids.is_empty() {
+        eprintln!("All repositories of {owner} are already cloned.");
+        return Ok(());
+    }
+
+    clone_repositories(&app_env, repo_ids, jobs, https).await
+}
+
+/// Clones repositories, running up to `jobs` clones at the same time.
+///
+/// Repositories that already have a local clone are skipped. Progress is printed to stderr as
+/// clones finish, failed clones do not stop the others.
+pub async fn clone_repositories(
+    app_env: &AppEnv<'_>,
+    repo_ids: Vec<PartialRepoId>,
+    jobs: usize,
+    https: bool,
+) -> Result<(), Error> {
+    let total = repo_ids.len();
+    let mut results = stream::iter(repo_ids)
+        .map(|x| {
+            let repo_id = x.complete(app_env.github_username);
+            async move {
+                let result = clone_repository(app_env, &repo_id, https).await;
+                (repo_id, result)
+            }
+        })
+        .buffer_unordered(jobs.max(1));
+
+    let mut done = 0;
+    let mut failed = Vec::new();
+    while let Some((repo_id, result)) = results.next().await {
+        done += 1;
+        match result {
+            Ok(Some(path)) => {
+                eprintln!("[{done}/{total}] Cloned {repo_id} to {}.", path.display())
+            }
+            Ok(None) => eprintln!("[{done}/{total}] Skipped {repo_id}, already cloned."),
+            Err(err) => {
+                eprintln!("[{done}/{total}] Failed to clone {repo_id}: {err:#}");
+                failed.push(repo_id);
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        let failed: Vec<_> = failed.iter().map(|x| x.to_string()).collect();
+        bail!(
+            "Failed to clone {} of {} repositories: {}.",
+            failed.len(),
+            total,
+            failed.join(", ")
+        )
+    }
+
+    Ok(())
+}
+
+/// Prints path to the local clone of a repository, cloning it first if there is none.
+pub async fn ensure_repository(app_env: AppEnv<'_>, repo_id: PartialRepoId) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let path = create_local_repository_path(app_env.workspace_root_dir, &repo_id);
+    if !path.exists() {
+        // status goes to stderr to keep stdout clean for the path
+        eprintln!("Cloning {repo_id} repository to {}.", path.display());
+        clone_repository(&app_env, &repo_id, false).await?;
+    }
+    writeln!(out, "{}", path.display())?;
+
+    Ok(())
+}
+
+/// Clones a repository on a blocking thread, returns `None` if it's already cloned.
+///
+/// Clones over SSH unless `https` is set, falling back to HTTPS when SSH authentication fails,
+/// e.g. without an SSH agent.
+async fn clone_repository(
+    app_env: &AppEnv<'_>,
+    repo_id: &FullRepoId,
+    https: bool,
+) -> Result<Option<PathBuf>, Error> {
+    let path = create_local_repository_path(app_env.workspace_root_dir, repo_id);
+    if path.exists() {
+        return Ok(None);
+    }
+
+    let repo = app_env.github_client.get_repository(repo_id).await?;
+
+    if app_env.dry_run {
+        let (url, _) = clone_urls(&repo, https)?;
+        eprintln!("[dry-run] clone {url} to {}", path.display());
+        return Ok(Some(path));
+    }
+    let clone = |https: bool| {
+        let urls = clone_urls(&repo, https);
+        let path = path.clone();
+        let token = https.then(|| app_env.github_token.0.to_owned());
+        let proxy = app_env.proxy.map(ToOwned::to_owned);
+        task::spawn_blocking(move || {
+            let (url, upstream_url) = urls?;
+            clone_to(
+                &url,
+                upstream_url.as_deref(),
+                &path,
+                token.as_deref(),
+                proxy.as_deref(),
+            )?;
+            Result::<_, Error>::Ok(())
+        })
+    };
+    match clone(https).await? {
+        Err(err) if !https && is_ssh_auth_error(&err) => {
+            eprintln!("Failed to clone {repo_id} over SSH, cloning over HTTPS: {err:#}");
+            clone(true).await??
+        }
+        result => result?,
+    }
+    Ok(Some(path))
+}
+
+/// Gets URLs of a repository and of its upstream if it's a fork, over HTTPS or SSH.
+fn clone_urls(repo: &GhRepository, https: bool) -> Result<(String, Option<String>), Error> {
+    let transport = if https { "HTTPS" } else { "SSH" };
+    let url_of = |x: &GhRepository| {
+        let url = if https {
+            x.clone_url.as_ref().map(ToString::to_string)
+        } else {
+            x.ssh_url.clone()
+        };
+        url.with_context(|| format!("Expecting repository to have {transport} url, but was not."))
+    };
+    let url = url_of(repo)?;
+    let upstream_url = repo.parent.as_deref().map(url_of).transpose()?;
+    Ok((url, upstream_url))
+}
+
+/// Returns true if the error is a failed SSH authentication, or a missing SSH agent.
+fn is_ssh_auth_error(err: &Error) -> bool {
+    match err.downcast_ref::<git2::Error>() {
+        Some(err) => err.code() == git2::ErrorCode::Auth || err.class() == git2::ErrorClass::Ssh,
+        None => false,
+    }
+}
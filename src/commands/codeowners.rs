@@ -0,0 +1,225 @@
+use crate::{app_env::AppEnv, repository_id::PartialRepoId};
+use anyhow::{bail, Error};
+use std::io::Write;
+use tabwriter::TabWriter;
+use tracing::warn;
+
+/// Locations where GitHub looks for the CODEOWNERS file, in order of precedence.
+const CODEOWNERS_PATHS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// Prints CODEOWNERS rules of a repository and reports their errors and patterns matching no
+/// files.
+pub async fn validate_codeowners(app_env: AppEnv<'_>, repo_id: PartialRepoId) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+    let gh_client = &app_env.github_client;
+
+    let mut found = None;
+    for path in CODEOWNERS_PATHS {
+        if let Some(content) = gh_client.get_file_content(&repo_id, path).await? {
+            found = Some((path, content));
+            break;
+        }
+    }
+    let (path, content) = match found {
+        Some(x) => x,
+        None => bail!("Repository {repo_id} does not have a CODEOWNERS file."),
+    };
+    let content = String::from_utf8_lossy(&content);
+
+    writeln!(out, "{path}\n")?;
+    let rules = parse_codeowners(&content);
+    let mut w = TabWriter::new(Vec::new());
+    for rule in &rules {
+        writeln!(
+            w,
+            "{}\t{}\t{}",
+            rule.line,
+            rule.pattern,
+            rule.owners.join(" ")
+        )?;
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    let tree = gh_client.get_tree(&repo_id).await?;
+    if tree.truncated {
+        warn!("tree of {repo_id} is truncated, patterns may match files left out");
+    }
+    let files: Vec<_> = tree
+        .tree
+        .iter()
+        .filter(|x| x.ty == "blob")
+        .map(|x| x.path.as_str())
+        .collect();
+    let unmatched: Vec<_> = rules
+        .iter()
+        .filter(|x| !files.iter().any(|file| pattern_matches(x.pattern, file)))
+        .collect();
+    if !unmatched.is_empty() {
+        writeln!(out)?;
+        for rule in &unmatched {
+            writeln!(
+                out,
+                "{path}:{}: pattern `{}` matches no files",
+                rule.line, rule.pattern
+            )?;
+        }
+    }
+
+    let errors = gh_client.list_codeowners_errors(&repo_id).await?;
+    if errors.is_empty() && unmatched.is_empty() {
+        return Ok(());
+    }
+    if !errors.is_empty() {
+        writeln!(out)?;
+    }
+    for err in &errors {
+        writeln!(
+            out,
+            "{}:{}:{}: {}",
+            err.path, err.line, err.column, err.kind
+        )?;
+        writeln!(out, "    {}", err.source.trim_end())?;
+        if let Some(suggestion) = &err.suggestion {
+            writeln!(out, "    {suggestion}")?;
+        }
+    }
+    bail!(
+        "CODEOWNERS has {} error(s) and {} pattern(s) matching no files.",
+        errors.len(),
+        unmatched.len()
+    )
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct CodeownersRule<'a> {
+    line: usize,
+    pattern: &'a str,
+    owners: Vec<&'a str>,
+}
+
+/// Parses CODEOWNERS rules, skipping blank lines and comments.
+///
+/// Comments start at a `#` that isn't escaped as `\#`.
+fn parse_codeowners(text: &str) -> Vec<CodeownersRule<'_>> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let line_num = idx + 1;
+            let comment = line
+                .char_indices()
+                .find(|&(idx, c)| c == '#' && !line[..idx].ends_with('\\'))
+                .map(|(idx, _)| idx);
+            let line = match comment {
+                Some(x) => &line[..x],
+                None => line,
+            };
+            let mut words = line.split_whitespace();
+            let pattern = words.next()?;
+            let owners = words.collect();
+            Some(CodeownersRule {
+                line: line_num,
+                pattern,
+                owners,
+            })
+        })
+        .collect()
+}
+
+/// Returns true if a CODEOWNERS pattern matches a file, following gitignore rules.
+///
+/// A pattern matching a directory matches files in it.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    // a slash at the start or middle anchors the pattern to the root
+    let anchored = pattern.trim_end_matches('/').contains('/');
+    let dir_only = pattern.ends_with('/');
+    let mut pattern: Vec<_> = pattern
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .split('/')
+        .collect();
+    if !anchored {
+        pattern.insert(0, "**");
+    }
+    let path: Vec<_> = path.split('/').collect();
+    (1..=path.len())
+        .filter(|&n| !dir_only || n < path.len())
+        .any(|n| segments_match(&pattern, &path[..n]))
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|idx| segments_match(rest, &path[idx..])),
+        Some((x, rest)) => match path.split_first() {
+            Some((y, path)) => {
+                let x: Vec<_> = x.chars().collect();
+                let y: Vec<_> = y.chars().collect();
+                segment_matches(&x, &y) && segments_match(rest, path)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Matches a path segment with `*` and `?` wildcards, `\` escapes the next character.
+fn segment_matches(pattern: &[char], segment: &[char]) -> bool {
+    match pattern.split_first() {
+        None => segment.is_empty(),
+        Some(('*', rest)) => (0..=segment.len()).any(|idx| segment_matches(rest, &segment[idx..])),
+        Some(('?', rest)) => !segment.is_empty() && segment_matches(rest, &segment[1..]),
+        Some(('\\', [x, rest @ ..])) => {
+            segment.first() == Some(x) && segment_matches(rest, &segment[1..])
+        }
+        Some((x, rest)) => segment.first() == Some(x) && segment_matches(rest, &segment[1..]),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_pattern_matches() {
+    assert!(pattern_matches("*", "src/main.rs"));
+    assert!(pattern_matches("*.rs", "src/main.rs"));
+    assert!(!pattern_matches("*.rs", "README.md"));
+    assert!(pattern_matches("/docs/", "docs/guide/intro.md"));
+    assert!(!pattern_matches("/docs/", "src/docs/intro.md"));
+    assert!(pattern_matches("docs/", "src/docs/intro.md"));
+    assert!(!pattern_matches("docs/", "docs"));
+    assert!(pattern_matches("src/*.rs", "src/main.rs"));
+    assert!(!pattern_matches("src/*.rs", "lib/src/main.rs"));
+    assert!(pattern_matches("**/logs", "build/logs/today.log"));
+    assert!(pattern_matches("apps/**/test.rs", "apps/a/b/test.rs"));
+    assert!(pattern_matches("README.md", "docs/README.md"));
+    assert!(pattern_matches("\\#notes", "#notes"));
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_codeowners() {
+    let text = "# default owners\n*       @kafji\n\n/docs/  @kafji @octocat # docs\n/tmp/\n\\#notes @kafji\n";
+    assert_eq!(
+        parse_codeowners(text),
+        [
+            CodeownersRule {
+                line: 2,
+                pattern: "*",
+                owners: vec!["@kafji"],
+            },
+            CodeownersRule {
+                line: 4,
+                pattern: "/docs/",
+                owners: vec!["@kafji", "@octocat"],
+            },
+            CodeownersRule {
+                line: 5,
+                pattern: "/tmp/",
+                owners: vec![],
+            },
+            CodeownersRule {
+                line: 6,
+                pattern: "\\#notes",
+                owners: vec!["@kafji"],
+            },
+        ]
+    );
+}
@@ -0,0 +1,163 @@
+use crate::{
+    app_env::AppEnv, cli::repos::CollabPermission, commands::undo::record_not_undoable,
+    display::RelativeTime, repository_id::PartialRepoId,
+};
+use anyhow::Error;
+use futures::TryStreamExt;
+use std::io::Write;
+use tabwriter::TabWriter;
+
+/// Prints collaborators of a repository with their role.
+pub async fn print_collaborators(app_env: AppEnv<'_>, repo_id: PartialRepoId) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let collaborators: Vec<_> = app_env
+        .github_client
+        .list_collaborators(&repo_id)
+        .try_collect()
+        .await?;
+    if collaborators.is_empty() {
+        writeln!(out, "No collaborators of {repo_id}.")?;
+        return Ok(());
+    }
+
+    let mut w = TabWriter::new(Vec::new());
+    for x in &collaborators {
+        writeln!(
+            w,
+            "{}\t{}",
+            x.login,
+            x.role_name.as_deref().unwrap_or_default()
+        )?;
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    Ok(())
+}
+
+/// Prints pending invitations to collaborate on a repository.
+pub async fn print_invitations(app_env: AppEnv<'_>, repo_id: PartialRepoId) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let invitations: Vec<_> = app_env
+        .github_client
+        .list_repository_invitations(&repo_id)
+        .try_collect()
+        .await?;
+    if invitations.is_empty() {
+        writeln!(out, "No pending invitations to {repo_id}.")?;
+        return Ok(());
+    }
+
+    let mut w = TabWriter::new(Vec::new());
+    for x in &invitations {
+        let invitee = x
+            .invitee
+            .as_ref()
+            .map(|x| x.login.as_str())
+            .unwrap_or_default();
+        writeln!(
+            w,
+            "{}\t{}\t{}\t{}",
+            x.id,
+            invitee,
+            x.permissions,
+            x.created_at.since()
+        )?;
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    Ok(())
+}
+
+/// Invites a user to collaborate on a repository, or changes the permission of a collaborator.
+pub async fn add_collaborator(
+    mut app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    username: &str,
+    permission: CollabPermission,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let permission = match permission {
+        CollabPermission::Pull => "pull",
+        CollabPermission::Triage => "triage",
+        CollabPermission::Push => "push",
+        CollabPermission::Maintain => "maintain",
+        CollabPermission::Admin => "admin",
+    };
+    let invitation = app_env
+        .github_client
+        .add_collaborator(&repo_id, username, permission)
+        .await?;
+    if app_env.dry_run {
+        return Ok(());
+    }
+    record_not_undoable(
+        &mut app_env.database,
+        &repo_id.to_string(),
+        "collaborator addition",
+    )?;
+    match invitation {
+        Some(x) => writeln!(out, "Invited {username} to {repo_id}, invitation {}.", x.id)?,
+        None => writeln!(
+            out,
+            "Set permission of {username} on {repo_id} to {permission}."
+        )?,
+    }
+
+    Ok(())
+}
+
+/// Removes a collaborator of a repository, or cancels the pending invitation of the user.
+pub async fn remove_collaborator(
+    mut app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    username: &str,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+    let gh_client = &app_env.github_client;
+
+    let invitations: Vec<_> = gh_client
+        .list_repository_invitations(&repo_id)
+        .try_collect()
+        .await?;
+    let invitation = invitations.into_iter().find(|x| {
+        x.invitee
+            .as_ref()
+            .map(|x| x.login.eq_ignore_ascii_case(username))
+            .unwrap_or_default()
+    });
+    match invitation {
+        Some(x) => {
+            gh_client
+                .delete_repository_invitation(&repo_id, x.id)
+                .await?;
+            if !app_env.dry_run {
+                record_not_undoable(
+                    &mut app_env.database,
+                    &repo_id.to_string(),
+                    "invitation cancellation",
+                )?;
+                writeln!(out, "Canceled invitation of {username} to {repo_id}.")?;
+            }
+        }
+        None => {
+            gh_client.remove_collaborator(&repo_id, username).await?;
+            if !app_env.dry_run {
+                record_not_undoable(
+                    &mut app_env.database,
+                    &repo_id.to_string(),
+                    "collaborator removal",
+                )?;
+                writeln!(out, "Removed {username} from {repo_id}.")?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use crate::{
+    app_env::{config_dir, database_path},
+    cli::{Cli, CompletionNames},
+    config::{load_profile_name, load_project_dirs, ProjectDirs},
+    database::Database,
+    find_project_paths,
+};
+use anyhow::Error;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use std::io::{self, Write};
+use tokio::task;
+
+const BIN_NAME: &str = "shub";
+
+/// `w` subcommands taking a project name.
+const PROJECT_SUBCOMMANDS: &[&str] = &["edit", "locate", "ls"];
+
+/// Prints completion script of a shell.
+///
+/// Scripts of bash and fish complete project names and repositories by calling
+/// `shub complete-names`.
+pub fn print_completions(shell: Shell) -> Result<(), Error> {
+    let mut out = io::stdout().lock();
+    clap_complete::generate(shell, &mut Cli::command(), BIN_NAME, &mut out);
+    let repo_subcommands = repo_subcommands();
+    match shell {
+        Shell::Bash => write!(out, "{}", bash_dynamic_completions(&repo_subcommands))?,
+        Shell::Fish => write!(out, "{}", fish_dynamic_completions(&repo_subcommands))?,
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Prints names completed dynamically, one per line.
+pub fn print_names(kind: CompletionNames, profile: Option<&str>) -> Result<(), Error> {
+    let names = match kind {
+        CompletionNames::Projects => {
+            let ProjectDirs { roots, depth } = load_project_dirs(&config_dir()?, profile)?;
+            let paths = task::block_in_place(|| find_project_paths(&roots, depth))?;
+            let mut names: Vec<_> = paths
+                .iter()
+                .filter_map(|x| Some(x.file_name()?.to_string_lossy().into_owned()))
+                .collect();
+            names.sort();
+            names.dedup();
+            names
+        }
+        CompletionNames::Repos => {
+            let config_dir = config_dir()?;
+            let profile = load_profile_name(&config_dir, profile)?;
+            Database::new(&database_path(&config_dir, profile.as_deref()))?.get_repository_ids()?
+        }
+    };
+    let mut out = io::stdout().lock();
+    for name in names {
+        writeln!(out, "{}", name)?;
+    }
+    Ok(())
+}
+
+/// Finds `r` subcommands whose first argument is a repository.
+fn repo_subcommands() -> Vec<String> {
+    let cmd = Cli::command();
+    let r = cmd.find_subcommand("r").expect("r command should exist");
+    r.get_subcommands()
+        .filter(|x| {
+            x.get_positionals()
+                .next()
+                .map(|x| ["repo", "from", "to"].contains(&x.get_id()))
+                .unwrap_or_default()
+        })
+        .map(|x| x.get_name().to_owned())
+        .collect()
+}
+
+fn bash_dynamic_completions(repo_subcommands: &[String]) -> String {
+    let case = |subcommand: &str, names: &[String]| {
+        names
+            .iter()
+            .map(|x| format!("\"{subcommand} {x}\""))
+            .collect::<Vec<_>>()
+            .join("|")
+    };
+    let project_names: Vec<_> = PROJECT_SUBCOMMANDS.iter().map(|x| x.to_string()).collect();
+    format!(
+        r#"
+_{BIN_NAME}_names() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    if [[ ${{COMP_CWORD}} -eq 3 && ${{cur}} != -* ]]; then
+        case "${{COMP_WORDS[1]}} ${{COMP_WORDS[2]}}" in
+            {projects})
+                COMPREPLY=($(compgen -W "$({BIN_NAME} complete-names projects 2>/dev/null)" -- "${{cur}}"))
+                return 0
+                ;;
+            {repos})
+                COMPREPLY=($(compgen -W "$({BIN_NAME} complete-names repos 2>/dev/null)" -- "${{cur}}"))
+                return 0
+                ;;
+        esac
+    fi
+    _{BIN_NAME} "$@"
+}}
+
+complete -F _{BIN_NAME}_names -o bashdefault -o default {BIN_NAME}
+"#,
+        projects = case("w", &project_names),
+        repos = case("r", repo_subcommands),
+    )
+}
+
+fn fish_dynamic_completions(repo_subcommands: &[String]) -> String {
+    format!(
+        "
+complete -c {BIN_NAME} -n '__fish_seen_subcommand_from w; and __fish_seen_subcommand_from {projects}' -f -a '({BIN_NAME} complete-names projects 2>/dev/null)'
+complete -c {BIN_NAME} -n '__fish_seen_subcommand_from r; and __fish_seen_subcommand_from {repos}' -f -a '({BIN_NAME} complete-names repos 2>/dev/null)'
+",
+        projects = PROJECT_SUBCOMMANDS.join(" "),
+        repos = repo_subcommands.join(" "),
+    )
+}
+
+#[cfg(test)]
+#[test]
+fn test_repo_subcommands() {
+    let subcommands = repo_subcommands();
+    for x in ["summary", "browse-upstream", "copy-settings", "sync-labels"] {
+        assert!(subcommands.iter().any(|y| y == x), "missing {x}");
+    }
+    for x in ["create", "ls", "clone"] {
+        assert!(!subcommands.iter().any(|y| y == x), "unexpected {x}");
+    }
+}
@@ -1,233 +1,738 @@
 use crate::{
     app_env::AppEnv,
+    cli::dashboard::{DashboardColumn, DashboardSort},
     database::Database,
+    display::{BuildStatusBadge, RelativeTime},
     github_client2::GithubClient2,
-    repository_id::{IsPartialRepositoryId, IsRepositoryId},
+    github_models::GhCheckRun,
+    output::Output,
+    repository_id::{IsPartialRepositoryId, PartialRepoId},
     types::{BuildStatus, Repository},
 };
 use anyhow::Error;
-use futures::{future, StreamExt, TryStreamExt};
-use octocrab::models::Repository as GhRepository;
+use console::Term;
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::Serialize;
 use std::{
-    cmp::{self, max},
-    fmt,
+    cmp::{self, max, Reverse},
+    collections::{HashMap, HashSet},
+    io::Write,
+    time::Duration,
 };
-use tracing::info;
+use tabwriter::TabWriter;
+use tracing::{info, warn};
 use unicode_segmentation::UnicodeSegmentation;
 
+/// Filter of repositories printed in the dashboard.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct DashboardFilter {
+    /// Print only repositories whose build failed.
+    pub fail_only: bool,
+    /// Also print repositories whose build is in progress, when printing only failures.
+    pub active: bool,
+}
+
+impl DashboardFilter {
+    fn matches(&self, build_status: Option<&BuildStatus>) -> bool {
+        if !self.fail_only {
+            return true;
+        }
+        match build_status {
+            Some(BuildStatus::Failure) => true,
+            Some(BuildStatus::InProgress) => self.active,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_dashboard_filter() {
+    let all = DashboardFilter::default();
+    assert!(all.matches(None));
+    assert!(all.matches(Some(&BuildStatus::Success)));
+
+    let failures = DashboardFilter {
+        fail_only: true,
+        active: false,
+    };
+    assert!(failures.matches(Some(&BuildStatus::Failure)));
+    assert!(!failures.matches(Some(&BuildStatus::InProgress)));
+    assert!(!failures.matches(Some(&BuildStatus::Success)));
+    assert!(!failures.matches(None));
+
+    let active = DashboardFilter {
+        fail_only: true,
+        active: true,
+    };
+    assert!(active.matches(Some(&BuildStatus::Failure)));
+    assert!(active.matches(Some(&BuildStatus::InProgress)));
+    assert!(!active.matches(Some(&BuildStatus::Success)));
+}
+
+/// Columns and order of the dashboard.
+#[derive(Clone, Debug)]
+pub struct DashboardView {
+    pub columns: Vec<DashboardColumn>,
+    /// Order by a column, instead of pinned first.
+    pub sort: Option<DashboardSort>,
+}
+
+/// Sorts repositories, repositories missing the sorted value are last.
+fn sort_repositories(repos: &mut [Repository], sort: DashboardSort) {
+    // lower ranks first
+    let build_rank = |x: &Repository| match x.build_status {
+        Some(BuildStatus::Failure) => 0,
+        Some(BuildStatus::InProgress) => 1,
+        Some(BuildStatus::Success) => 2,
+        None => 3,
+    };
+    match sort {
+        DashboardSort::Name => repos.sort_by(|a, b| a.name.cmp(&b.name)),
+        DashboardSort::Build => repos.sort_by_key(build_rank),
+        DashboardSort::Prs => repos.sort_by_key(|x| Reverse(x.open_pull_requests)),
+        DashboardSort::Branches => repos.sort_by_key(|x| Reverse(x.unmerged_branches)),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_sort_repositories() {
+    let repo = |name: &str, build_status, open_pull_requests| Repository {
+        name: name.to_owned(),
+        owner: "kafji".to_owned(),
+        a_fork: false,
+        archived: false,
+        build_status,
+        pushed_at: None,
+        open_pull_requests,
+        unmerged_branches: None,
+        build_status_sha: None,
+    };
+    let mut repos = [
+        repo("b", Some(BuildStatus::Success), None),
+        repo("c", None, Some(1)),
+        repo("a", Some(BuildStatus::Failure), Some(4)),
+    ];
+    let names = |xs: &[Repository]| xs.iter().map(|x| x.name.clone()).collect::<Vec<_>>();
+
+    sort_repositories(&mut repos, DashboardSort::Name);
+    assert_eq!(names(&repos), ["a", "b", "c"]);
+    sort_repositories(&mut repos, DashboardSort::Build);
+    assert_eq!(names(&repos), ["a", "b", "c"]);
+    sort_repositories(&mut repos, DashboardSort::Prs);
+    assert_eq!(names(&repos), ["a", "c", "b"]);
+}
+
 /// Prints dashboard, repositories and their build statuses.
-pub async fn print_dashboard<'app>(app_env: AppEnv<'app>) -> Result<(), Error> {
-    let gh_username = app_env.github_username;
+///
+/// Repositories are the ones of the organization when there's one, owned ones otherwise.
+pub async fn print_dashboard<'app>(
+    app_env: AppEnv<'app>,
+    org: Option<String>,
+    csv: bool,
+    filter: DashboardFilter,
+    view: DashboardView,
+) -> Result<(), Error> {
+    let owner = org.as_deref().unwrap_or(app_env.github_username);
 
-    let repos = app_env.database.get_dashboard_repositories(gh_username)?;
-    let repos = repos
-        .into_iter()
-        .map(|r| {
-            let bs = r.build_status.map(|x| x.to_string()).unwrap_or_default();
-            (r.name, bs)
-        })
-        .collect::<Vec<_>>();
-    let repos: Vec<_> = repos
+    let mut repos = app_env.database.get_dashboard_repositories(owner).await?;
+    repos.retain(|x| filter.matches(x.build_status.as_ref()));
+    if let Some(sort) = view.sort {
+        sort_repositories(&mut repos, sort);
+    }
+    let rates = app_env
+        .database
+        .get_success_rates(owner, SUCCESS_RATE_BUILDS)
+        .await?;
+    if csv {
+        return write_dashboard_csv(app_env.output, &repos, &rates);
+    }
+    let rows: Vec<_> = repos
         .iter()
-        .map(|(a, b)| (a.as_str(), b.as_str()))
+        .map(|x| DashboardRow::new(x, rates.get(&x.name).copied()))
         .collect();
-    do_print_dashboard(&repos[..]);
+    if app_env.output.write_records(&rows)? {
+        return Ok(());
+    }
+    let rows = dashboard_rows(repos, &rates, &view.columns);
+    do_print_dashboard(app_env.output, &rows)?;
 
     Ok(())
 }
 
-pub async fn update_dashboard<'app>(mut env: AppEnv<'app>) -> Result<(), anyhow::Error> {
+/// Returns cells of the dashboard, a repository name followed by the cells of the columns.
+fn dashboard_rows(
+    repos: Vec<Repository>,
+    rates: &HashMap<String, f64>,
+    columns: &[DashboardColumn],
+) -> Vec<Vec<String>> {
+    let count = |x: Option<u64>| x.map(|x| x.to_string()).unwrap_or_else(|| "-".to_owned());
+    repos
+        .into_iter()
+        .map(|r| {
+            let rate = rates.get(&r.name).copied();
+            let mut row = vec![r.name];
+            for column in columns {
+                let cell = match column {
+                    DashboardColumn::Build => r
+                        .build_status
+                        .as_ref()
+                        .map(|x| BuildStatusBadge::new(x).to_string())
+                        .unwrap_or_default(),
+                    DashboardColumn::Prs => count(r.open_pull_requests),
+                    DashboardColumn::Branches => count(r.unmerged_branches),
+                    DashboardColumn::Rate => {
+                        rate.map(format_rate).unwrap_or_else(|| "-".to_owned())
+                    }
+                };
+                row.push(cell);
+            }
+            row
+        })
+        .collect()
+}
+
+pub async fn update_dashboard<'app>(
+    mut env: AppEnv<'app>,
+    org: Option<String>,
+    csv: bool,
+    filter: DashboardFilter,
+    view: DashboardView,
+) -> Result<(), anyhow::Error> {
     let db = &mut env.database;
-    let username = &env.github_username;
-    let gh_client = env.github_client.clone();
-    update_repositories(&gh_client, db).await?;
-    update_build_statuses(db, username, gh_client).await?;
+    let owner = org.as_deref().unwrap_or(env.github_username);
+    let gh_client = &env.github_client;
+    let pushed = update_repositories(gh_client, db, owner, org.as_deref()).await?;
+    update_build_statuses(db, owner, gh_client, &pushed).await?;
+    update_repository_activities(db, owner, gh_client).await?;
 
-    print_dashboard(env).await?;
+    print_dashboard(env, org, csv, filter, view).await?;
 
     Ok(())
 }
 
-/// Fetches owned repositories.
-#[tracing::instrument(skip_all)]
-async fn get_repositories<'a>(
-    gh_client: &'a GithubClient2,
-    gh_username: &'a str,
-    db: &mut Database,
-) -> Result<Vec<Repository>, anyhow::Error> {
-    let repos = db.get_dashboard_repositories(gh_username)?;
-    if !repos.is_empty() {
-        info!("loaded repositories from database");
-        return Ok(repos);
-    }
-
-    let predicate = move |r: GhRepository| {
-        let owned = r.owner().map(|x| x == gh_username).unwrap_or_default();
-        let a_fork = r.fork.unwrap_or_default();
-        let archived = r.archived.unwrap_or_default();
-        future::ok(if owned && !a_fork && !archived {
-            Some(r)
-        } else {
-            None
-        })
-    };
-    let gh_repos = gh_client
-        .list_owned_repositories()
-        .try_filter_map(predicate)
-        .try_collect::<Vec<_>>()
+/// Updates and prints dashboard every interval until interrupted with Ctrl-C, redrawing it in
+/// place.
+///
+/// Repository activities aren't refreshed, they're only updated with `--update`.
+pub async fn watch_dashboard(
+    mut env: AppEnv<'_>,
+    org: Option<String>,
+    filter: DashboardFilter,
+    view: DashboardView,
+    interval: Duration,
+) -> Result<(), Error> {
+    let owner = org.as_deref().unwrap_or(env.github_username);
+    let mut out = Term::buffered_stdout();
+
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    // lines of the previous dashboard, to be cleared
+    let mut lines = 0;
+    loop {
+        let refresh = async {
+            let db = &mut env.database;
+            let gh_client = &env.github_client;
+            let pushed = update_repositories(gh_client, db, owner, org.as_deref()).await?;
+            update_build_statuses(db, owner, gh_client, &pushed).await?;
+
+            let mut repos = db.get_dashboard_repositories(owner).await?;
+            repos.retain(|x| filter.matches(x.build_status.as_ref()));
+            if let Some(sort) = view.sort {
+                sort_repositories(&mut repos, sort);
+            }
+            let rates = db.get_success_rates(owner, SUCCESS_RATE_BUILDS).await?;
+            let rows = dashboard_rows(repos, &rates, &view.columns);
+            let mut dashboard = Vec::new();
+            do_print_dashboard(&mut dashboard, &rows)?;
+            Result::<_, Error>::Ok(dashboard)
+        };
+        let dashboard = tokio::select! {
+            x = refresh => x?,
+            x = ctrl_c.as_mut() => {
+                x?;
+                break;
+            }
+        };
+
+        out.clear_last_lines(lines)?;
+        out.write_all(&dashboard)?;
+        out.flush()?;
+        lines = dashboard.iter().filter(|x| **x == b'\n').count();
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            x = ctrl_c.as_mut() => {
+                x?;
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Pins or unpins a repository, pinned repositories are printed at the top of the dashboard.
+pub async fn pin_repository<'app>(
+    mut app_env: AppEnv<'app>,
+    repo_id: PartialRepoId,
+    pinned: bool,
+) -> Result<(), Error> {
+    let repo_id = repo_id.complete(app_env.github_username);
+    app_env.database.set_pinned(&repo_id, pinned)?;
+    Ok(())
+}
+
+/// Ignores or unignores a repository, ignored repositories are neither printed nor have their
+/// build status updated.
+pub async fn ignore_repository<'app>(
+    mut app_env: AppEnv<'app>,
+    repo_id: PartialRepoId,
+    ignored: bool,
+) -> Result<(), Error> {
+    let repo_id = repo_id.complete(app_env.github_username);
+    app_env.database.set_ignored(&repo_id, ignored)?;
+    Ok(())
+}
+
+/// Number of recent finished builds the success rate is computed over.
+const SUCCESS_RATE_BUILDS: usize = 10;
+
+/// Default number of build status changes printed by [print_build_status_history].
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+
+/// Formats a success rate as a percentage.
+fn format_rate(rate: f64) -> String {
+    format!("{:.0}%", rate * 100.0)
+}
+
+/// Prints recorded build status changes of a repository, most recent first, and its success
+/// rate.
+pub async fn print_build_status_history(
+    app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let limit = app_env.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    let history = app_env.database.get_build_status_history(&repo_id, limit)?;
+    if history.is_empty() {
+        writeln!(
+            out,
+            "No build statuses of {repo_id} recorded, update the dashboard to record them."
+        )?;
+        return Ok(());
+    }
+
+    let mut w = TabWriter::new(Vec::new());
+    for x in &history {
+        writeln!(
+            w,
+            "{}\t{}\t{}",
+            &x.sha[..cmp::min(7, x.sha.len())],
+            BuildStatusBadge::new(&x.status),
+            x.recorded_at.since()
+        )?;
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    let rates = app_env
+        .database
+        .get_success_rates(&repo_id.owner, SUCCESS_RATE_BUILDS)
         .await?;
-    let repos = gh_repos
+    if let Some(rate) = rates.get(&repo_id.name) {
+        writeln!(
+            out,
+            "\nSuccess rate of the last {} builds: {}",
+            SUCCESS_RATE_BUILDS,
+            format_rate(*rate)
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Prints repositories excluded from the dashboard.
+pub async fn print_ignored_repositories(app_env: AppEnv<'_>) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repos = app_env.database.get_ignored()?;
+    if repos.is_empty() {
+        writeln!(out, "No ignored repositories.")?;
+        return Ok(());
+    }
+    for repo in repos {
+        writeln!(out, "{}", repo)?;
+    }
+    Ok(())
+}
+
+/// Number of repositories whose build statuses are fetched in one GraphQL query.
+const BUILD_STATUS_BATCH_SIZE: usize = 50;
+
+/// Fetches build statuses of repositories from the status check rollups of their default branch
+/// heads, in one query, along with the commits they are of.
+async fn get_build_statuses(
+    gh_client: &GithubClient2,
+    repos: &[Repository],
+) -> Result<Vec<(Option<BuildStatus>, Option<String>)>, Error> {
+    let rollups = gh_client.get_status_check_rollup_states(repos).await?;
+    let statuses = rollups
         .into_iter()
-        .map(|x| Repository {
-            name: x.name,
-            owner: x
-                .owner
-                .map(|x| x.login)
-                .unwrap_or_else(|| gh_username.to_owned()),
-            a_fork: x.fork.unwrap_or_default(),
-            archived: x.archived.unwrap_or_default(),
-            build_status: None,
+        .map(|x| match x {
+            Some(x) => (
+                x.state.as_deref().map(build_status_from_rollup_state),
+                Some(x.sha),
+            ),
+            None => (None, None),
         })
-        .collect::<Vec<_>>();
-    db.put_repositories(&repos)?;
-    Ok(repos)
+        .collect();
+    Ok(statuses)
 }
 
-/// Fetches build status.
+fn build_status_from_rollup_state(state: &str) -> BuildStatus {
+    match state {
+        "SUCCESS" => BuildStatus::Success,
+        "PENDING" | "EXPECTED" => BuildStatus::InProgress,
+        _ => BuildStatus::Failure,
+    }
+}
+
+/// Fetches build status and the commit it is of.
+///
+/// Check runs aren't fetched when the latest commit is the one of the stored final build status.
 async fn get_build_status(
     gh_client: &GithubClient2,
-    repo_id: &(impl IsRepositoryId + fmt::Debug),
-) -> Result<Option<BuildStatus>, Error> {
-    let commit = gh_client.get_latest_commit(repo_id).await?;
-    let runs = match commit {
-        Some(commit) => {
-            let gitref = &commit.sha;
-            let runs = gh_client.get_check_runs_for_gitref(repo_id, gitref).await?;
-            Some(runs)
-        }
-        None => None,
+    repo: &Repository,
+) -> Result<(Option<BuildStatus>, Option<String>), Error> {
+    let commit = match gh_client.get_latest_commit(repo).await? {
+        Some(x) => x,
+        None => return Ok((None, None)),
     };
-    let status = if let Some(runs) = runs {
-        runs.iter()
-            .map(|x| match x.status.as_str() {
-                "queued" => None,
-                "in_progress" => Some(BuildStatus::InProgress),
-                "completed" => match x.conclusion.as_deref() {
-                    Some("success") => Some(BuildStatus::Success),
-                    _ => Some(BuildStatus::Failure),
-                },
-                _ => Some(BuildStatus::Failure),
-            })
-            .reduce(|acc, x| max(acc, x))
-    } else {
-        None
+    if repo.build_status_sha.as_ref() == Some(&commit.sha) && is_final(repo.build_status.as_ref()) {
+        return Ok((repo.build_status.clone(), Some(commit.sha)));
+    }
+    let runs = gh_client
+        .get_check_runs_for_gitref(repo, &commit.sha)
+        .await?;
+    Ok((build_status_from_check_runs(&runs), Some(commit.sha)))
+}
+
+/// Whether a build status won't change without a push.
+fn is_final(build_status: Option<&BuildStatus>) -> bool {
+    matches!(
+        build_status,
+        Some(BuildStatus::Success) | Some(BuildStatus::Failure)
+    )
+}
+
+/// Whether the build status of a repository has to be fetched, i.e. the repository was pushed
+/// since the previous update or its build status may still change.
+///
+/// Checks re-run without a push aren't noticed until the next push.
+fn needs_build_status(repo: &Repository, pushed: bool) -> bool {
+    pushed || repo.build_status_sha.is_none() || !is_final(repo.build_status.as_ref())
+}
+
+#[cfg(test)]
+#[test]
+fn test_needs_build_status() {
+    let repo = |build_status, build_status_sha: Option<&str>| Repository {
+        name: "shub".to_owned(),
+        owner: "kafji".to_owned(),
+        a_fork: false,
+        archived: false,
+        build_status,
+        build_status_sha: build_status_sha.map(ToOwned::to_owned),
+        pushed_at: None,
+        open_pull_requests: None,
+        unmerged_branches: None,
     };
-    Ok(status.flatten())
+
+    assert!(needs_build_status(&repo(None, None), false));
+    assert!(!needs_build_status(
+        &repo(Some(BuildStatus::Success), Some("c0ffee")),
+        false
+    ));
+    assert!(needs_build_status(
+        &repo(Some(BuildStatus::Success), Some("c0ffee")),
+        true
+    ));
+    assert!(needs_build_status(
+        &repo(Some(BuildStatus::InProgress), Some("c0ffee")),
+        false
+    ));
+    assert!(!needs_build_status(
+        &repo(Some(BuildStatus::Failure), Some("c0ffee")),
+        false
+    ));
 }
 
-fn do_print_dashboard<'a>(xs: &[(&'a str /* name */, &'a str /* build status */)]) {
-    // cache name lengths
-    let mut name_lengths = Vec::with_capacity(xs.len());
+/// Combines statuses of check runs into the worst one, `None` when there are no runs or they
+/// are all queued.
+pub(crate) fn build_status_from_check_runs(runs: &[GhCheckRun]) -> Option<BuildStatus> {
+    runs.iter()
+        .map(|x| match x.status.as_str() {
+            "queued" => None,
+            "in_progress" => Some(BuildStatus::InProgress),
+            "completed" => match x.conclusion.as_deref() {
+                Some("success") => Some(BuildStatus::Success),
+                _ => Some(BuildStatus::Failure),
+            },
+            _ => Some(BuildStatus::Failure),
+        })
+        .reduce(|acc, x| max(acc, x))
+        .flatten()
+}
 
-    // find max length for name
-    let mut name_max_length = 0;
+/// Prints rows of cells, a repository name followed by the cells of the columns.
+fn do_print_dashboard(mut out: impl Write, rows: &[Vec<String>]) -> Result<(), Error> {
+    // cells may have colors, measure their printed width
+    let width = |x: &str| {
+        if x.contains('\u{1b}') {
+            console::measure_text_width(x)
+        } else {
+            x.graphemes(true).count()
+        }
+    };
 
-    for (name, _) in xs {
-        let length = name.graphemes(true).count();
-        name_lengths.push(length);
-        name_max_length = cmp::max(name_max_length, length);
+    // find max width of each column
+    let mut max_widths = Vec::new();
+    for row in rows {
+        for (idx, cell) in row.iter().enumerate() {
+            let w = width(cell);
+            match max_widths.get_mut(idx) {
+                Some(max_width) => *max_width = cmp::max(*max_width, w),
+                None => max_widths.push(w),
+            }
+        }
     }
 
     let default_col_margin = 2;
 
     // print dashboard
-    for (idx, (name, build_status)) in xs.iter().enumerate() {
-        // calc. how many spaces required to align the next column
-        let name_col_right_margin = {
-            let pad_deficit = name_max_length - name_lengths[idx];
-            (0..pad_deficit + default_col_margin)
-                .map(|_| ' ')
-                .collect::<String>()
-        };
+    for row in rows {
+        let mut line = String::new();
+        for (idx, cell) in row.iter().enumerate() {
+            line.push_str(cell);
+            // pad to align the next column
+            if idx + 1 < row.len() {
+                let pad_deficit = max_widths[idx] - width(cell);
+                line.extend((0..pad_deficit + default_col_margin).map(|_| ' '));
+            }
+        }
+        writeln!(out, "{}", line)?;
+    }
+
+    Ok(())
+}
 
-        println!("{}{}{}", name, name_col_right_margin, build_status);
+/// Repository and its build status, as printed by `--format json`.
+#[derive(Serialize, Debug)]
+struct DashboardRow<'a> {
+    owner: &'a str,
+    name: &'a str,
+    build_status: Option<String>,
+    open_pull_requests: Option<u64>,
+    unmerged_branches: Option<u64>,
+    success_rate: Option<f64>,
+}
+
+impl<'a> DashboardRow<'a> {
+    fn new(x: &'a Repository, success_rate: Option<f64>) -> Self {
+        Self {
+            owner: &x.owner,
+            name: &x.name,
+            build_status: x.build_status.as_ref().map(ToString::to_string),
+            open_pull_requests: x.open_pull_requests,
+            unmerged_branches: x.unmerged_branches,
+            success_rate,
+        }
     }
 }
 
+fn write_dashboard_csv(
+    out: &Output,
+    repos: &[Repository],
+    rates: &HashMap<String, f64>,
+) -> Result<(), Error> {
+    let mut w = csv::Writer::from_writer(out);
+    w.write_record([
+        "owner",
+        "name",
+        "build_status",
+        "open_pull_requests",
+        "unmerged_branches",
+        "success_rate",
+    ])?;
+    let count = |x: Option<u64>| x.map(|x| x.to_string()).unwrap_or_default();
+    for r in repos {
+        let build_status = r
+            .build_status
+            .as_ref()
+            .map(|x| x.to_string())
+            .unwrap_or_default();
+        let rate = rates
+            .get(&r.name)
+            .map(|x| x.to_string())
+            .unwrap_or_default();
+        w.write_record([
+            &r.owner,
+            &r.name,
+            &build_status,
+            &count(r.open_pull_requests),
+            &count(r.unmerged_branches),
+            &rate,
+        ])?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+/// Fetches owned repositories, or repositories of an organization, keeping their stored build
+/// statuses and activities.
+///
+/// Returns repositories pushed since the previous update, as owner and name.
 async fn update_repositories(
     gh_client: &GithubClient2,
     db: &mut Database,
-) -> Result<(), anyhow::Error> {
+    owner: &str,
+    org: Option<&str>,
+) -> Result<HashSet<(String, String)>, anyhow::Error> {
     info!("updating repositories");
 
-    // fetch owned repositories
-    let gh_repos = gh_client
-        .list_owned_repositories()
-        .try_collect::<Vec<_>>()
-        .await?;
+    // fetch repositories, forks and archived ones are stored but not printed
+    let gh_repos = match org {
+        Some(org) => {
+            gh_client
+                .list_organization_repositories(org)
+                .try_collect::<Vec<_>>()
+                .await?
+        }
+        None => {
+            gh_client
+                .list_owned_repositories()
+                .try_collect::<Vec<_>>()
+                .await?
+        }
+    };
 
-    // update stored repositories
-    let repos = gh_repos
+    // stored values are replaced, carry over the ones computed by previous updates
+    let stored: HashMap<_, _> = db
+        .get_dashboard_repositories(owner)
+        .await?
         .into_iter()
-        .map(Repository::try_from)
-        .collect::<Result<Vec<_>, _>>()?;
-    db.put_repositories(&repos[..])?;
+        .map(|x| ((x.owner.clone(), x.name.clone()), x))
+        .collect();
+    let mut pushed = HashSet::new();
+    let mut repos = Vec::with_capacity(gh_repos.len());
+    for gh_repo in gh_repos {
+        let mut repo = Repository::try_from(gh_repo)?;
+        let key = (repo.owner.clone(), repo.name.clone());
+        match stored.get(&key) {
+            Some(x) => {
+                if x.pushed_at != repo.pushed_at {
+                    pushed.insert(key);
+                }
+                repo.build_status = x.build_status.clone();
+                repo.build_status_sha = x.build_status_sha.clone();
+                repo.open_pull_requests = x.open_pull_requests;
+                repo.unmerged_branches = x.unmerged_branches;
+            }
+            None => {
+                pushed.insert(key);
+            }
+        }
+        repos.push(repo);
+    }
 
-    Ok(())
+    // update stored repositories
+    db.put_repositories(repos).await?;
+
+    Ok(pushed)
 }
 
+/// Updates build statuses of repositories that were pushed or whose build status may change.
 async fn update_build_statuses(
     db: &mut Database,
     owner: &str,
-    gh_client: GithubClient2,
+    gh_client: &GithubClient2,
+    pushed: &HashSet<(String, String)>,
 ) -> Result<(), anyhow::Error> {
     info!("updating build statuses");
 
     // get stored repositories
-    let repos = db.get_dashboard_repositories(owner)?;
+    let mut repos = db.get_dashboard_repositories(owner).await?;
+    repos.retain(|x| needs_build_status(x, pushed.contains(&(x.owner.clone(), x.name.clone()))));
+    info!("{} repositories need build statuses", repos.len());
 
     // fetch build statuses
-    let bss = {
-        let (tx, mut rx) = tokio::sync::mpsc::channel(32);
-        tokio::spawn(async move {
-            futures::stream::iter(repos)
-                .then(|x| futures::future::ok::<_, anyhow::Error>(x))
-                .and_then(move |x| {
-                    let gh_client = gh_client.clone();
-                    async move {
-                        let build_status = get_build_status(&gh_client, &x).await?;
-                        info!("build status: {:?}", build_status);
-                        Ok((x, build_status))
-                    }
-                })
-                .try_for_each_concurrent(2, move |(r, s)| {
-                    let tx = tx.clone();
-                    async move {
-                        if let Some(s) = s {
-                            tx.send((r, s)).await?;
-                        }
-                        Ok(())
-                    }
-                })
-                .await
-        });
-        let collector = tokio::spawn(async move {
-            let mut vs = Vec::new();
-            loop {
-                let v = rx.recv().await;
-                match v {
-                    Some(x) => vs.push(x),
-                    None => break,
-                }
+    let mut bss = Vec::new();
+    for chunk in repos.chunks(BUILD_STATUS_BATCH_SIZE) {
+        let statuses = match get_build_statuses(gh_client, chunk).await {
+            Ok(x) => x,
+            Err(err) => {
+                warn!(
+                    ?err,
+                    "failed to fetch build statuses in batch, fetching one by one"
+                );
+                stream::iter(chunk)
+                    .map(|x| get_build_status(gh_client, x))
+                    .buffered(2)
+                    .try_collect()
+                    .await?
             }
-            Result::<_, anyhow::Error>::Ok(vs)
-        });
-        collector
+        };
+        info!("build statuses: {:?}", statuses);
+        bss.extend(
+            chunk
+                .iter()
+                .cloned()
+                .zip(statuses)
+                .map(|(r, (s, sha))| (r, s, sha)),
+        );
+    }
+
+    // update stored values
+    db.set_build_statuses(bss).await?;
+
+    Ok(())
+}
+
+async fn update_repository_activities(
+    db: &mut Database,
+    owner: &str,
+    gh_client: &GithubClient2,
+) -> Result<(), anyhow::Error> {
+    info!("updating repository activities");
+
+    // get stored repositories
+    let repos = db.get_dashboard_repositories(owner).await?;
+
+    // fetch open pull requests and unmerged branches, they're optional columns so a failed
+    // batch is skipped
+    let mut activities = Vec::new();
+    for chunk in repos.chunks(BUILD_STATUS_BATCH_SIZE) {
+        let xs = match gh_client.get_repository_activities(chunk).await {
+            Ok(x) => x,
+            Err(err) => {
+                warn!(?err, "failed to fetch repository activities");
+                continue;
+            }
+        };
+        activities.extend(
+            chunk
+                .iter()
+                .cloned()
+                .zip(xs)
+                .filter_map(|(r, x)| x.map(|x| (r, x.open_pull_requests, x.unmerged_branches))),
+        );
     }
-    .await??;
 
     // update stored values
-    db.set_build_statuses(&bss[..])?;
+    db.set_repository_activities(activities).await?;
 
     Ok(())
 }
@@ -0,0 +1,111 @@
+use crate::{
+    app_env::AppEnv,
+    commands::repos::list_source_repositories,
+    github_models::GhDependencyManifest,
+    repository_id::{FullRepoId, PartialRepoId},
+};
+use anyhow::Error;
+use futures::{future, stream, StreamExt, TryStreamExt};
+use std::io::Write;
+use tabwriter::TabWriter;
+
+/// Number of repositories whose dependency graphs are fetched at the same time.
+const DEPS_CONCURRENCY: usize = 4;
+
+/// Prints dependencies of a repository grouped by their manifest.
+pub async fn print_dependencies(app_env: AppEnv<'_>, repo_id: PartialRepoId) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let manifests = app_env
+        .github_client
+        .list_dependency_manifests(&repo_id)
+        .await?;
+    if manifests.is_empty() {
+        writeln!(out, "Repository {repo_id} has no dependency manifests.")?;
+        return Ok(());
+    }
+
+    for (idx, manifest) in manifests.iter().enumerate() {
+        if idx > 0 {
+            writeln!(out)?;
+        }
+        writeln!(
+            out,
+            "{} ({})",
+            manifest.filename, manifest.dependencies_count
+        )?;
+
+        let mut w = TabWriter::new(Vec::new());
+        for dep in &manifest.dependencies {
+            writeln!(
+                w,
+                "  {}\t{}\t{}",
+                dep.package_name,
+                dep.requirements,
+                dep.package_manager.as_deref().unwrap_or_default()
+            )?;
+        }
+        write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+        let hidden =
+            (manifest.dependencies_count as usize).saturating_sub(manifest.dependencies.len());
+        if hidden > 0 {
+            writeln!(out, "  ..and {hidden} more")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints owned repositories that are neither forks nor archived and depend on a repository, with
+/// the manifests and packages they depend on it through.
+///
+/// GitHub doesn't expose dependents of a repository in its APIs, so only owned repositories are
+/// searched, and only the first 100 dependencies of their manifests.
+pub async fn print_dependents(app_env: AppEnv<'_>, repo_id: PartialRepoId) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+    let gh_client = &app_env.github_client;
+
+    let repo_ids = list_source_repositories(gh_client, app_env.github_username).await?;
+    let manifests: Vec<(FullRepoId, Vec<GhDependencyManifest>)> = stream::iter(repo_ids)
+        .filter(|x| future::ready(*x != repo_id))
+        .map(|x| async move {
+            let manifests = gh_client.list_dependency_manifests(&x).await?;
+            Result::<_, Error>::Ok((x, manifests))
+        })
+        .buffered(DEPS_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let name = repo_id.to_string();
+    let mut w = TabWriter::new(Vec::new());
+    let mut found = false;
+    for (dependent, manifests) in &manifests {
+        for manifest in manifests {
+            for dep in &manifest.dependencies {
+                let depends = dep
+                    .repository
+                    .as_ref()
+                    .map(|x| x.name_with_owner.eq_ignore_ascii_case(&name))
+                    .unwrap_or_default();
+                if depends {
+                    found = true;
+                    writeln!(
+                        w,
+                        "{}\t{}\t{}\t{}",
+                        dependent, manifest.filename, dep.package_name, dep.requirements
+                    )?;
+                }
+            }
+        }
+    }
+    if !found {
+        writeln!(out, "No owned repositories depend on {repo_id}.")?;
+        return Ok(());
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    Ok(())
+}
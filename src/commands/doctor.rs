@@ -0,0 +1,250 @@
+use crate::{
+    app_env::AppEnv, create_local_repository_path, list_local_repository_paths,
+    repository_id::FullRepoId,
+};
+use anyhow::Error;
+use dialoguer::Confirm;
+use git2::Repository;
+use std::{env, fs, io::Write, path::Path};
+use tokio::task;
+
+/// Token scopes needed by shub, with what needs them.
+const REQUIRED_SCOPES: &[(&str, &str)] = &[("repo", "private repositories")];
+
+/// Token scopes only needed by some commands, their absence is noted but is not a problem.
+const OPTIONAL_SCOPES: &[(&str, &str)] = &[("delete_repo", "`r delete`")];
+
+/// Checks the environment, then checks that local projects are at the path their origin remote
+/// says they should be, that the origin still exists, and that forks have an upstream remote,
+/// offering to fix the problems found.
+pub async fn check_workspace_layout(app_env: AppEnv<'_>) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let root = app_env.workspace_root_dir;
+    let gh_client = &app_env.github_client;
+
+    let mut problems = check_environment(&app_env).await?;
+
+    // a missing workspace is reported by the environment check
+    let paths = if root.is_dir() {
+        task::block_in_place(|| list_local_repository_paths(root))?
+    } else {
+        Vec::new()
+    };
+
+    for path in paths {
+        let remotes = task::block_in_place(|| read_remotes(&path))?;
+
+        let origin = match remotes.origin {
+            Some(x) => x,
+            None => {
+                writeln!(out, "{}: has no origin remote", path.display())?;
+                problems += 1;
+                continue;
+            }
+        };
+        let repo_id = match FullRepoId::from_remote_url(&origin) {
+            Some(x) => x,
+            None => {
+                writeln!(
+                    out,
+                    "{}: origin `{origin}` is not a repository URL",
+                    path.display()
+                )?;
+                problems += 1;
+                continue;
+            }
+        };
+
+        let expected_path = create_local_repository_path(root, &repo_id);
+        let path = if path != expected_path {
+            writeln!(
+                out,
+                "{}: origin is {repo_id}, expected to be at {}",
+                path.display(),
+                expected_path.display()
+            )?;
+            problems += 1;
+            if expected_path.exists() {
+                writeln!(out, "{}: already exists", expected_path.display())?;
+                continue;
+            }
+            if app_env.dry_run {
+                eprintln!("[dry-run] move to {}", expected_path.display());
+                continue;
+            }
+            if !confirm(&format!("Move to {}?", expected_path.display()))? {
+                continue;
+            }
+            move_dir(&path, &expected_path)?;
+            expected_path
+        } else {
+            path
+        };
+
+        let repo = match gh_client.find_repository(&repo_id).await? {
+            Some(x) => x,
+            None => {
+                writeln!(out, "{}: origin {repo_id} is not found on GitHub, delete the project or point origin to another repository",
+                    path.display()
+                )?;
+                problems += 1;
+                continue;
+            }
+        };
+        if let Some(full_name) = repo.full_name.as_deref() {
+            if !full_name.eq_ignore_ascii_case(&repo_id.to_string()) {
+                let url = repo.ssh_url.as_deref().unwrap_or(full_name);
+                writeln!(out, "{}: origin {repo_id} moved to {full_name}, run `git remote set-url origin {url}`",
+                    path.display()
+                )?;
+                problems += 1;
+            }
+        }
+
+        if remotes.upstream.is_none() {
+            let parent_url = repo.parent.and_then(|x| x.ssh_url);
+            if let Some(parent_url) = parent_url {
+                writeln!(
+                    out,
+                    "{}: is a fork but has no upstream remote",
+                    path.display()
+                )?;
+                problems += 1;
+                if app_env.dry_run {
+                    eprintln!("[dry-run] add `{parent_url}` as upstream remote");
+                } else if confirm(&format!("Add `{parent_url}` as upstream remote?"))? {
+                    task::block_in_place(|| {
+                        Repository::open(&path)?.remote("upstream", &parent_url)?;
+                        Result::<_, Error>::Ok(())
+                    })?;
+                }
+            }
+        }
+    }
+
+    if problems == 0 {
+        writeln!(out, "No problems found.")?;
+    }
+
+    Ok(())
+}
+
+/// Checks the SSH agent, the token scopes, the workspace, and the database, returns the number
+/// of problems found.
+async fn check_environment(app_env: &AppEnv<'_>) -> Result<usize, Error> {
+    let mut out = app_env.output;
+    let mut problems = Vec::new();
+
+    if !app_env.workspace_root_dir.is_dir() {
+        problems.push(format!(
+            "workspace {} is not a directory, set WORKSPACE_HOME or `workspace_root` of the profile",
+            app_env.workspace_root_dir.display()
+        ));
+    }
+
+    if env::var_os("SHUB_EDITOR").is_none() {
+        problems.push("SHUB_EDITOR is not set, `w edit` needs it".to_owned());
+    }
+
+    if let Some(problem) = check_ssh_agent() {
+        problems.push(problem);
+    }
+
+    match app_env.github_client.get_token_scopes().await {
+        Ok(Some(scopes)) => {
+            for (scope, needed_by) in REQUIRED_SCOPES {
+                if !scopes.iter().any(|x| x == scope) {
+                    problems.push(format!(
+                        "token is missing `{scope}` scope, needed by {needed_by}, add it in GitHub developer settings"
+                    ));
+                }
+            }
+            for (scope, needed_by) in OPTIONAL_SCOPES {
+                if !scopes.iter().any(|x| x == scope) {
+                    writeln!(
+                        out,
+                        "token has no `{scope}` scope, which is only needed by {needed_by}"
+                    )?;
+                }
+            }
+        }
+        // fine-grained tokens have permissions instead of scopes
+        Ok(None) => (),
+        Err(err) => problems.push(format!(
+            "token is not valid, {err}, run `shub auth login` or set SHUB_TOKEN"
+        )),
+    }
+
+    let integrity = task::block_in_place(|| app_env.database.check_integrity())?;
+    for problem in integrity {
+        problems.push(format!(
+            "database {} is corrupted, {problem}, delete it to rebuild the cache",
+            app_env.database_path.display()
+        ));
+    }
+
+    for problem in &problems {
+        writeln!(out, "{problem}")?;
+    }
+    Ok(problems.len())
+}
+
+/// Checks that an SSH agent is running, git operations authenticate with it.
+#[cfg(unix)]
+fn check_ssh_agent() -> Option<String> {
+    match env::var_os("SSH_AUTH_SOCK") {
+        None => Some(
+            "SSH agent is not running, start it with `eval $(ssh-agent)` and add a key with `ssh-add`"
+                .to_owned(),
+        ),
+        Some(x) if !Path::new(&x).exists() => Some(format!(
+            "SSH agent socket {} does not exist, restart the agent",
+            Path::new(&x).display()
+        )),
+        Some(_) => None,
+    }
+}
+
+/// Agents other than on Unix are not found through an environment variable.
+#[cfg(not(unix))]
+fn check_ssh_agent() -> Option<String> {
+    None
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct Remotes {
+    origin: Option<String>,
+    upstream: Option<String>,
+}
+
+fn read_remotes(path: &Path) -> Result<Remotes, Error> {
+    let repo = Repository::open(path)?;
+    let url_of = |name| {
+        repo.find_remote(name)
+            .ok()
+            .and_then(|x| x.url().map(ToOwned::to_owned))
+    };
+    let s = Remotes {
+        origin: url_of("origin"),
+        upstream: url_of("upstream"),
+    };
+    Ok(s)
+}
+
+fn move_dir(from: &Path, to: &Path) -> Result<(), Error> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(from, to)?;
+    Ok(())
+}
+
+fn confirm(prompt: &str) -> Result<bool, Error> {
+    let yes = Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .show_default(true)
+        .wait_for_newline(true)
+        .interact()?;
+    Ok(yes)
+}
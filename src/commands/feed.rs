@@ -0,0 +1,28 @@
+use crate::{app_env::AppEnv, display::FeedEntries};
+use anyhow::Error;
+use futures::{future, TryStreamExt};
+use std::{collections::HashSet, io::Write};
+
+/// Prints recent releases and newly created repositories of the users I follow.
+pub async fn print_following_feed(app_env: AppEnv<'_>) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let gh_client = &app_env.github_client;
+
+    let followed = gh_client
+        .list_followed_users()
+        .map_ok(|x| x.login)
+        .try_collect::<HashSet<_>>()
+        .await?;
+
+    // received events also include activities of watched repositories, keep only the ones
+    // done by followed users
+    let events = gh_client
+        .list_received_events(app_env.github_username)
+        .try_filter(|x| future::ready(followed.contains(&x.actor.login)))
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    write!(out, "{}", FeedEntries::from_github_events(&events))?;
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use crate::{
+    app_env::AppEnv,
+    commands::{repos::list_source_repositories, undo::record_not_undoable},
+    github_models::{GhHook, GhHookConfig, GhNewHook},
+    repository_id::{FullRepoId, PartialRepoId},
+};
+use anyhow::Error;
+use futures::{stream, StreamExt, TryStreamExt};
+use std::io::Write;
+use tabwriter::TabWriter;
+
+/// Number of repositories whose webhooks are fetched at the same time.
+const HOOKS_CONCURRENCY: usize = 4;
+
+/// Prints webhooks of a repository, or of all owned repositories that are neither forks nor
+/// archived, with the status of their last delivery.
+pub async fn print_hooks(app_env: AppEnv<'_>, repo_id: Option<PartialRepoId>) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let gh_client = &app_env.github_client;
+
+    let repo_ids = match repo_id {
+        Some(x) => vec![x.complete(app_env.github_username)],
+        None => list_source_repositories(gh_client, app_env.github_username).await?,
+    };
+    let hooks: Vec<(FullRepoId, Vec<GhHook>)> = stream::iter(repo_ids)
+        .map(|repo_id| async move {
+            let hooks = gh_client.list_hooks(&repo_id).try_collect().await?;
+            Result::<_, Error>::Ok((repo_id, hooks))
+        })
+        .buffered(HOOKS_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    if hooks.iter().all(|(_, xs)| xs.is_empty()) {
+        writeln!(out, "No webhooks.")?;
+        return Ok(());
+    }
+
+    let mut w = TabWriter::new(Vec::new());
+    for (repo_id, xs) in &hooks {
+        for x in xs {
+            let last_response = x
+                .last_response
+                .as_ref()
+                .and_then(|x| match (x.code, &x.status) {
+                    (Some(code), _) => Some(code.to_string()),
+                    (None, Some(status)) => Some(status.clone()),
+                    (None, None) => None,
+                })
+                .unwrap_or_else(|| "-".to_owned());
+            writeln!(
+                w,
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                repo_id,
+                x.id,
+                x.config.url.as_deref().unwrap_or_default(),
+                x.events.join(","),
+                if x.active { "active" } else { "inactive" },
+                last_response
+            )?;
+        }
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    Ok(())
+}
+
+/// Adds a webhook delivering events of a repository to a URL as JSON.
+pub async fn add_hook(
+    mut app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    url: String,
+    events: &[String],
+    secret: Option<String>,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let hook = GhNewHook {
+        config: GhHookConfig {
+            url: Some(url),
+            content_type: Some("json".to_owned()),
+            secret,
+        },
+        events,
+        active: true,
+    };
+    if let Some(hook) = app_env.github_client.create_hook(&repo_id, &hook).await? {
+        record_not_undoable(
+            &mut app_env.database,
+            &repo_id.to_string(),
+            "webhook addition",
+        )?;
+        writeln!(out, "Added webhook {} to {repo_id}.", hook.id)?;
+    }
+
+    Ok(())
+}
+
+/// Removes a webhook from a repository.
+pub async fn remove_hook(
+    mut app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    hook_id: u64,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    app_env.github_client.delete_hook(&repo_id, hook_id).await?;
+    if !app_env.dry_run {
+        record_not_undoable(
+            &mut app_env.database,
+            &repo_id.to_string(),
+            "webhook removal",
+        )?;
+        writeln!(out, "Removed webhook {hook_id} from {repo_id}.")?;
+    }
+
+    Ok(())
+}
+
+/// Sends a ping event to a webhook, its delivery shows up as the last response of the webhook.
+pub async fn ping_hook(
+    app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    hook_id: u64,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    app_env.github_client.ping_hook(&repo_id, hook_id).await?;
+    if !app_env.dry_run {
+        writeln!(out, "Pinged webhook {hook_id} of {repo_id}.")?;
+    }
+
+    Ok(())
+}
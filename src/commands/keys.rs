@@ -0,0 +1,123 @@
+use crate::{
+    app_env::AppEnv,
+    commands::{repos::list_source_repositories, undo::record_not_undoable},
+    display::RelativeTime,
+    github_models::{GhDeployKey, GhNewDeployKey},
+    repository_id::{FullRepoId, PartialRepoId},
+};
+use anyhow::{Context, Error};
+use futures::{stream, StreamExt, TryStreamExt};
+use std::{fs, io::Write, path::Path};
+use tabwriter::TabWriter;
+
+/// Number of repositories whose deploy keys are fetched at the same time.
+const KEYS_CONCURRENCY: usize = 4;
+
+/// Prints deploy keys of a repository, or of all owned repositories that are neither forks nor
+/// archived.
+pub async fn print_deploy_keys(
+    app_env: AppEnv<'_>,
+    repo_id: Option<PartialRepoId>,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let gh_client = &app_env.github_client;
+
+    let repo_ids = match repo_id {
+        Some(x) => vec![x.complete(app_env.github_username)],
+        None => list_source_repositories(gh_client, app_env.github_username).await?,
+    };
+    let keys: Vec<(FullRepoId, Vec<GhDeployKey>)> = stream::iter(repo_ids)
+        .map(|repo_id| async move {
+            let keys = gh_client.list_deploy_keys(&repo_id).try_collect().await?;
+            Result::<_, Error>::Ok((repo_id, keys))
+        })
+        .buffered(KEYS_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    if keys.iter().all(|(_, xs)| xs.is_empty()) {
+        writeln!(out, "No deploy keys.")?;
+        return Ok(());
+    }
+
+    let mut w = TabWriter::new(Vec::new());
+    for (repo_id, xs) in &keys {
+        for x in xs {
+            writeln!(
+                w,
+                "{}\t{}\t{}\t{}\t{}",
+                repo_id,
+                x.id,
+                x.title,
+                if x.read_only {
+                    "read-only"
+                } else {
+                    "read-write"
+                },
+                x.created_at.since()
+            )?;
+        }
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    Ok(())
+}
+
+/// Adds a deploy key read from a public key file to a repository.
+pub async fn add_deploy_key(
+    mut app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    title: &str,
+    key_file: &Path,
+    write: bool,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let key = fs::read_to_string(key_file)
+        .with_context(|| format!("Failed to read {}.", key_file.display()))?;
+    let key = GhNewDeployKey {
+        title,
+        key: key.trim(),
+        read_only: !write,
+    };
+    if let Some(key) = app_env
+        .github_client
+        .create_deploy_key(&repo_id, &key)
+        .await?
+    {
+        record_not_undoable(
+            &mut app_env.database,
+            &repo_id.to_string(),
+            "deploy key addition",
+        )?;
+        writeln!(out, "Added deploy key {} to {repo_id}.", key.id)?;
+    }
+
+    Ok(())
+}
+
+/// Removes a deploy key from a repository.
+pub async fn remove_deploy_key(
+    mut app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    key_id: u64,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    app_env
+        .github_client
+        .delete_deploy_key(&repo_id, key_id)
+        .await?;
+    if !app_env.dry_run {
+        record_not_undoable(
+            &mut app_env.database,
+            &repo_id.to_string(),
+            "deploy key removal",
+        )?;
+        writeln!(out, "Removed deploy key {key_id} from {repo_id}.")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use crate::{
+    app_env::AppEnv, commands::undo::record_not_undoable, github_models::GhLabel,
+    repository_id::PartialRepoId,
+};
+use anyhow::{bail, Context, Error};
+use dialoguer::Confirm;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, io::Write, path::Path};
+
+/// Name, color, and description of a label.
+///
+/// This is also the format of an entry in a labels file.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct LabelSpec {
+    pub name: String,
+    pub color: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl From<GhLabel> for LabelSpec {
+    fn from(x: GhLabel) -> Self {
+        Self {
+            name: x.name,
+            color: x.color,
+            description: x.description.filter(|x| !x.is_empty()),
+        }
+    }
+}
+
+impl LabelSpec {
+    /// Normalizes the color to lower case hex without the leading `#`.
+    fn normalize(mut self) -> Self {
+        self.color = self.color.trim_start_matches('#').to_lowercase();
+        self
+    }
+}
+
+/// A change to make the target labels match the source labels.
+#[derive(PartialEq, Debug)]
+pub enum LabelChange {
+    Create(LabelSpec),
+    Update { old: LabelSpec, new: LabelSpec },
+    Delete(LabelSpec),
+}
+
+impl fmt::Display for LabelChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn describe(x: &LabelSpec) -> String {
+            match &x.description {
+                Some(description) => format!("#{} {:?}", x.color, description),
+                None => format!("#{}", x.color),
+            }
+        }
+        match self {
+            LabelChange::Create(x) => write!(f, "+ {} ({})", x.name, describe(x)),
+            LabelChange::Update { old, new } => {
+                write!(f, "~ {} ({} -> {})", old.name, describe(old), describe(new))
+            }
+            LabelChange::Delete(x) => write!(f, "- {} ({})", x.name, describe(x)),
+        }
+    }
+}
+
+/// Computes changes to make the target labels match the source labels.
+///
+/// Labels are matched by name, ignoring case, like GitHub does. Target labels missing from the
+/// source are deleted only when `prune` is set.
+pub fn diff_labels(source: &[LabelSpec], target: &[LabelSpec], prune: bool) -> Vec<LabelChange> {
+    let find = |labels: &[LabelSpec], name: &str| {
+        labels
+            .iter()
+            .find(|x| x.name.eq_ignore_ascii_case(name))
+            .cloned()
+    };
+
+    let mut changes = Vec::new();
+    for new in source {
+        let new = new.clone().normalize();
+        match find(target, &new.name).map(LabelSpec::normalize) {
+            None => changes.push(LabelChange::Create(new)),
+            Some(old) if old != new => changes.push(LabelChange::Update { old, new }),
+            Some(_) => (),
+        }
+    }
+    if prune {
+        for old in target {
+            if find(source, &old.name).is_none() {
+                changes.push(LabelChange::Delete(old.clone().normalize()));
+            }
+        }
+    }
+    changes
+}
+
+/// Reads labels from a YAML file containing a list of [LabelSpec].
+fn read_labels_file(path: &Path) -> Result<Vec<LabelSpec>, Error> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let labels = serde_yaml::from_str(&text)
+        .with_context(|| format!("Failed to parse labels file {}", path.display()))?;
+    Ok(labels)
+}
+
+/// Makes labels of a repository match labels of another repository or of a labels file.
+pub async fn sync_labels(
+    mut app_env: AppEnv<'_>,
+    from: Option<PartialRepoId>,
+    from_file: Option<&Path>,
+    to: PartialRepoId,
+    prune: bool,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let gh_client = &app_env.github_client;
+    let to = to.complete(app_env.github_username);
+
+    let source: Vec<LabelSpec> = match (from, from_file) {
+        (_, Some(path)) => read_labels_file(path)?,
+        (Some(from), None) => {
+            let from = from.complete(app_env.github_username);
+            gh_client
+                .list_labels(&from)
+                .map_ok(LabelSpec::from)
+                .try_collect()
+                .await?
+        }
+        (None, None) => bail!("Missing source of the labels."),
+    };
+    let target: Vec<LabelSpec> = gh_client
+        .list_labels(&to)
+        .map_ok(LabelSpec::from)
+        .try_collect()
+        .await?;
+
+    let changes = diff_labels(&source, &target, prune);
+    if changes.is_empty() {
+        writeln!(out, "Labels of {to} are already in sync.")?;
+        return Ok(());
+    }
+    for change in &changes {
+        eprintln!("{}", change);
+    }
+
+    if !app_env.dry_run
+        && !Confirm::new()
+            .with_prompt(format!("Apply {} label changes to {to}?", changes.len()))
+            .default(false)
+            .show_default(true)
+            .wait_for_newline(true)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    for change in &changes {
+        match change {
+            LabelChange::Create(new) => gh_client.create_label(&to, new).await?,
+            LabelChange::Update { old, new } => {
+                // `new_name` keeps the name casing of the source
+                let body = serde_json::json!({
+                    "new_name": new.name,
+                    "color": new.color,
+                    "description": new.description.as_deref().unwrap_or_default(),
+                });
+                gh_client.update_label(&to, &old.name, &body).await?
+            }
+            LabelChange::Delete(old) => gh_client.delete_label(&to, &old.name).await?,
+        }
+    }
+    if !app_env.dry_run {
+        record_not_undoable(&mut app_env.database, &to.to_string(), "labels sync")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[test]
+fn test_diff_labels() {
+    let label = |name: &str, color: &str, description: Option<&str>| LabelSpec {
+        name: name.to_owned(),
+        color: color.to_owned(),
+        description: description.map(ToOwned::to_owned),
+    };
+    let source = [
+        label("bug", "#D73A4A", Some("Something isn't working")),
+        label("Enhancement", "a2eeef", None),
+        label("question", "d876e3", None),
+    ];
+    let target = [
+        label("bug", "d73a4a", Some("Something isn't working")),
+        label("enhancement", "a2eeef", Some("New feature or request")),
+        label("wontfix", "ffffff", None),
+    ];
+
+    let changes = diff_labels(&source, &target, false);
+    assert_eq!(
+        changes,
+        [
+            LabelChange::Update {
+                old: label("enhancement", "a2eeef", Some("New feature or request")),
+                new: label("Enhancement", "a2eeef", None),
+            },
+            LabelChange::Create(label("question", "d876e3", None)),
+        ]
+    );
+
+    let changes = diff_labels(&source, &target, true);
+    assert_eq!(changes.len(), 3);
+    assert_eq!(
+        changes[2],
+        LabelChange::Delete(label("wontfix", "ffffff", None))
+    );
+}
@@ -0,0 +1,178 @@
+use crate::{app_env::AppEnv, list_local_repository_paths};
+use anyhow::Error;
+use git2::{Repository, Status, StatusOptions};
+use std::{collections::HashMap, fs, io::Write, path::Path};
+use tabwriter::TabWriter;
+use tokio::task;
+
+/// Languages by file extension.
+const LANGUAGES: &[(&str, &str)] = &[
+    ("c", "C"),
+    ("h", "C"),
+    ("cc", "C++"),
+    ("cpp", "C++"),
+    ("hpp", "C++"),
+    ("cs", "C#"),
+    ("css", "CSS"),
+    ("dart", "Dart"),
+    ("ex", "Elixir"),
+    ("exs", "Elixir"),
+    ("go", "Go"),
+    ("hs", "Haskell"),
+    ("html", "HTML"),
+    ("java", "Java"),
+    ("js", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("json", "JSON"),
+    ("kt", "Kotlin"),
+    ("lua", "Lua"),
+    ("md", "Markdown"),
+    ("nix", "Nix"),
+    ("php", "PHP"),
+    ("py", "Python"),
+    ("rb", "Ruby"),
+    ("rs", "Rust"),
+    ("scala", "Scala"),
+    ("sh", "Shell"),
+    ("bash", "Shell"),
+    ("sql", "SQL"),
+    ("swift", "Swift"),
+    ("toml", "TOML"),
+    ("ts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("yaml", "YAML"),
+    ("yml", "YAML"),
+    ("zig", "Zig"),
+];
+
+/// Files and lines counts.
+#[derive(PartialEq, Default, Clone, Copy, Debug)]
+struct Count {
+    files: usize,
+    lines: usize,
+}
+
+/// Prints files and lines counts per language of each local project and of the whole workspace.
+///
+/// Only files tracked by git or untracked but not ignored are counted.
+pub async fn print_languages(app_env: AppEnv<'_>) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let root = app_env.workspace_root_dir;
+    let paths = task::block_in_place(|| list_local_repository_paths(root))?;
+
+    let mut w = TabWriter::new(Vec::new());
+    let mut total = HashMap::new();
+    for path in &paths {
+        let name = path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        let counts = match task::block_in_place(|| count_languages(path)) {
+            Ok(x) => x,
+            Err(err) => {
+                writeln!(w, "{name}\terror: {err}\t\t")?;
+                continue;
+            }
+        };
+        writeln!(w, "{name}\t\t\t")?;
+        for (language, count) in &counts {
+            let x: &mut Count = total.entry(*language).or_default();
+            x.files += count.files;
+            x.lines += count.lines;
+        }
+        write_counts(&mut w, counts)?;
+    }
+    writeln!(w, "Workspace\t\t\t")?;
+    write_counts(&mut w, total)?;
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    Ok(())
+}
+
+fn write_counts(w: &mut impl Write, counts: HashMap<&str, Count>) -> Result<(), Error> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|(a_lang, a), (b_lang, b)| b.lines.cmp(&a.lines).then(a_lang.cmp(b_lang)));
+    for (language, count) in counts {
+        writeln!(
+            w,
+            "  {}\t{} files\t{} lines",
+            language, count.files, count.lines
+        )?;
+    }
+    Ok(())
+}
+
+/// Counts files and lines per language of a project working tree.
+fn count_languages(path: &Path) -> Result<HashMap<&'static str, Count>, Error> {
+    let repo = Repository::open(path)?;
+
+    let mut files: Vec<_> = repo
+        .index()?
+        .iter()
+        .map(|x| String::from_utf8_lossy(&x.path).into_owned())
+        .collect();
+    let untracked = repo.statuses(Some(
+        StatusOptions::new()
+            .include_untracked(true)
+            .recurse_untracked_dirs(true),
+    ))?;
+    files.extend(
+        untracked
+            .iter()
+            .filter(|x| x.status().contains(Status::WT_NEW))
+            .filter_map(|x| x.path().map(ToOwned::to_owned)),
+    );
+
+    let mut counts = HashMap::new();
+    for file in files {
+        let language = match language_of(&file) {
+            Some(x) => x,
+            None => continue,
+        };
+        // tracked files may have been deleted from the working tree
+        let content = match fs::read(path.join(&file)) {
+            Ok(x) => x,
+            Err(_) => continue,
+        };
+        let count: &mut Count = counts.entry(language).or_default();
+        count.files += 1;
+        count.lines += count_lines(&content);
+    }
+    Ok(counts)
+}
+
+fn count_lines(content: &[u8]) -> usize {
+    let newlines = content.iter().filter(|x| **x == b'\n').count();
+    // last line may not end with a newline
+    match content.last() {
+        Some(b'\n') | None => newlines,
+        Some(_) => newlines + 1,
+    }
+}
+
+fn language_of(file: &str) -> Option<&'static str> {
+    let ext = Path::new(file).extension()?.to_str()?;
+    LANGUAGES
+        .iter()
+        .find(|(x, _)| x.eq_ignore_ascii_case(ext))
+        .map(|(_, x)| *x)
+}
+
+#[cfg(test)]
+#[test]
+fn test_language_of() {
+    assert_eq!(language_of("src/main.rs"), Some("Rust"));
+    assert_eq!(language_of("web/App.TSX"), Some("TypeScript"));
+    assert_eq!(language_of("Makefile"), None);
+    assert_eq!(language_of("logo.png"), None);
+}
+
+#[cfg(test)]
+#[test]
+fn test_count_lines() {
+    assert_eq!(count_lines(b""), 0);
+    assert_eq!(count_lines(b"a\nb\n"), 2);
+    assert_eq!(count_lines(b"a\nb"), 2);
+}
@@ -0,0 +1,27 @@
+use crate::{app_env::AppEnv, display::AbsoluteTime};
+use anyhow::Error;
+use chrono::{TimeZone, Utc};
+use std::io::Write;
+use tabwriter::TabWriter;
+
+/// Prints used and remaining requests of each GitHub API rate limit, and when they reset.
+pub async fn print_rate_limits(app_env: AppEnv<'_>) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let rate_limit = app_env.github_client.get_rate_limit().await?;
+
+    let mut w = TabWriter::new(Vec::new());
+    writeln!(w, "resource\tused\tremaining\tresets at")?;
+    for (name, x) in &rate_limit.resources {
+        writeln!(
+            w,
+            "{name}\t{}/{}\t{}\t{}",
+            x.used,
+            x.limit,
+            x.remaining,
+            Utc.timestamp(x.reset, 0).at()
+        )?;
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    Ok(())
+}
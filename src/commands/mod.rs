@@ -1 +1,28 @@
+pub mod audit;
+pub mod auth;
+pub mod backup;
+pub mod clone;
+pub mod codeowners;
+pub mod collaborators;
+pub mod completions;
 pub mod dashboard;
+pub mod deps;
+pub mod doctor;
+pub mod feed;
+pub mod hooks;
+pub mod keys;
+pub mod labels;
+pub mod languages;
+pub mod limits;
+pub mod notifications;
+pub mod pulls;
+pub mod rename_branch;
+pub mod repos;
+pub mod runs;
+pub mod serve;
+pub mod settings;
+pub mod stars;
+pub mod tasks;
+pub mod topics;
+pub mod undo;
+pub mod workspace;
@@ -0,0 +1,73 @@
+use crate::{
+    app_env::AppEnv,
+    commands::undo::record_not_undoable,
+    display::{emojify, RelativeTime},
+};
+use anyhow::{bail, Error};
+use futures::{StreamExt, TryStreamExt};
+use std::io::Write;
+use tabwriter::TabWriter;
+
+/// Prints unread notifications, most recently updated first.
+pub async fn print_notifications(app_env: AppEnv<'_>) -> Result<(), Error> {
+    let mut out = app_env.output;
+
+    let notifications = app_env
+        .github_client
+        .list_notifications()
+        .take(app_env.limit.unwrap_or(usize::MAX))
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    if notifications.is_empty() {
+        writeln!(out, "No unread notifications.")?;
+        return Ok(());
+    }
+
+    let mut w = TabWriter::new(Vec::new());
+    for x in &notifications {
+        writeln!(
+            w,
+            "{}\t{}\t{}\t{}\t{}",
+            x.id,
+            x.reason,
+            x.repository.full_name,
+            emojify(&x.subject.title),
+            x.updated_at.since()
+        )?;
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    Ok(())
+}
+
+/// Marks a notification thread as read, or all notifications with `all`.
+pub async fn mark_notifications_read(
+    mut app_env: AppEnv<'_>,
+    thread_id: Option<String>,
+    all: bool,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+
+    match (thread_id, all) {
+        (Some(_), true) => bail!("Expecting either a thread id or --all, but was both."),
+        (None, false) => bail!("Missing thread id, or --all to mark all notifications as read."),
+        (Some(id), false) => {
+            app_env.github_client.mark_notification_read(&id).await?;
+            if !app_env.dry_run {
+                let target = format!("notifications/{id}");
+                record_not_undoable(&mut app_env.database, &target, "marking as read")?;
+                writeln!(out, "Marked notification {id} as read.")?;
+            }
+        }
+        (None, true) => {
+            app_env.github_client.mark_all_notifications_read().await?;
+            if !app_env.dry_run {
+                record_not_undoable(&mut app_env.database, "notifications", "marking as read")?;
+                writeln!(out, "Marked all notifications as read.")?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,397 @@
+use crate::{
+    app::{create_fetch_options, create_push_options, get_repo_id_for_cwd},
+    app_env::AppEnv,
+    commands::{dashboard::build_status_from_check_runs, undo::record_not_undoable},
+    create_local_repository_path,
+    display::{emojify, BuildStatusBadge, RelativeTime},
+    github_models::{GhNewPullRequest, GhPullRequest},
+    repository_id::PartialRepoId,
+};
+use anyhow::{bail, Context, Error};
+use dialoguer::Editor;
+use futures::{StreamExt, TryStreamExt};
+use git2::{build::CheckoutBuilder, BranchType, Repository};
+use std::{env, io::Write, path::Path};
+use tabwriter::TabWriter;
+use tokio::task;
+
+/// Prints open pull requests with their author, age, and CI status.
+pub async fn print_pull_requests(
+    app_env: AppEnv<'_>,
+    repo_id: Option<PartialRepoId>,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = match repo_id {
+        Some(x) => x.complete(app_env.github_username),
+        None => get_repo_id_for_cwd().await?,
+    };
+    let gh_client = &app_env.github_client;
+
+    let prs = gh_client
+        .list_open_pull_requests(&repo_id)
+        .take(app_env.limit.unwrap_or(usize::MAX))
+        .map_ok(|x| {
+            let repo_id = &repo_id;
+            async move {
+                let runs = gh_client
+                    .get_check_runs_for_gitref(repo_id, &x.head.sha)
+                    .await?;
+                Result::<_, Error>::Ok((x, build_status_from_check_runs(&runs)))
+            }
+        })
+        .try_buffered(4)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    if prs.is_empty() {
+        writeln!(out, "No open pull requests in {repo_id}.")?;
+        return Ok(());
+    }
+
+    let mut w = TabWriter::new(Vec::new());
+    for (pr, status) in &prs {
+        let author = pr
+            .user
+            .as_ref()
+            .map(|x| x.login.as_str())
+            .unwrap_or_default();
+        let status = status
+            .as_ref()
+            .map(|x| BuildStatusBadge::new(x).to_string())
+            .unwrap_or_default();
+        writeln!(
+            w,
+            "#{}\t{}\t{}\t{}\t{}",
+            pr.number,
+            emojify(&pr.title),
+            author,
+            pr.created_at.since(),
+            status
+        )?;
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    Ok(())
+}
+
+/// Creates a pull request from the current branch of the repository in the current directory,
+/// pushing the branch first when it has unpushed commits.
+///
+/// Without a title, title and body are edited in `$SHUB_EDITOR`, starting from the message of
+/// the latest commit.
+pub async fn create_pull_request(
+    mut app_env: AppEnv<'_>,
+    title: Option<String>,
+    body: Option<String>,
+    base: Option<String>,
+    draft: bool,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = get_repo_id_for_cwd().await?;
+    let (branch, message) = task::block_in_place(read_current_branch)?;
+
+    let base = match base {
+        Some(x) => x,
+        None => app_env
+            .github_client
+            .get_repository(&repo_id)
+            .await?
+            .default_branch
+            .context("Expecting repository to have default branch, but was not.")?,
+    };
+    if branch == base {
+        bail!("Current branch is the base branch {base}, switch to another branch.")
+    }
+
+    let (title, body) = match title {
+        Some(title) => (title, body.unwrap_or_default()),
+        None => {
+            let editor = env::var("SHUB_EDITOR")
+                .context("Missing title, pass --title or set SHUB_EDITOR to edit it.")?;
+            let template = match body {
+                Some(body) => format!("{}\n\n{body}", message.lines().next().unwrap_or_default()),
+                None => message,
+            };
+            let text = Editor::new()
+                .executable(editor)
+                .extension(".md")
+                .edit(&template)?;
+            match text.as_deref().and_then(parse_pull_request_text) {
+                Some(x) => x,
+                None => bail!("Aborted, pull request title is empty."),
+            }
+        }
+    };
+
+    let pushed = task::block_in_place(|| push_branch(&branch, app_env.dry_run, app_env.proxy))?;
+    if pushed && app_env.dry_run {
+        eprintln!("[dry-run] push {branch} to origin");
+    }
+
+    let pr = GhNewPullRequest {
+        title: &title,
+        body: &body,
+        head: &branch,
+        base: &base,
+        draft,
+    };
+    if let Some(pr) = app_env
+        .github_client
+        .create_pull_request(&repo_id, &pr)
+        .await?
+    {
+        let target = format!("{repo_id}#{}", pr.number);
+        record_not_undoable(&mut app_env.database, &target, "pull request creation")?;
+        writeln!(out, "{}", pr.html_url)?;
+    }
+
+    Ok(())
+}
+
+/// Reads current branch name and message of its latest commit.
+fn read_current_branch() -> Result<(String, String), Error> {
+    let repo = Repository::discover(".")?;
+    let head = repo.head()?;
+    if !head.is_branch() {
+        bail!("HEAD is detached, check out a branch first.")
+    }
+    let branch = head.shorthand().unwrap_or_default().to_owned();
+    let message = head
+        .peel_to_commit()?
+        .message()
+        .unwrap_or_default()
+        .to_owned();
+    Ok((branch, message))
+}
+
+/// Pushes a branch to origin when it has no upstream or has unpushed commits, returns whether
+/// it needs pushing.
+fn push_branch(branch: &str, dry_run: bool, proxy: Option<&str>) -> Result<bool, Error> {
+    let repo = Repository::discover(".")?;
+    let mut local = repo.find_branch(branch, BranchType::Local)?;
+    let target = local
+        .get()
+        .target()
+        .expect("branch should point to a commit");
+
+    let has_upstream = match local.upstream() {
+        Ok(upstream) => {
+            let remote = upstream
+                .get()
+                .target()
+                .expect("branch should point to a commit");
+            let (ahead, _) = repo.graph_ahead_behind(target, remote)?;
+            if ahead == 0 {
+                return Ok(false);
+            }
+            true
+        }
+        Err(_) => false,
+    };
+    if dry_run {
+        return Ok(true);
+    }
+
+    let mut remote = repo.find_remote("origin")?;
+    remote.push(
+        &[format!("refs/heads/{branch}:refs/heads/{branch}")],
+        Some(&mut create_push_options(proxy)),
+    )?;
+    if !has_upstream {
+        local.set_upstream(Some(&format!("origin/{branch}")))?;
+    }
+
+    Ok(true)
+}
+
+/// Splits edited text into title, the first line, and body, the remaining lines.
+///
+/// Returns `None` when the title is empty.
+fn parse_pull_request_text(text: &str) -> Option<(String, String)> {
+    let text = text.trim();
+    let (title, body) = text.split_once('\n').unwrap_or((text, ""));
+    let title = title.trim();
+    if title.is_empty() {
+        return None;
+    }
+    Some((title.to_owned(), body.trim().to_owned()))
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_pull_request_text() {
+    assert_eq!(
+        parse_pull_request_text("Add foo\n\nFoo is added.\nBar is not.\n"),
+        Some((
+            "Add foo".to_owned(),
+            "Foo is added.\nBar is not.".to_owned()
+        ))
+    );
+    assert_eq!(
+        parse_pull_request_text("\n  Add foo  \n"),
+        Some(("Add foo".to_owned(), String::new()))
+    );
+    assert_eq!(parse_pull_request_text(" \n\n"), None);
+}
+
+/// Fetches head of a pull request into the local clone of its repository and checks it out as
+/// `pr-:number` branch.
+pub async fn checkout_pull_request(
+    app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    number: u64,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+    let path = create_local_repository_path(app_env.workspace_root_dir, &repo_id);
+    if !path.exists() {
+        bail!("{repo_id} is not cloned, clone it with `shub r clone {repo_id}`.")
+    }
+
+    let branch = format!("pr-{number}");
+    if app_env.dry_run {
+        eprintln!(
+            "[dry-run] fetch pull/{number}/head to {branch} in {}",
+            path.display()
+        );
+        return Ok(());
+    }
+    task::block_in_place(|| fetch_and_checkout(&path, number, &branch, app_env.proxy))?;
+    writeln!(
+        out,
+        "Checked out {repo_id}#{number} as {branch} in {}.",
+        path.display()
+    )?;
+
+    Ok(())
+}
+
+fn fetch_and_checkout(
+    path: &Path,
+    number: u64,
+    branch: &str,
+    proxy: Option<&str>,
+) -> Result<(), Error> {
+    let repo = Repository::open(path)?;
+
+    let remote_ref = format!("refs/remotes/origin/pr/{number}");
+    let mut remote = repo.find_remote("origin")?;
+    remote.fetch(
+        &[format!("+refs/pull/{number}/head:{remote_ref}")],
+        Some(&mut create_fetch_options(proxy)),
+        None,
+    )?;
+    let commit = repo.find_reference(&remote_ref)?.peel_to_commit()?;
+
+    match repo.find_branch(branch, BranchType::Local) {
+        Ok(mut local) => {
+            // only move an existing branch forward, it may have local commits
+            let target = local
+                .get()
+                .target()
+                .expect("branch should point to a commit");
+            if target != commit.id() {
+                if !repo.graph_descendant_of(commit.id(), target)? {
+                    bail!("{branch} exists and has diverged from the pull request head")
+                }
+                if local.is_head() {
+                    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))?;
+                }
+                local
+                    .get_mut()
+                    .set_target(commit.id(), &format!("shub: update {branch}"))?;
+            }
+        }
+        Err(_) => {
+            repo.branch(branch, &commit, false)?;
+        }
+    }
+
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))?;
+    repo.set_head(&format!("refs/heads/{branch}"))?;
+
+    Ok(())
+}
+
+/// Prints open pull requests with their mergeability, flagging the ones that need attention.
+pub async fn print_pull_request_conflicts(
+    app_env: AppEnv<'_>,
+    repo_id: Option<PartialRepoId>,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = match repo_id {
+        Some(x) => x.complete(app_env.github_username),
+        None => get_repo_id_for_cwd().await?,
+    };
+    let gh_client = &app_env.github_client;
+
+    // mergeability is only available when getting a single pull request
+    let prs = gh_client
+        .list_open_pull_requests(&repo_id)
+        .take(app_env.limit.unwrap_or(usize::MAX))
+        .map_ok(|x| gh_client.get_pull_request(&repo_id, x.number))
+        .try_buffered(4)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let mut w = TabWriter::new(Vec::new());
+    for pr in &prs {
+        let author = pr
+            .user
+            .as_ref()
+            .map(|x| x.login.as_str())
+            .unwrap_or_default();
+        writeln!(
+            w,
+            "#{}\t{}\t{}\t{}\t{}\t{}",
+            pr.number,
+            emojify(&pr.title),
+            author,
+            pr.mergeable_state.as_deref().unwrap_or("unknown"),
+            Mergeability::from_github_pull_request(pr),
+            pr.updated_at.since()
+        )?;
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    Ok(())
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+enum Mergeability {
+    Mergeable,
+    Conflicts,
+    ChecksFailing,
+    Behind,
+    Unknown,
+}
+
+impl Mergeability {
+    fn from_github_pull_request(pr: &GhPullRequest) -> Self {
+        use Mergeability::*;
+        match pr.mergeable_state.as_deref() {
+            Some("dirty") => Conflicts,
+            Some("blocked") | Some("unstable") => ChecksFailing,
+            Some("behind") => Behind,
+            Some("clean") | Some("has_hooks") => Mergeable,
+            _ => match pr.mergeable {
+                Some(false) => Conflicts,
+                _ => Unknown,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Mergeability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Mergeability::*;
+        let s = match self {
+            Mergeable => "",
+            Conflicts => "needs rebase",
+            ChecksFailing => "checks failing",
+            Behind => "behind base",
+            Unknown => "?",
+        };
+        f.write_str(s)
+    }
+}
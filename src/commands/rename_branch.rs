@@ -0,0 +1,110 @@
+use crate::{
+    app::create_fetch_options,
+    app_env::AppEnv,
+    create_local_repository_path,
+    repository_id::{FullRepoId, PartialRepoId},
+    types::MutationKind,
+};
+use anyhow::{Context, Error};
+use git2::{BranchType, Repository};
+use serde::{Deserialize, Serialize};
+use std::{io::Write, path::Path};
+use tokio::task;
+
+/// Undo data of a branch rename.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RenamedBranch {
+    pub old: String,
+    pub new: String,
+}
+
+/// Renames a remote branch and updates the local clone, if there is one, to follow it.
+pub async fn rename_branch(
+    mut app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    old: &str,
+    new: &str,
+) -> Result<(), Error> {
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    do_rename_branch(&app_env, &repo_id, old, new).await?;
+
+    if !app_env.dry_run {
+        let data = RenamedBranch {
+            old: old.to_owned(),
+            new: new.to_owned(),
+        };
+        app_env.database.put_mutation(
+            MutationKind::RenameBranch,
+            &repo_id.to_string(),
+            &serde_json::to_string(&data)?,
+        )?;
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn do_rename_branch(
+    app_env: &AppEnv<'_>,
+    repo_id: &FullRepoId,
+    old: &str,
+    new: &str,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    app_env
+        .github_client
+        .rename_branch(repo_id, old, new)
+        .await?;
+    if !app_env.dry_run {
+        writeln!(out, "Renamed {repo_id} branch {old} to {new}.")?;
+    }
+
+    let path = create_local_repository_path(app_env.workspace_root_dir, repo_id);
+    if !path.exists() {
+        return Ok(());
+    }
+    if app_env.dry_run {
+        eprintln!("[dry-run] update local clone at {}", path.display());
+        return Ok(());
+    }
+    task::block_in_place(|| update_local_clone(&path, old, new, app_env.proxy))
+        .with_context(|| format!("Failed to update local clone at {}.", path.display()))?;
+    writeln!(out, "Updated local clone at {}.", path.display())?;
+
+    Ok(())
+}
+
+/// Renames local branch, points it to the renamed remote branch, and updates `origin/HEAD` if
+/// it was pointing to the old branch.
+fn update_local_clone(path: &Path, old: &str, new: &str, proxy: Option<&str>) -> Result<(), Error> {
+    let repo = Repository::open(path)?;
+
+    // fetch the renamed branch and prune the old one
+    let mut remote = repo.find_remote("origin")?;
+    let mut options = create_fetch_options(proxy);
+    options.prune(git2::FetchPrune::On);
+    remote.fetch::<&str>(&[], Some(&mut options), None)?;
+
+    let origin_head = "refs/remotes/origin/HEAD";
+    let points_to_old = repo
+        .find_reference(origin_head)
+        .ok()
+        .and_then(|x| x.symbolic_target().map(ToOwned::to_owned))
+        .map(|x| x == format!("refs/remotes/origin/{old}"))
+        .unwrap_or_default();
+    if points_to_old {
+        repo.reference_symbolic(
+            origin_head,
+            &format!("refs/remotes/origin/{new}"),
+            true,
+            "shub: rename branch",
+        )?;
+    }
+
+    if let Ok(mut branch) = repo.find_branch(old, BranchType::Local) {
+        let mut branch = branch.rename(new, false)?;
+        branch.set_upstream(Some(&format!("origin/{new}")))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,777 @@
+use crate::{
+    app::get_repo_id_for_cwd,
+    app_env::AppEnv,
+    cli::repos::{RepoSort, RepoType, SortDirection},
+    commands::{dashboard::build_status_from_check_runs, undo::record_not_undoable},
+    create_local_repository_path,
+    display::{emojify, BuildStatusBadge, CommitInfo, Markdown, RelativeTime},
+    github_client2::GithubClient2,
+    github_models::{GhCommit, GhRepository},
+    process,
+    repository_id::{FullRepoId, IsPartialRepositoryId, PartialRepoId},
+    types::MutationKind,
+    OwnedRepository,
+};
+use anyhow::{bail, Context, Error};
+use dialoguer::{Confirm, Input};
+use futures::{future, stream, try_join, StreamExt, TryStreamExt};
+use git2::{BranchType, ObjectType, Oid, Repository, StatusOptions, TreeWalkMode, TreeWalkResult};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{collections::HashMap, fs, io::Write, path::Path};
+use tabwriter::TabWriter;
+use tokio::task;
+use tracing::warn;
+
+/// Changelog file names, in order of preference.
+const CHANGELOG_FILES: [&str; 4] = ["CHANGELOG.md", "CHANGES.md", "HISTORY.md", "RELEASES.md"];
+
+/// Number of largest blobs to print in the size report.
+const LARGEST_BLOBS_COUNT: usize = 10;
+
+/// Number of latest commits of listed repositories fetched at the same time.
+const COMMIT_FETCH_CONCURRENCY: usize = 8;
+
+/// Page of a repository on GitHub.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum RepoPage {
+    Home,
+    /// Home of the upstream repository of a fork.
+    Upstream,
+    PullRequests,
+    Issues,
+    Actions,
+    Settings,
+}
+
+/// Opens a page of a repository, of the current directory when there is none, in the browser.
+pub async fn browse_repository(
+    app_env: AppEnv<'_>,
+    repo_id: Option<PartialRepoId>,
+    page: RepoPage,
+) -> Result<(), Error> {
+    let repo_id = match repo_id {
+        Some(x) => x.complete(app_env.github_username),
+        None => get_repo_id_for_cwd().await?,
+    };
+
+    // the URL comes from the API to also work with GitHub Enterprise Server
+    let repo = app_env.github_client.get_repository(&repo_id).await?;
+    let html_url = if page == RepoPage::Upstream {
+        if !repo.fork.unwrap_or_default() {
+            bail!("Repository {repo_id} is not a fork.")
+        }
+        repo.parent.and_then(|x| x.html_url)
+    } else {
+        repo.html_url
+    }
+    .with_context(|| format!("Repository {repo_id} has no HTML URL."))?;
+
+    process::open_url(&page_url(html_url.as_str(), page))
+}
+
+fn page_url(html_url: &str, page: RepoPage) -> String {
+    let path = match page {
+        RepoPage::Home | RepoPage::Upstream => return html_url.to_owned(),
+        RepoPage::PullRequests => "pulls",
+        RepoPage::Issues => "issues",
+        RepoPage::Actions => "actions",
+        RepoPage::Settings => "settings",
+    };
+    format!("{}/{path}", html_url.trim_end_matches('/'))
+}
+
+#[cfg(test)]
+#[test]
+fn test_page_url() {
+    let url = "https://github.com/kafji/shub";
+    assert_eq!(page_url(url, RepoPage::Home), url);
+    assert_eq!(
+        page_url(url, RepoPage::PullRequests),
+        "https://github.com/kafji/shub/pulls"
+    );
+    assert_eq!(
+        page_url("https://github.example.com/kafji/shub/", RepoPage::Settings),
+        "https://github.example.com/kafji/shub/settings"
+    );
+}
+
+/// Prints owned repositories, or repositories of an organization, in the given order, with
+/// their latest commit unless `no_commit` is set.
+///
+/// Repositories are printed as they arrive, latest commits of the next ones are fetched ahead
+/// while waiting for one. Size order is not supported by the API, repositories are sorted after
+/// fetching all of them.
+pub async fn print_owned_repositories(
+    app_env: AppEnv<'_>,
+    org: Option<String>,
+    ty: Option<RepoType>,
+    sort: Option<RepoSort>,
+    direction: Option<SortDirection>,
+    no_commit: bool,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let gh_client = &app_env.github_client;
+    let limit = app_env.limit.unwrap_or(usize::MAX);
+
+    let api_type = ty.map(|x| match x {
+        RepoType::All => "all",
+        // an organization owns all of its repositories
+        RepoType::Owner if org.is_some() => "all",
+        RepoType::Owner => "owner",
+        RepoType::Public => "public",
+        RepoType::Private => "private",
+        RepoType::Member => "member",
+    });
+
+    let api_sort = sort.and_then(|x| match x {
+        RepoSort::Pushed => Some("pushed"),
+        RepoSort::Created => Some("created"),
+        RepoSort::Name => Some("full_name"),
+        RepoSort::Size => None,
+    });
+    let api_direction = direction.map(|x| match x {
+        SortDirection::Asc => "asc",
+        SortDirection::Desc => "desc",
+    });
+    let repos = match &org {
+        Some(org) => gh_client
+            .list_organization_repositories_sorted(org, api_type, api_sort, api_direction)
+            .boxed_local(),
+        None => gh_client
+            .list_owned_repositories_sorted(api_type, api_sort, api_direction)
+            .boxed_local(),
+    };
+    let repos = if let Some(RepoSort::Size) = sort {
+        let mut repos = repos.try_collect::<Vec<_>>().await?;
+        repos.sort_by_key(|x| x.size.unwrap_or_default());
+        if !matches!(direction, Some(SortDirection::Asc)) {
+            repos.reverse();
+        }
+        repos.truncate(limit);
+        stream::iter(repos.into_iter().map(Ok)).boxed_local()
+    } else {
+        repos.take(limit).boxed_local()
+    };
+
+    let mut repos = repos
+        .map_ok(|repo| async move {
+            let commit = if no_commit {
+                None
+            } else {
+                get_latest_commit(gh_client, &repo).await
+            };
+            Ok(OwnedRepository(repo, commit))
+        })
+        .try_buffered(COMMIT_FETCH_CONCURRENCY);
+    while let Some(repo) = repos.try_next().await? {
+        writeln!(out, "{}", repo)?;
+    }
+
+    Ok(())
+}
+
+/// Lists owned repositories that are neither forks nor archived.
+pub(crate) async fn list_source_repositories(
+    gh_client: &GithubClient2,
+    username: &str,
+) -> Result<Vec<FullRepoId>, Error> {
+    gh_client
+        .list_owned_repositories()
+        .try_filter(|x| {
+            let owned = x.owner() == Some(username);
+            let a_fork = x.fork.unwrap_or_default();
+            let archived = x.archived.unwrap_or_default();
+            future::ready(owned && !a_fork && !archived)
+        })
+        .map_ok(|x| FullRepoId {
+            owner: username.to_owned(),
+            name: x.name,
+        })
+        .try_collect()
+        .await
+}
+
+/// Fetches the latest commit of a listed repository, `None` when it has none or it can't be
+/// fetched, e.g. the repository is empty.
+async fn get_latest_commit(gh_client: &GithubClient2, repo: &GhRepository) -> Option<GhCommit> {
+    let repo_id = FullRepoId {
+        owner: repo.owner().unwrap_or_default().to_owned(),
+        name: repo.name.clone(),
+    };
+    match gh_client.get_latest_commit(&repo_id).await {
+        Ok(x) => x,
+        Err(err) => {
+            warn!(?err, %repo_id, "failed to fetch latest commit");
+            None
+        }
+    }
+}
+
+/// Prints overview of a repository.
+///
+/// The repository, its latest commit, latest release, and open pull requests are fetched at the
+/// same time, the build status follows once the latest commit is known.
+pub async fn print_repository_summary(
+    app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let gh_client = &app_env.github_client;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let (repo, commit, release, open_prs) = try_join!(
+        gh_client.get_repository(&repo_id),
+        gh_client.get_latest_commit(&repo_id),
+        gh_client.get_latest_release(&repo_id),
+        gh_client.count_open_pull_requests(&repo_id),
+    )?;
+    let build_status = match &commit {
+        Some(x) => {
+            let runs = gh_client
+                .get_check_runs_for_gitref(&repo_id, &x.sha)
+                .await?;
+            build_status_from_check_runs(&runs)
+        }
+        None => None,
+    };
+
+    writeln!(out, "{repo_id}")?;
+    if let Some(description) = repo.description.as_deref().filter(|x| !x.is_empty()) {
+        writeln!(out, "{}", emojify(description))?;
+    }
+    writeln!(out)?;
+
+    let mut w = TabWriter::new(Vec::new());
+    let visibility = match repo.private {
+        Some(true) => "private",
+        Some(false) => "public",
+        None => "-",
+    };
+    writeln!(w, "Visibility\t{visibility}")?;
+    writeln!(
+        w,
+        "Default branch\t{}",
+        repo.default_branch.as_deref().unwrap_or("-")
+    )?;
+    // open issues count of GitHub includes pull requests
+    let open_issues =
+        u64::from(repo.open_issues_count.unwrap_or_default()).saturating_sub(open_prs);
+    writeln!(w, "Open issues\t{open_issues}")?;
+    writeln!(w, "Open pull requests\t{open_prs}")?;
+    match &release {
+        Some(x) => {
+            let published = x
+                .published_at
+                .as_ref()
+                .map(|x| format!(" ({})", x.since()))
+                .unwrap_or_default();
+            writeln!(w, "Latest release\t{}{}", x.tag_name, published)?
+        }
+        None => writeln!(w, "Latest release\t-")?,
+    }
+    match &build_status {
+        Some(x) => writeln!(w, "Build\t{}", BuildStatusBadge::new(x))?,
+        None => writeln!(w, "Build\t-")?,
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    if let Some(commit) = &commit {
+        writeln!(out)?;
+        write!(out, "{}", CommitInfo::from_github_commit(commit))?;
+    }
+
+    Ok(())
+}
+
+/// Undo data of archiving or unarchiving a repository.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ArchivedRepository {
+    /// Whether it was archived before.
+    pub archived: bool,
+}
+
+/// Undo data of a repository rename.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RenamedRepository {
+    /// Name before the rename.
+    pub old: String,
+}
+
+/// Archives or unarchives a repository after confirming it.
+pub async fn set_repository_archived(
+    mut app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    archived: bool,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let repo = app_env.github_client.get_repository(&repo_id).await?;
+    if repo.archived.unwrap_or_default() == archived {
+        let state = if archived { "archived" } else { "not archived" };
+        writeln!(out, "Repository {repo_id} is already {state}.")?;
+        return Ok(());
+    }
+
+    // the preview is part of the prompt, not of the output
+    eprintln!("{repo_id}");
+    if let Some(description) = repo.description.as_deref().filter(|x| !x.is_empty()) {
+        eprintln!("{}", emojify(description));
+    }
+    if let Some(pushed_at) = &repo.pushed_at {
+        eprintln!("Last pushed {}.", pushed_at.since());
+    }
+
+    let action = if archived { "Archive" } else { "Unarchive" };
+    if !app_env.dry_run
+        && !Confirm::new()
+            .with_prompt(format!("{action} {repo_id}?"))
+            .default(false)
+            .show_default(true)
+            .wait_for_newline(true)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    app_env
+        .github_client
+        .update_repository(&repo_id, &json!({ "archived": archived }))
+        .await?;
+    if !app_env.dry_run {
+        let data = ArchivedRepository {
+            archived: !archived,
+        };
+        app_env.database.put_mutation(
+            MutationKind::SetArchived,
+            &repo_id.to_string(),
+            &serde_json::to_string(&data)?,
+        )?;
+        let state = if archived { "Archived" } else { "Unarchived" };
+        writeln!(out, "{state} {repo_id}.")?;
+    }
+
+    Ok(())
+}
+
+/// Deletes a repository after confirming it by typing its full name, and optionally its local
+/// clone.
+///
+/// The local clone is not deleted when it has work that's not on any remote, unless forced.
+pub async fn delete_repository(
+    mut app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    yes: bool,
+    local: bool,
+    force: bool,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    // fails early for a repository that doesn't exist
+    let repo = app_env.github_client.get_repository(&repo_id).await?;
+
+    // checked before deleting the repository, after which the clone may be the only copy
+    let path = create_local_repository_path(app_env.workspace_root_dir, &repo_id);
+    if local && !force && path.exists() {
+        let unsaved = task::block_in_place(|| find_unsaved_work(&path))?;
+        if !unsaved.is_empty() {
+            bail!(
+                "Local clone {} has {}, delete with --force to lose them.",
+                path.display(),
+                unsaved.join(", ")
+            )
+        }
+    }
+
+    if !app_env.dry_run && !yes {
+        eprintln!("{repo_id}");
+        if let Some(description) = repo.description.as_deref().filter(|x| !x.is_empty()) {
+            eprintln!("{}", emojify(description));
+        }
+        eprintln!("This deletes the repository with its issues, pull requests, and wiki.");
+        let name: String = Input::new()
+            .with_prompt(format!("Type {repo_id} to confirm"))
+            .allow_empty(true)
+            .interact_text()?;
+        if name != repo_id.to_string() {
+            bail!("Typed `{}` doesn't match {repo_id}.", name)
+        }
+    }
+
+    app_env.github_client.delete_repository(&repo_id).await?;
+    if !app_env.dry_run {
+        record_not_undoable(
+            &mut app_env.database,
+            &repo_id.to_string(),
+            "repository deletion",
+        )?;
+        writeln!(out, "Deleted {repo_id}.")?;
+    }
+
+    if local {
+        if !path.exists() {
+            writeln!(out, "Repository {repo_id} has no local clone.")?;
+        } else if app_env.dry_run {
+            writeln!(out, "Would delete {}.", path.display())?;
+        } else {
+            fs::remove_dir_all(&path)
+                .with_context(|| format!("Failed to delete {}.", path.display()))?;
+            writeln!(out, "Deleted {}.", path.display())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds work of a local clone that's not on any remote: uncommitted changes, stashes, and local
+/// branches whose commits no remote branch has.
+fn find_unsaved_work(path: &Path) -> Result<Vec<String>, Error> {
+    let mut repo = Repository::open(path)?;
+    let mut unsaved = Vec::new();
+
+    let dirty = repo
+        .statuses(Some(
+            StatusOptions::new()
+                .include_untracked(true)
+                .include_ignored(false),
+        ))?
+        .iter()
+        .any(|x| !x.status().is_ignored());
+    if dirty {
+        unsaved.push("uncommitted changes".to_owned());
+    }
+
+    let mut stashed = false;
+    repo.stash_foreach(|_, _, _| {
+        stashed = true;
+        false
+    })?;
+    if stashed {
+        unsaved.push("stashed changes".to_owned());
+    }
+
+    // symbolic references, e.g. `origin/HEAD`, have no target
+    let remote_heads: Vec<Oid> = repo
+        .references_glob("refs/remotes/*")?
+        .filter_map(|x| x.ok()?.target())
+        .collect();
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        let head = match branch.get().target() {
+            Some(x) => x,
+            None => continue,
+        };
+        let pushed = remote_heads
+            .iter()
+            .any(|&x| x == head || repo.graph_descendant_of(x, head).unwrap_or(false));
+        if !pushed {
+            let name = branch.name()?.unwrap_or_default();
+            unsaved.push(format!("unpushed branch {name}"));
+        }
+    }
+
+    Ok(unsaved)
+}
+
+/// Renames a repository, then points `origin` of its local clone to the new URL and moves the
+/// clone to the path of the new name.
+pub async fn rename_repository(
+    mut app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    new_name: &str,
+) -> Result<(), Error> {
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let new_id = do_rename_repository(&app_env, &repo_id, new_name).await?;
+    if let Some(new_id) = new_id {
+        let data = RenamedRepository {
+            old: repo_id.name.clone(),
+        };
+        app_env.database.put_mutation(
+            MutationKind::RenameRepository,
+            &new_id.to_string(),
+            &serde_json::to_string(&data)?,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Renames a repository and updates its local clone, returning the new identifier, none on dry
+/// run.
+pub(crate) async fn do_rename_repository(
+    app_env: &AppEnv<'_>,
+    repo_id: &FullRepoId,
+    new_name: &str,
+) -> Result<Option<FullRepoId>, Error> {
+    let mut out = app_env.output;
+
+    let repo = match app_env
+        .github_client
+        .rename_repository(repo_id, new_name)
+        .await?
+    {
+        Some(x) => x,
+        None => return Ok(None),
+    };
+    // the full name GitHub returns is authoritative, e.g. when the owner was renamed too
+    let new_id: FullRepoId = match &repo.full_name {
+        Some(x) => x.parse()?,
+        None => FullRepoId {
+            owner: repo_id.owner.clone(),
+            name: repo.name.clone(),
+        },
+    };
+    writeln!(out, "Renamed {repo_id} to {new_id}.")?;
+
+    let old_path = create_local_repository_path(app_env.workspace_root_dir, repo_id);
+    if !old_path.exists() {
+        return Ok(Some(new_id));
+    }
+    let new_path = create_local_repository_path(app_env.workspace_root_dir, &new_id);
+    if new_path.exists() {
+        bail!(
+            "Can not move local clone to {}, it already exists.",
+            new_path.display()
+        )
+    }
+    task::block_in_place(|| {
+        let clone = Repository::open(&old_path)?;
+        // keeps the scheme of the current URL, HTTPS clones may have no SSH key to push with
+        let https = match clone.find_remote("origin") {
+            Ok(x) => x.url().map_or(false, |x| x.starts_with("https://")),
+            Err(_) => false,
+        };
+        let url = if https {
+            repo.clone_url.as_ref().map(ToString::to_string)
+        } else {
+            repo.ssh_url.clone()
+        };
+        if let Some(url) = url {
+            clone.remote_set_url("origin", &url)?;
+        }
+        drop(clone);
+        if let Some(parent) = new_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&old_path, &new_path)?;
+        Result::<_, Error>::Ok(())
+    })
+    .with_context(|| format!("Failed to update local clone {}.", old_path.display()))?;
+    writeln!(out, "Moved local clone to {}.", new_path.display())?;
+
+    Ok(Some(new_id))
+}
+
+/// Prints README of a repository.
+pub async fn print_readme(app_env: AppEnv<'_>, repo_id: PartialRepoId) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let readme = match app_env.github_client.get_readme(&repo_id).await? {
+        Some(x) => x,
+        None => {
+            writeln!(out, "Repository {repo_id} has no README.")?;
+            return Ok(());
+        }
+    };
+    write!(out, "{}", Markdown::new(&String::from_utf8_lossy(&readme)))?;
+
+    Ok(())
+}
+
+/// Prints changelog of a repository, looking for the commonly used changelog files in the
+/// repository root.
+pub async fn print_changelog(app_env: AppEnv<'_>, repo_id: PartialRepoId) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    for file in CHANGELOG_FILES {
+        if let Some(changelog) = app_env
+            .github_client
+            .get_file_content(&repo_id, file)
+            .await?
+        {
+            write!(
+                out,
+                "{}",
+                Markdown::new(&String::from_utf8_lossy(&changelog))
+            )?;
+            return Ok(());
+        }
+    }
+    writeln!(out, "Repository {repo_id} has no changelog.")?;
+
+    Ok(())
+}
+
+/// Prints size of a repository as reported by the API and, if it has a local clone, size of its
+/// object store and its largest blobs.
+pub async fn print_repository_size(
+    app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let repo = app_env.github_client.get_repository(&repo_id).await?;
+    // the API reports size in kilobytes
+    let size = repo.size.unwrap_or_default() as u64 * 1024;
+    writeln!(out, "{repo_id}")?;
+    writeln!(out, "  remote: {}", format_size(size))?;
+
+    let path = create_local_repository_path(app_env.workspace_root_dir, &repo_id);
+    if !path.exists() {
+        writeln!(out, "Repository {repo_id} has no local clone.")?;
+        return Ok(());
+    }
+
+    let (objects_size, blobs) = task::block_in_place(|| {
+        let objects_size = dir_size(&path.join(".git").join("objects"))?;
+        let blobs = find_largest_blobs(&path, LARGEST_BLOBS_COUNT)?;
+        Result::<_, Error>::Ok((objects_size, blobs))
+    })?;
+    writeln!(out, "  local objects: {}", format_size(objects_size))?;
+
+    writeln!(out)?;
+    writeln!(out, "Largest blobs")?;
+    let mut w = TabWriter::new(Vec::new());
+    for (oid, size, path) in blobs {
+        writeln!(
+            w,
+            "  {}\t{}\t{}",
+            oid,
+            format_size(size as _),
+            path.as_deref().unwrap_or("(not in HEAD)")
+        )?;
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    Ok(())
+}
+
+/// Sums sizes of files under a directory.
+fn dir_size(path: &Path) -> Result<u64, Error> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        if meta.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += meta.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Finds the largest blobs in a repository object database, with their paths in HEAD tree if
+/// they are still there.
+fn find_largest_blobs(
+    path: &Path,
+    count: usize,
+) -> Result<Vec<(Oid, usize, Option<String>)>, Error> {
+    let repo = Repository::open(path)?;
+    let odb = repo.odb()?;
+
+    let mut blobs = Vec::new();
+    let mut err = None;
+    odb.foreach(|oid| match odb.read_header(*oid) {
+        Ok((size, ObjectType::Blob)) => {
+            blobs.push((*oid, size));
+            true
+        }
+        Ok(_) => true,
+        Err(x) => {
+            err = Some(x);
+            false
+        }
+    })?;
+    if let Some(err) = err {
+        return Err(err.into());
+    }
+    blobs.sort_by(|(_, a), (_, b)| b.cmp(a));
+    blobs.truncate(count);
+
+    let mut paths = HashMap::new();
+    if let Ok(head) = repo.head().and_then(|x| x.peel_to_tree()) {
+        head.walk(TreeWalkMode::PreOrder, |dir, entry| {
+            if blobs.iter().any(|(oid, _)| *oid == entry.id()) {
+                let name = entry.name().unwrap_or_default();
+                paths.insert(entry.id(), format!("{dir}{name}"));
+            }
+            TreeWalkResult::Ok
+        })?;
+    }
+
+    let blobs = blobs
+        .into_iter()
+        .map(|(oid, size)| (oid, size, paths.remove(&oid)))
+        .collect();
+    Ok(blobs)
+}
+
+/// Formats bytes count with binary unit prefixes.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024. && unit < UNITS.len() - 1 {
+        size /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_format_size() {
+    assert_eq!(format_size(512), "512 B");
+    assert_eq!(format_size(1536), "1.5 KiB");
+    assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+}
+
+#[cfg(test)]
+#[test]
+fn test_find_unsaved_work() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(dir.path()).unwrap();
+    assert!(find_unsaved_work(dir.path()).unwrap().is_empty());
+
+    fs::write(dir.path().join("README.md"), "shub").unwrap();
+    assert_eq!(
+        find_unsaved_work(dir.path()).unwrap(),
+        ["uncommitted changes"]
+    );
+
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("README.md")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("kafji", "kafji@example.com").unwrap();
+    let commit = repo
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Add readme",
+            &tree,
+            &[],
+        )
+        .unwrap();
+    let branch = repo.head().unwrap().shorthand().unwrap().to_owned();
+    assert_eq!(
+        find_unsaved_work(dir.path()).unwrap(),
+        [format!("unpushed branch {branch}")]
+    );
+
+    repo.reference("refs/remotes/origin/main", commit, false, "push")
+        .unwrap();
+    assert!(find_unsaved_work(dir.path()).unwrap().is_empty());
+}
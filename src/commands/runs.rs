@@ -0,0 +1,192 @@
+use crate::{
+    app_env::AppEnv, commands::undo::record_not_undoable, display::RelativeTime,
+    repository_id::PartialRepoId, types::BuildRef,
+};
+use anyhow::{Context, Error};
+use chrono::{Duration, Utc};
+use dialoguer::Confirm;
+use futures::{stream, StreamExt, TryStreamExt};
+use std::{
+    io::{Cursor, Write},
+    path::PathBuf,
+};
+use tokio::task;
+use zip::ZipArchive;
+
+/// Number of workflow runs deleted at the same time.
+const DELETE_CONCURRENCY: usize = 4;
+
+/// Deletes completed workflow runs of a repository except the most recent ones, optionally only
+/// the ones older than a duration, after confirming them.
+pub async fn clean_workflow_runs(
+    mut app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    keep: usize,
+    older_than: Option<humantime::Duration>,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let cutoff = match older_than {
+        Some(x) => Some(Utc::now() - Duration::from_std(x.into())?),
+        None => None,
+    };
+    let runs: Vec<_> = app_env
+        .github_client
+        .list_workflow_runs(&repo_id)
+        .skip(keep)
+        .try_filter(|x| {
+            let completed = x.status.as_deref() == Some("completed");
+            let old = cutoff.map(|c| x.created_at < c).unwrap_or(true);
+            futures::future::ready(completed && old)
+        })
+        .try_collect()
+        .await?;
+
+    let (newest, oldest) = match (runs.first(), runs.last()) {
+        (Some(newest), Some(oldest)) => (newest, oldest),
+        _ => {
+            writeln!(out, "No workflow runs of {repo_id} to delete.")?;
+            return Ok(());
+        }
+    };
+    eprintln!(
+        "{} workflow runs of {repo_id} created between {} and {}.",
+        runs.len(),
+        oldest.created_at.since(),
+        newest.created_at.since()
+    );
+
+    if !app_env.dry_run
+        && !Confirm::new()
+            .with_prompt("Delete them?")
+            .default(false)
+            .show_default(true)
+            .wait_for_newline(true)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    let total = runs.len();
+    let gh_client = &app_env.github_client;
+    let repo_id = &repo_id;
+    let mut results = stream::iter(&runs)
+        .map(|x| async move { gh_client.delete_workflow_run(repo_id, x.id).await })
+        .buffer_unordered(DELETE_CONCURRENCY);
+    let mut done = 0;
+    while let Some(result) = results.next().await {
+        result?;
+        done += 1;
+        if !app_env.dry_run {
+            eprint!("\rDeleted {done}/{total} workflow runs.");
+        }
+    }
+    if !app_env.dry_run {
+        eprintln!();
+        record_not_undoable(
+            &mut app_env.database,
+            &repo_id.to_string(),
+            "workflow runs deletion",
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Re-runs failed jobs of the failed workflow runs of a commit, returns the commit SHA when
+/// there were runs to re-run.
+///
+/// The commit is the latest commit of the default branch, or the one the reference points to.
+pub async fn rerun_failed_checks(
+    mut app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    build_ref: Option<BuildRef>,
+) -> Result<Option<String>, Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+    let gh_client = &app_env.github_client;
+
+    let sha = match build_ref {
+        None => match gh_client.get_latest_commit(&repo_id).await? {
+            Some(x) => x.sha,
+            None => {
+                writeln!(out, "Repository {repo_id} doesn't have a commit yet.")?;
+                return Ok(None);
+            }
+        },
+        Some(BuildRef::GitRef(x)) => gh_client.get_commit(&repo_id, &x).await?.sha,
+        Some(BuildRef::PullRequest(x)) => gh_client.get_pull_request(&repo_id, x).await?.head.sha,
+    };
+
+    let runs: Vec<_> = gh_client
+        .list_workflow_runs_for_commit(&repo_id, &sha)
+        .await?
+        .into_iter()
+        .filter(|x| matches!(x.conclusion.as_deref(), Some("failure" | "timed_out")))
+        .collect();
+    if runs.is_empty() {
+        writeln!(
+            out,
+            "No failed workflow runs of {repo_id} at {}.",
+            &sha[..8]
+        )?;
+        return Ok(None);
+    }
+
+    for run in &runs {
+        gh_client.rerun_failed_jobs(&repo_id, run.id).await?;
+        if !app_env.dry_run {
+            writeln!(
+                out,
+                "Re-running failed jobs of {} ({}).",
+                run.name.as_deref().unwrap_or("-"),
+                run.id
+            )?;
+        }
+    }
+    if !app_env.dry_run {
+        let target = format!("{repo_id}@{}", &sha[..8]);
+        record_not_undoable(&mut app_env.database, &target, "re-run of failed jobs")?;
+    }
+
+    Ok(Some(sha))
+}
+
+/// Downloads logs of a workflow run and extracts them to a directory, or prints log of one of
+/// its jobs.
+///
+/// The directory defaults to `<name>-run-<run id>` in the current directory.
+pub async fn download_run_logs(
+    app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    run_id: u64,
+    dir: Option<PathBuf>,
+    job_id: Option<u64>,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    if let Some(job_id) = job_id {
+        let log = app_env
+            .github_client
+            .download_job_logs(&repo_id, job_id)
+            .await?;
+        out.write_all(&log)?;
+        return Ok(());
+    }
+
+    let archive = app_env
+        .github_client
+        .download_workflow_run_logs(&repo_id, run_id)
+        .await?;
+    let dir = dir.unwrap_or_else(|| PathBuf::from(format!("{}-run-{run_id}", repo_id.name)));
+    task::block_in_place(|| {
+        ZipArchive::new(Cursor::new(archive))
+            .and_then(|mut x| x.extract(&dir))
+            .with_context(|| format!("Failed to extract logs to {}.", dir.display()))
+    })?;
+    writeln!(out, "{}", dir.display())?;
+
+    Ok(())
+}
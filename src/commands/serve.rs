@@ -0,0 +1,149 @@
+use crate::{
+    app_env::AppEnv,
+    database::Database,
+    types::{BuildStatus, Repository},
+};
+use anyhow::{Context, Error};
+use chrono::{DateTime, Utc};
+use std::{fmt::Write as _, net::SocketAddr, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time,
+};
+use tracing::warn;
+
+/// Time a client has to send its request line before the connection is dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves metrics of dashboard repositories in Prometheus text format at `/metrics`.
+///
+/// Metrics are read from the local cache, keep it fresh with `shub d --update`.
+///
+/// Each connection is handled in its own task, so a slow client doesn't hold up the others.
+pub async fn serve_metrics(app_env: AppEnv<'_>, addr: SocketAddr) -> Result<(), Error> {
+    let listener = TcpListener::bind(addr).await?;
+    eprintln!("Serving metrics at http://{addr}/metrics.");
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let db = app_env.database.clone();
+        let owner = app_env.github_username.to_owned();
+        tokio::spawn(async move {
+            if let Err(err) = handle_request(&db, &owner, stream).await {
+                warn!(?err, %peer, "Failed to handle request.");
+            }
+        });
+    }
+}
+
+async fn handle_request(db: &Database, owner: &str, mut stream: TcpStream) -> Result<(), Error> {
+    // only the request line matters, it fits in the first read
+    let mut buf = [0; 1024];
+    let n = time::timeout(READ_TIMEOUT, stream.read(&mut buf))
+        .await
+        .context("Timed out reading request.")??;
+    let request = String::from_utf8_lossy(&buf[..n]);
+
+    let response = if request.starts_with("GET /metrics ") {
+        let repos = db.get_dashboard_repositories(owner).await?;
+        let body = render_metrics(&repos, Utc::now());
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_owned()
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+/// Renders metrics of repositories in Prometheus text format.
+///
+/// Owner and name labels are not escaped, GitHub doesn't allow quotes nor backslashes in them.
+fn render_metrics(repos: &[Repository], now: DateTime<Utc>) -> String {
+    let mut s = String::new();
+
+    s.push_str("# HELP shub_repository_build_status Build status of the latest commit.\n");
+    s.push_str("# TYPE shub_repository_build_status gauge\n");
+    for r in repos {
+        let status = match &r.build_status {
+            Some(x) => x,
+            None => continue,
+        };
+        for x in [
+            BuildStatus::Success,
+            BuildStatus::Failure,
+            BuildStatus::InProgress,
+        ] {
+            let value = if &x == status { 1 } else { 0 };
+            writeln!(
+                s,
+                "shub_repository_build_status{{owner=\"{}\",name=\"{}\",status=\"{}\"}} {}",
+                r.owner, r.name, x, value
+            )
+            .unwrap();
+        }
+    }
+
+    s.push_str("# HELP shub_repository_last_push_age_seconds Seconds since the last push.\n");
+    s.push_str("# TYPE shub_repository_last_push_age_seconds gauge\n");
+    for r in repos {
+        let pushed_at = match r.pushed_at {
+            Some(x) => x,
+            None => continue,
+        };
+        writeln!(
+            s,
+            "shub_repository_last_push_age_seconds{{owner=\"{}\",name=\"{}\"}} {}",
+            r.owner,
+            r.name,
+            (now - pushed_at).num_seconds()
+        )
+        .unwrap();
+    }
+
+    s
+}
+
+#[cfg(test)]
+#[test]
+fn test_render_metrics() {
+    use chrono::TimeZone;
+
+    let now = Utc.ymd(2022, 8, 1).and_hms(0, 0, 0);
+    let repo = |name: &str, build_status, pushed_at| Repository {
+        name: name.to_owned(),
+        owner: "kafji".to_owned(),
+        a_fork: false,
+        archived: false,
+        build_status,
+        pushed_at,
+        open_pull_requests: None,
+        unmerged_branches: None,
+        build_status_sha: None,
+    };
+    let repos = [
+        repo(
+            "shub",
+            Some(BuildStatus::Failure),
+            Some(Utc.ymd(2022, 7, 31).and_hms(23, 0, 0)),
+        ),
+        repo("sekret", None, None),
+    ];
+    assert_eq!(
+        render_metrics(&repos, now),
+        r#"# HELP shub_repository_build_status Build status of the latest commit.
+# TYPE shub_repository_build_status gauge
+shub_repository_build_status{owner="kafji",name="shub",status="success"} 0
+shub_repository_build_status{owner="kafji",name="shub",status="failure"} 1
+shub_repository_build_status{owner="kafji",name="shub",status="in_progress"} 0
+# HELP shub_repository_last_push_age_seconds Seconds since the last push.
+# TYPE shub_repository_last_push_age_seconds gauge
+shub_repository_last_push_age_seconds{owner="kafji",name="shub"} 3600
+"#
+    );
+}
@@ -0,0 +1,471 @@
+use crate::{
+    app_env::AppEnv,
+    error::ShubError,
+    github_models::GhRepository,
+    repository_id::{FullRepoId, IsPartialRepositoryId, PartialRepoId},
+    types::MutationKind,
+};
+use anyhow::{anyhow, bail, Context, Error};
+use dialoguer::Confirm;
+use futures::{future, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{self, Path, PathBuf},
+};
+use tabwriter::TabWriter;
+
+/// Directory of settings profiles in the config directory.
+const SETTINGS_PROFILES_DIR: &str = "settings-profiles";
+
+/// Number of repositories fetched or updated at the same time when applying settings to all
+/// repositories.
+const SETTINGS_CONCURRENCY: usize = 4;
+
+/// Prints settings of a repository.
+pub async fn view_repository_settings(
+    app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+    let repo = app_env.github_client.get_repository(&repo_id).await?;
+    let settings = repo.extract_repository_settings()?;
+    if out.write_record(&settings)? {
+        return Ok(());
+    }
+    writeln!(out, "{}", settings)?;
+    Ok(())
+}
+
+/// Copies settings of a repository to another repository, and its topics when asked to.
+pub async fn copy_repository_settings(
+    app_env: AppEnv<'_>,
+    from: PartialRepoId,
+    to: PartialRepoId,
+    include_topics: bool,
+) -> Result<(), Error> {
+    let from = from.complete(app_env.github_username);
+    let to = to.complete(app_env.github_username);
+    let gh_client = &app_env.github_client;
+
+    let settings = gh_client
+        .get_repository(&from)
+        .await?
+        .extract_repository_settings()?;
+    let topics = if include_topics {
+        Some(gh_client.get_topics(&from).await?)
+    } else {
+        None
+    };
+    apply_repository_settings(app_env, &to, &settings, topics).await
+}
+
+/// Applies settings, and topics when given, to a repository after confirming the changes.
+async fn apply_repository_settings(
+    mut app_env: AppEnv<'_>,
+    to: &FullRepoId,
+    new_settings: &RepositorySettings,
+    new_topics: Option<Vec<String>>,
+) -> Result<(), Error> {
+    let gh_client = &app_env.github_client;
+
+    let old_settings = gh_client
+        .get_repository(to)
+        .await?
+        .extract_repository_settings()?;
+    let diff = RepositorySettingsDiff::new(&old_settings, new_settings);
+
+    // the changes are part of the prompt, not of the output
+    eprintln!("{}", diff);
+
+    let topics = match new_topics {
+        Some(new) => {
+            let old = gh_client.get_topics(to).await?;
+            eprintln!("topics: {} -> {}", old.join(", "), new.join(", "));
+            Some((old, new)).filter(|(old, new)| old != new)
+        }
+        None => None,
+    };
+
+    if !app_env.dry_run
+        && !Confirm::new()
+            .with_prompt("Apply settings?")
+            .default(false)
+            .show_default(true)
+            .wait_for_newline(true)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    gh_client.update_repository(to, new_settings).await?;
+
+    if !app_env.dry_run {
+        app_env.database.put_mutation(
+            MutationKind::UpdateSettings,
+            &to.to_string(),
+            &serde_json::to_string(&old_settings)?,
+        )?;
+    }
+
+    if let Some((old, new)) = topics {
+        super::topics::replace_topics(&mut app_env, to, old, &new).await?;
+    }
+
+    Ok(())
+}
+
+/// Settings profile, a file in the settings profiles directory of the config directory.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct SettingsProfile {
+    /// Topics to replace the topics of a repository with, kept as they are when missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    topics: Option<Vec<String>>,
+    pub settings: RepositorySettings,
+}
+
+#[cfg(test)]
+#[test]
+fn test_settings_profile_file() {
+    let profile = SettingsProfile {
+        topics: Some(vec!["rust".to_owned(), "cli".to_owned()]),
+        settings: RepositorySettings {
+            allow_rebase_merge: false,
+            allow_squash_merge: true,
+            allow_auto_merge: true,
+            delete_branch_on_merge: true,
+            allow_merge_commit: false,
+        },
+    };
+    let text = toml::to_string(&profile).unwrap();
+    assert_eq!(toml::from_str::<SettingsProfile>(&text).unwrap(), profile);
+
+    let profile: SettingsProfile = toml::from_str(
+        r#"
+        [settings]
+        allow_rebase_merge = true
+        allow_squash_merge = true
+        allow_auto_merge = false
+        delete_branch_on_merge = true
+        allow_merge_commit = true
+        "#,
+    )
+    .unwrap();
+    assert_eq!(profile.topics, None);
+}
+
+/// Returns path to the file of a settings profile, failing for names that would be outside of
+/// the settings profiles directory.
+fn settings_profile_path(config_dir: &Path, name: &str) -> Result<PathBuf, Error> {
+    if name.is_empty() || name.contains("..") || name.chars().any(path::is_separator) {
+        return Err(ShubError::config(anyhow!("Invalid settings profile name `{name}`.")).into());
+    }
+    let path = config_dir
+        .join(SETTINGS_PROFILES_DIR)
+        .join(format!("{name}.toml"));
+    Ok(path)
+}
+
+#[cfg(test)]
+#[test]
+fn test_settings_profile_path() {
+    let config_dir = Path::new("/home/kafji/.config/shub");
+    assert_eq!(
+        settings_profile_path(config_dir, "oss").unwrap(),
+        config_dir.join("settings-profiles/oss.toml")
+    );
+    for name in ["", "..", "../config", "work/oss", "/etc/passwd"] {
+        let err = settings_profile_path(config_dir, name).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ShubError>(),
+            Some(ShubError::Config { .. })
+        ));
+    }
+}
+
+/// Saves settings and topics of a repository as a settings profile.
+pub async fn save_settings_profile(
+    app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    name: &str,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+    let gh_client = &app_env.github_client;
+
+    let settings = gh_client
+        .get_repository(&repo_id)
+        .await?
+        .extract_repository_settings()?;
+    let topics = gh_client.get_topics(&repo_id).await?;
+    let profile = SettingsProfile {
+        topics: Some(topics),
+        settings,
+    };
+
+    let path = settings_profile_path(&app_env.config_dir, name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, toml::to_string(&profile)?)
+        .with_context(|| format!("Failed to write {}.", path.display()))?;
+    writeln!(out, "Saved {repo_id} settings to {}.", path.display())?;
+
+    Ok(())
+}
+
+/// Applies a settings profile to a repository.
+pub async fn apply_settings_profile(
+    app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    name: &str,
+) -> Result<(), Error> {
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let SettingsProfile { topics, settings } = read_settings_profile(&app_env.config_dir, name)?;
+
+    apply_repository_settings(app_env, &repo_id, &settings, topics).await
+}
+
+/// Reads a settings profile from the config directory.
+pub(crate) fn read_settings_profile(
+    config_dir: &Path,
+    name: &str,
+) -> Result<SettingsProfile, Error> {
+    let path = settings_profile_path(config_dir, name)?;
+    let text = match fs::read_to_string(&path) {
+        Ok(x) => x,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            bail!(
+                "Settings profile `{}` not found at {}.",
+                name,
+                path.display()
+            )
+        }
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {}.", path.display())),
+    };
+    let profile =
+        toml::from_str(&text).with_context(|| format!("Failed to parse {}.", path.display()))?;
+    Ok(profile)
+}
+
+/// Copies settings of a repository to all owned repositories that are neither forks nor
+/// archived, confirming the changes once.
+pub async fn copy_repository_settings_to_all(
+    mut app_env: AppEnv<'_>,
+    from: PartialRepoId,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let from = from.complete(app_env.github_username);
+    let gh_client = &app_env.github_client;
+    let username = app_env.github_username;
+
+    let new_settings = gh_client
+        .get_repository(&from)
+        .await?
+        .extract_repository_settings()?;
+
+    // listed repositories don't have their settings, get them one by one
+    let changes: Vec<(FullRepoId, RepositorySettings)> = gh_client
+        .list_owned_repositories()
+        .try_filter(|x| {
+            let owned = x.owner() == Some(username);
+            let a_fork = x.fork.unwrap_or_default();
+            let archived = x.archived.unwrap_or_default();
+            future::ready(owned && !a_fork && !archived && x.name != from.name)
+        })
+        .map_ok(|x| async move {
+            let repo_id = FullRepoId {
+                owner: username.to_owned(),
+                name: x.name,
+            };
+            let settings = gh_client
+                .get_repository(&repo_id)
+                .await?
+                .extract_repository_settings()?;
+            Result::<_, Error>::Ok((repo_id, settings))
+        })
+        .try_buffered(SETTINGS_CONCURRENCY)
+        .try_filter(|(_, old)| future::ready(*old != new_settings))
+        .try_collect()
+        .await?;
+
+    if changes.is_empty() {
+        writeln!(out, "All repositories already have the settings of {from}.")?;
+        return Ok(());
+    }
+
+    let mut w = TabWriter::new(Vec::new());
+    writeln!(w, "repository\t{}", RepositorySettings::KEYS.join("\t"))?;
+    for (repo_id, old) in &changes {
+        let values: Vec<_> = old
+            .to_values()
+            .iter()
+            .zip(new_settings.to_values())
+            .map(|(old, new)| {
+                if *old == new {
+                    old.to_string()
+                } else {
+                    format!("{old} -> {new}")
+                }
+            })
+            .collect();
+        writeln!(w, "{}\t{}", repo_id.name, values.join("\t"))?;
+    }
+    eprint!("{}", String::from_utf8(w.into_inner()?)?);
+
+    if !app_env.dry_run
+        && !Confirm::new()
+            .with_prompt(format!("Apply settings to {} repositories?", changes.len()))
+            .default(false)
+            .show_default(true)
+            .wait_for_newline(true)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    let results: Vec<_> = futures::stream::iter(&changes)
+        .map(|(repo_id, old)| async move {
+            let result = gh_client.update_repository(repo_id, &new_settings).await;
+            (repo_id, old, result)
+        })
+        .buffer_unordered(SETTINGS_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut failed = 0;
+    for (repo_id, old, result) in results {
+        match result {
+            Ok(()) if !app_env.dry_run => {
+                app_env.database.put_mutation(
+                    MutationKind::UpdateSettings,
+                    &repo_id.to_string(),
+                    &serde_json::to_string(old)?,
+                )?;
+            }
+            Ok(()) => (),
+            Err(err) => {
+                failed += 1;
+                eprintln!("Failed to apply settings to {repo_id}: {err:#}");
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("Failed to apply settings to {failed} repositories.")
+    }
+
+    Ok(())
+}
+
+pub trait ExtractRepositorySettings {
+    fn extract_repository_settings(&self) -> Result<RepositorySettings, Error>;
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Copy, Clone, Debug)]
+pub struct RepositorySettings {
+    allow_rebase_merge: bool,
+    allow_squash_merge: bool,
+    allow_auto_merge: bool,
+    delete_branch_on_merge: bool,
+    allow_merge_commit: bool,
+}
+
+impl RepositorySettings {
+    /// Names of the settings, in the same order as [Self::to_values].
+    pub const KEYS: [&'static str; 5] = [
+        "allow_rebase_merge",
+        "allow_squash_merge",
+        "allow_auto_merge",
+        "delete_branch_on_merge",
+        "allow_merge_commit",
+    ];
+
+    /// Returns values of the settings, in the same order as [Self::KEYS].
+    pub fn to_values(&self) -> [bool; 5] {
+        [
+            self.allow_rebase_merge,
+            self.allow_squash_merge,
+            self.allow_auto_merge,
+            self.delete_branch_on_merge,
+            self.allow_merge_commit,
+        ]
+    }
+}
+
+macro_rules! write_key {
+    ($w:expr, $this:expr, $key:ident) => {{
+        let val = $this.$key;
+        write!($w, "{key:>25} = {val:5}\n", key = stringify!($key))
+    }};
+}
+
+impl fmt::Display for RepositorySettings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_key!(f, self, allow_rebase_merge)?;
+        write_key!(f, self, allow_squash_merge)?;
+        write_key!(f, self, allow_auto_merge)?;
+        write_key!(f, self, delete_branch_on_merge)?;
+        write_key!(f, self, allow_merge_commit)?;
+        Ok(())
+    }
+}
+
+macro_rules! extract_key {
+    ($repo:expr, $key:ident) => {
+        $repo
+            .$key
+            .ok_or_else(|| Error::msg(format!("Missing value for key `{}`.", stringify!($key))))
+    };
+}
+
+impl ExtractRepositorySettings for GhRepository {
+    fn extract_repository_settings(&self) -> Result<RepositorySettings, Error> {
+        let repo = self;
+        let s = RepositorySettings {
+            allow_rebase_merge: extract_key!(repo, allow_rebase_merge)?,
+            allow_squash_merge: extract_key!(repo, allow_squash_merge)?,
+            allow_auto_merge: extract_key!(repo, allow_auto_merge)?,
+            delete_branch_on_merge: extract_key!(repo, delete_branch_on_merge)?,
+            allow_merge_commit: extract_key!(repo, allow_merge_commit)?,
+        };
+        Ok(s)
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct RepositorySettingsDiff<'a> {
+    old: &'a RepositorySettings,
+    new: &'a RepositorySettings,
+}
+
+impl<'a> RepositorySettingsDiff<'a> {
+    fn new(old: &'a RepositorySettings, new: &'a RepositorySettings) -> Self {
+        Self { old, new }
+    }
+}
+
+macro_rules! diff_key {
+    ($w:expr, $this:expr, $key:ident) => {{
+        let old = $this.old.$key;
+        let new = $this.new.$key;
+        write!(
+            $w,
+            "{key:>25} = {old:>5} -> {new:<5}\n",
+            key = stringify!($key)
+        )
+    }};
+}
+
+impl fmt::Display for RepositorySettingsDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        diff_key!(f, self, allow_rebase_merge)?;
+        diff_key!(f, self, allow_squash_merge)?;
+        diff_key!(f, self, allow_auto_merge)?;
+        diff_key!(f, self, delete_branch_on_merge)?;
+        diff_key!(f, self, allow_merge_commit)?;
+        Ok(())
+    }
+}
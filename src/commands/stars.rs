@@ -0,0 +1,749 @@
+use crate::{
+    app_env::AppEnv,
+    cli::stars::StarAction,
+    display::{emojify, RelativeTime},
+    github_models::GhStar,
+    process,
+    repository_id::{FullRepoId, PartialRepoId},
+    types::{MutationKind, Star},
+};
+use anyhow::{bail, Context, Error};
+use chrono::{DateTime, Duration, Utc};
+use dialoguer::{Confirm, FuzzySelect, MultiSelect};
+use futures::{future, stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+    str::FromStr,
+};
+use tabwriter::TabWriter;
+use tokio::time;
+
+/// Number of languages and topics printed by stats by default, the rest are counted as other.
+pub const DEFAULT_STATS_TOP: usize = 10;
+
+/// Years without a push after which audit flags a starred repository by default.
+pub const DEFAULT_AUDIT_YEARS: u32 = 2;
+
+/// Wait between starring repositories, GitHub asks to wait a second between mutating requests
+/// to avoid its secondary rate limit.
+const STAR_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Width of percentage bars at 100%.
+const BAR_WIDTH: usize = 20;
+
+/// Prompts to pick starred repositories and clones them.
+///
+/// Starred repositories are read from the cache, they're fetched first when the cache is empty
+/// or when asked to.
+pub async fn clone_starred_repositories(
+    mut app_env: AppEnv<'_>,
+    update: bool,
+    multi: bool,
+    jobs: usize,
+    https: bool,
+) -> Result<(), Error> {
+    let stars = get_stars(&mut app_env, update).await?;
+    let repo_ids = if multi {
+        pick_starred_repositories(&stars)?
+    } else {
+        pick_starred_repository(&stars)?.into_iter().collect()
+    };
+    super::clone::clone_repositories(&app_env, repo_ids, jobs, https).await
+}
+
+/// Prompts to pick a starred repository, then prints its URL or opens it.
+pub async fn pick_star(
+    mut app_env: AppEnv<'_>,
+    update: bool,
+    action: StarAction,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let stars = get_stars(&mut app_env, update).await?;
+    let repo_id = match pick_starred_repository(&stars)? {
+        Some(x) => x.complete(app_env.github_username),
+        None => return Ok(()),
+    };
+    // the URL comes from the API to also work with GitHub Enterprise Server
+    let url = app_env
+        .github_client
+        .get_repository(&repo_id)
+        .await?
+        .html_url
+        .with_context(|| format!("Repository {repo_id} has no HTML URL."))?
+        .to_string();
+    match action {
+        StarAction::Print => writeln!(out, "{url}")?,
+        StarAction::Open => process::open_url(&url)?,
+    }
+    Ok(())
+}
+
+/// Prompts to pick one of starred repositories, returns none if the prompt was cancelled.
+fn pick_starred_repository(stars: &[Star]) -> Result<Option<PartialRepoId>, Error> {
+    let items: Vec<_> = stars
+        .iter()
+        .map(|x| match &x.description {
+            Some(desc) => format!("{}/{}  {}", x.owner, x.name, emojify(desc)),
+            None => format!("{}/{}", x.owner, x.name),
+        })
+        .collect();
+    let selection = FuzzySelect::new()
+        .with_prompt("Repository")
+        .items(&items)
+        .interact_opt()?;
+
+    let repo_id = selection.map(|idx| PartialRepoId {
+        owner: Some(stars[idx].owner.clone()),
+        name: stars[idx].name.clone(),
+    });
+    Ok(repo_id)
+}
+
+/// Prompts to pick starred repositories, returns nothing if the prompt was cancelled.
+fn pick_starred_repositories(stars: &[Star]) -> Result<Vec<PartialRepoId>, Error> {
+    let items: Vec<_> = stars
+        .iter()
+        .map(|x| format!("{}/{}", x.owner, x.name))
+        .collect();
+    let selection = MultiSelect::new()
+        .with_prompt("Repositories")
+        .items(&items)
+        .interact_opt()?
+        .unwrap_or_default();
+
+    let repo_ids = selection
+        .into_iter()
+        .map(|idx| PartialRepoId {
+            owner: Some(stars[idx].owner.clone()),
+            name: stars[idx].name.clone(),
+        })
+        .collect();
+    Ok(repo_ids)
+}
+
+/// Filter of starred repositories to unstar.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum StarFilter {
+    /// Archived repositories.
+    Archived,
+    /// Repositories not pushed in this many years.
+    Inactive(u32),
+}
+
+impl StarFilter {
+    fn matches(&self, star: &GhStar, now: DateTime<Utc>) -> bool {
+        match self {
+            StarFilter::Archived => star.archived,
+            StarFilter::Inactive(years) => star
+                .pushed_at
+                .map(|x| now - x > Duration::days(365 * *years as i64))
+                .unwrap_or(true),
+        }
+    }
+}
+
+impl FromStr for StarFilter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "archived" {
+            return Ok(StarFilter::Archived);
+        }
+        match s.strip_prefix("inactive:").map(str::parse) {
+            Some(Ok(years)) => Ok(StarFilter::Inactive(years)),
+            _ => bail!(
+                "Expecting `archived` or `inactive:<years>` filter, but was `{}`.",
+                s
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_star_filter() {
+    assert_eq!(
+        "archived".parse::<StarFilter>().unwrap(),
+        StarFilter::Archived
+    );
+    assert_eq!(
+        "inactive:2".parse::<StarFilter>().unwrap(),
+        StarFilter::Inactive(2)
+    );
+    assert!("inactive:x".parse::<StarFilter>().is_err());
+    assert!("forks".parse::<StarFilter>().is_err());
+}
+
+/// Undo data of unstarring.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UnstarredRepositories {
+    /// Unstarred repositories, `:owner/:name`.
+    pub repos: Vec<String>,
+}
+
+/// Unstars a repository or, with a filter, the starred repositories matching it after
+/// confirming them.
+pub async fn unstar_repositories(
+    mut app_env: AppEnv<'_>,
+    repo_id: Option<PartialRepoId>,
+    filter: Option<StarFilter>,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+
+    let repo_ids = match (repo_id, filter) {
+        (Some(repo_id), _) => vec![repo_id.complete(app_env.github_username)],
+        (None, Some(filter)) => {
+            let now = Utc::now();
+            let repo_ids: Vec<_> = app_env
+                .github_client
+                .list_stars()
+                .await?
+                .into_iter()
+                .filter(|x| filter.matches(x, now))
+                .map(|x| FullRepoId {
+                    owner: x.owner,
+                    name: x.name,
+                })
+                .collect();
+            if repo_ids.is_empty() {
+                writeln!(out, "No starred repositories match the filter.")?;
+                return Ok(());
+            }
+            for x in &repo_ids {
+                eprintln!("{x}");
+            }
+            if !app_env.dry_run
+                && !Confirm::new()
+                    .with_prompt(format!("Unstar {} repositories?", repo_ids.len()))
+                    .default(false)
+                    .show_default(true)
+                    .wait_for_newline(true)
+                    .interact()?
+            {
+                return Ok(());
+            }
+            repo_ids
+        }
+        (None, None) => bail!("Expecting a repository or a filter."),
+    };
+
+    unstar(&mut app_env, &repo_ids).await
+}
+
+/// Unstars repositories, removing them from the cache and recording the mutation to undo it.
+async fn unstar(app_env: &mut AppEnv<'_>, repo_ids: &[FullRepoId]) -> Result<(), Error> {
+    let mut out = app_env.output;
+
+    for repo_id in repo_ids {
+        app_env.github_client.unstar_repository(repo_id).await?;
+        if !app_env.dry_run {
+            app_env.database.delete_star(repo_id)?;
+        }
+    }
+
+    if !app_env.dry_run {
+        let data = UnstarredRepositories {
+            repos: repo_ids.iter().map(|x| x.to_string()).collect(),
+        };
+        app_env.database.put_mutation(
+            MutationKind::Unstar,
+            app_env.github_username,
+            &serde_json::to_string(&data)?,
+        )?;
+        writeln!(out, "Unstarred {} repositories.", repo_ids.len())?;
+    }
+
+    Ok(())
+}
+
+/// Undo data of starring.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StarredRepositories {
+    /// Starred repositories, `:owner/:name`.
+    pub repos: Vec<String>,
+}
+
+/// Stars repositories starred by another user that are not starred yet, after confirming them.
+///
+/// Stars are sent one at a time, rate limited requests wait and retry.
+pub async fn import_stars(mut app_env: AppEnv<'_>, username: &str) -> Result<(), Error> {
+    let mut out = app_env.output;
+
+    let starred: HashSet<_> = app_env
+        .github_client
+        .list_stars()
+        .await?
+        .into_iter()
+        .map(|x| format!("{}/{}", x.owner, x.name).to_lowercase())
+        .collect();
+    let repo_ids: Vec<_> = app_env
+        .github_client
+        .list_user_starred_repositories(username)
+        .try_filter_map(|x| async move {
+            let owner = match x.owner {
+                Some(owner) => owner.login,
+                None => return Ok(None),
+            };
+            Ok(Some(FullRepoId {
+                owner,
+                name: x.name,
+            }))
+        })
+        .try_filter(|x| future::ready(!starred.contains(&x.to_string().to_lowercase())))
+        .try_collect()
+        .await?;
+
+    if repo_ids.is_empty() {
+        writeln!(
+            out,
+            "All repositories starred by {username} are already starred."
+        )?;
+        return Ok(());
+    }
+    for x in &repo_ids {
+        eprintln!("{x}");
+    }
+    if !app_env.dry_run
+        && !Confirm::new()
+            .with_prompt(format!("Star {} repositories?", repo_ids.len()))
+            .default(false)
+            .show_default(true)
+            .wait_for_newline(true)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    for (i, repo_id) in repo_ids.iter().enumerate() {
+        if i > 0 && !app_env.dry_run {
+            time::sleep(STAR_INTERVAL).await;
+        }
+        app_env.github_client.star_repository(repo_id).await?;
+    }
+
+    if !app_env.dry_run {
+        // clear the cache so it's fetched again with the imported stars
+        app_env.database.put_stars(&[])?;
+        let data = StarredRepositories {
+            repos: repo_ids.iter().map(|x| x.to_string()).collect(),
+        };
+        app_env.database.put_mutation(
+            MutationKind::Star,
+            app_env.github_username,
+            &serde_json::to_string(&data)?,
+        )?;
+        writeln!(out, "Starred {} repositories.", repo_ids.len())?;
+    }
+
+    Ok(())
+}
+
+/// Why audit flags a starred repository.
+#[derive(Serialize, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum StarFinding {
+    /// Deleted, or made private.
+    Deleted,
+    Archived,
+    /// Not pushed in the given years.
+    Inactive,
+}
+
+/// Starred repository flagged by audit.
+#[derive(Serialize, Debug)]
+pub struct StarAuditRecord {
+    pub repository: String,
+    pub finding: StarFinding,
+    pub pushed_at: Option<DateTime<Utc>>,
+}
+
+/// Checks cached starred repositories on GitHub, `jobs` at the same time, and prints the ones
+/// that are deleted, archived, or not pushed in `years`.
+///
+/// With `unstar`, prompts to pick flagged repositories to unstar. Deleted repositories are
+/// only removed from the cache, their stars are gone with them.
+pub async fn audit_stars(
+    mut app_env: AppEnv<'_>,
+    update: bool,
+    years: u32,
+    jobs: usize,
+    unstar_picked: bool,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let stars = get_stars(&mut app_env, update).await?;
+    let now = Utc::now();
+
+    let gh_client = &app_env.github_client;
+    let mut records: Vec<StarAuditRecord> = stream::iter(&stars)
+        .map(|x| async move {
+            let repo_id = FullRepoId {
+                owner: x.owner.clone(),
+                name: x.name.clone(),
+            };
+            let repo = gh_client.find_repository(&repo_id).await?;
+            let finding = match &repo {
+                Some(repo) => audit_finding(repo.archived, repo.pushed_at, now, years),
+                None => Some(StarFinding::Deleted),
+            };
+            let record = finding.map(|finding| StarAuditRecord {
+                repository: repo_id.to_string(),
+                finding,
+                pushed_at: repo.and_then(|x| x.pushed_at),
+            });
+            Result::<_, Error>::Ok(record)
+        })
+        .buffer_unordered(jobs.max(1))
+        .filter_map(|x| async move { x.transpose() })
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()?;
+    records.sort_by(|a, b| a.repository.cmp(&b.repository));
+
+    if records.is_empty() {
+        writeln!(out, "No starred repositories are flagged.")?;
+        return Ok(());
+    }
+    if !app_env.output.write_records(&records)? {
+        let mut w = TabWriter::new(Vec::new());
+        for x in &records {
+            let pushed = x.pushed_at.map(|x| x.since().to_string());
+            writeln!(
+                w,
+                "{}\t{:?}\t{}",
+                x.repository,
+                x.finding,
+                pushed.unwrap_or_default()
+            )?;
+        }
+        write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+    }
+
+    if !unstar_picked {
+        return Ok(());
+    }
+    let items: Vec<_> = records
+        .iter()
+        .map(|x| format!("{} ({:?})", x.repository, x.finding))
+        .collect();
+    let defaults = vec![true; items.len()];
+    let selection = MultiSelect::new()
+        .with_prompt("Unstar")
+        .items(&items)
+        .defaults(&defaults)
+        .interact_opt()?
+        .unwrap_or_default();
+
+    let mut repo_ids = Vec::new();
+    for idx in selection {
+        let x = &records[idx];
+        let repo_id: FullRepoId = x.repository.parse()?;
+        if x.finding == StarFinding::Deleted {
+            if !app_env.dry_run {
+                app_env.database.delete_star(&repo_id)?;
+            }
+        } else {
+            repo_ids.push(repo_id);
+        }
+    }
+    if !repo_ids.is_empty() {
+        unstar(&mut app_env, &repo_ids).await?;
+    }
+
+    Ok(())
+}
+
+/// Flags an existing repository that is archived or not pushed in `years`.
+fn audit_finding(
+    archived: Option<bool>,
+    pushed_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    years: u32,
+) -> Option<StarFinding> {
+    if archived.unwrap_or_default() {
+        return Some(StarFinding::Archived);
+    }
+    let inactive = pushed_at
+        .map(|x| now - x > Duration::days(365 * years as i64))
+        .unwrap_or(true);
+    if inactive {
+        Some(StarFinding::Inactive)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_audit_finding() {
+    use chrono::TimeZone;
+
+    let now = Utc.ymd(2022, 8, 1).and_hms(0, 0, 0);
+    let pushed = |year| Some(Utc.ymd(year, 7, 1).and_hms(0, 0, 0));
+    assert_eq!(
+        audit_finding(Some(true), pushed(2022), now, 2),
+        Some(StarFinding::Archived)
+    );
+    assert_eq!(audit_finding(Some(false), pushed(2021), now, 2), None);
+    assert_eq!(
+        audit_finding(None, pushed(2019), now, 2),
+        Some(StarFinding::Inactive)
+    );
+    assert_eq!(
+        audit_finding(None, None, now, 2),
+        Some(StarFinding::Inactive)
+    );
+}
+
+/// Prints starred repositories from the cache, fetching them first when the cache is empty or
+/// when asked to.
+pub async fn print_cached_stars(
+    mut app_env: AppEnv<'_>,
+    refresh: bool,
+    jsonl: bool,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let stars = get_stars(&mut app_env, refresh).await?;
+    let limit = app_env.limit.unwrap_or(usize::MAX);
+    let stars = &stars[..limit.min(stars.len())];
+    if jsonl {
+        for star in stars {
+            writeln!(out, "{}", serde_json::to_string(star)?)?;
+        }
+        return Ok(());
+    }
+    if out.write_records(stars)? {
+        return Ok(());
+    }
+    for star in stars {
+        writeln!(out, "{}", star)?;
+    }
+    Ok(())
+}
+
+/// Prints cached starred repositories matching a query, best matches first.
+///
+/// When the cache is empty, starred repositories are fetched from GitHub and searched as they
+/// are, most recently starred first, then cached for the next searches.
+pub async fn search_stars(mut app_env: AppEnv<'_>, query: &str) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let cached = app_env.database.get_stars()?;
+    let stars = if cached.is_empty() {
+        let stars = get_stars(&mut app_env, false).await?;
+        stars
+            .into_iter()
+            .filter(|x| star_matches(x, query))
+            .collect()
+    } else {
+        app_env.database.search_stars(query)?
+    };
+    if stars.is_empty() {
+        writeln!(out, "No starred repositories match `{query}`.")?;
+        return Ok(());
+    }
+    let limit = app_env.limit.unwrap_or(usize::MAX);
+    for star in stars.iter().take(limit) {
+        writeln!(out, "{}", star)?;
+    }
+    Ok(())
+}
+
+/// Prints starred repositories counts by language, by topic, and by how long ago they were
+/// starred.
+///
+/// Only the `top` most common languages and topics are printed, the rest are counted as other.
+/// A repository counts once for each of its topics.
+pub async fn print_star_stats(
+    mut app_env: AppEnv<'_>,
+    update: bool,
+    top: usize,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let stars = get_stars(&mut app_env, update).await?;
+    let total = stars.len();
+
+    let mut w = TabWriter::new(Vec::new());
+
+    writeln!(w, "Language\t\t\t")?;
+    let languages = count_top(
+        stars.iter().map(|x| x.language.as_deref().unwrap_or("-")),
+        top,
+    );
+    for (name, count) in languages {
+        write_stat_row(&mut w, name, count, total)?;
+    }
+
+    writeln!(w, "\t\t\t")?;
+    writeln!(w, "Topic\t\t\t")?;
+    let topics = count_top(
+        stars.iter().flat_map(|x| {
+            let topics = x.topics.iter().map(String::as_str);
+            // repositories without topics are counted as `-`, like without a language
+            let none = x.topics.is_empty().then_some("-");
+            topics.chain(none)
+        }),
+        top,
+    );
+    for (name, count) in topics {
+        write_stat_row(&mut w, name, count, total)?;
+    }
+
+    writeln!(w, "\t\t\t")?;
+    writeln!(w, "Starred\t\t\t")?;
+    let now = Utc::now();
+    let mut ages = [0; STAR_AGES.len()];
+    for x in &stars {
+        if let Some(starred_at) = x.starred_at {
+            ages[star_age(now, starred_at)] += 1;
+        }
+    }
+    for ((_, name), count) in STAR_AGES.iter().zip(ages) {
+        write_stat_row(&mut w, name, count, total)?;
+    }
+
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    Ok(())
+}
+
+/// Counts occurrences of names, returns the `top` most common first and the rest summed as
+/// `Other`.
+fn count_top<'a>(names: impl Iterator<Item = &'a str>, top: usize) -> Vec<(&'a str, usize)> {
+    let mut counts = HashMap::<_, usize>::new();
+    for name in names {
+        *counts.entry(name).or_default() += 1;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+    let others: usize = counts.iter().skip(top).map(|(_, x)| x).sum();
+    counts.truncate(top);
+    if others > 0 {
+        counts.push(("Other", others));
+    }
+    counts
+}
+
+#[cfg(test)]
+#[test]
+fn test_count_top() {
+    let names = ["rust", "go", "rust", "zig", "go", "rust", "c"];
+    assert_eq!(
+        count_top(names.into_iter(), 2),
+        [("rust", 3), ("go", 2), ("Other", 2)]
+    );
+    assert_eq!(count_top(names.into_iter(), 4).len(), 4);
+    assert!(count_top(std::iter::empty(), 2).is_empty());
+}
+
+fn write_stat_row(w: &mut impl Write, name: &str, count: usize, total: usize) -> Result<(), Error> {
+    let fraction = if total == 0 {
+        0.
+    } else {
+        count as f64 / total as f64
+    };
+    let bar = "#".repeat((fraction * BAR_WIDTH as f64).round() as usize);
+    writeln!(w, "  {}\t{}\t{:.1}%\t{}", name, count, fraction * 100., bar)?;
+    Ok(())
+}
+
+/// Star age buckets, upper bound in days and name.
+const STAR_AGES: [(Option<i64>, &str); 5] = [
+    (Some(30), "< 1 month"),
+    (Some(180), "1-6 months"),
+    (Some(365), "6-12 months"),
+    (Some(730), "1-2 years"),
+    (None, "> 2 years"),
+];
+
+/// Returns index of the bucket in [STAR_AGES] a star belongs to.
+fn star_age(now: DateTime<Utc>, starred_at: DateTime<Utc>) -> usize {
+    let age = now - starred_at;
+    STAR_AGES
+        .iter()
+        .position(|(days, _)| days.map(|x| age < Duration::days(x)).unwrap_or(true))
+        .expect("last bucket should be unbounded")
+}
+
+/// Gets starred repositories from the cache, fetching them first if necessary.
+/// Returns true if every word of a query is a word, or a prefix of a word, of the name,
+/// description, language, or topics of a starred repository, ignoring case, like the search of
+/// the cache.
+fn star_matches(star: &Star, query: &str) -> bool {
+    let text = [
+        Some(star.owner.as_str()),
+        Some(star.name.as_str()),
+        star.description.as_deref(),
+        star.language.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .chain(star.topics.iter().map(String::as_str))
+    .collect::<Vec<_>>()
+    .join(" ")
+    .to_lowercase();
+    let words: Vec<_> = text
+        .split(|x: char| !x.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .collect();
+    let mut query = query
+        .split(|x: char| !x.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .peekable();
+    query.peek().is_some()
+        && query.all(|q| {
+            let q = q.to_lowercase();
+            words.iter().any(|x| x.starts_with(&q))
+        })
+}
+
+#[cfg(test)]
+#[test]
+fn test_star_matches() {
+    let star = Star {
+        owner: "kafji".to_owned(),
+        name: "shub".to_owned(),
+        description: Some("GitHub CLI for my workflow".to_owned()),
+        language: Some("Rust".to_owned()),
+        starred_at: None,
+        topics: vec!["command-line".to_owned()],
+    };
+    assert!(star_matches(&star, "rust"));
+    assert!(star_matches(&star, "git work"));
+    assert!(star_matches(&star, "command"));
+    assert!(star_matches(&star, "kafji/shub"));
+    assert!(!star_matches(&star, "rust python"));
+    assert!(!star_matches(&star, "hub"));
+    assert!(!star_matches(&star, ""));
+}
+
+async fn get_stars(app_env: &mut AppEnv<'_>, update: bool) -> Result<Vec<Star>, Error> {
+    let stars = app_env.database.get_stars()?;
+    // stars cached by older versions don't know when they were starred
+    if !update && !stars.is_empty() && stars.iter().all(|x| x.starred_at.is_some()) {
+        return Ok(stars);
+    }
+    let stars: Vec<_> = app_env
+        .github_client
+        .list_stars()
+        .await?
+        .into_iter()
+        .map(Star::from)
+        .collect();
+    app_env.database.put_stars(&stars)?;
+    Ok(stars)
+}
+
+#[cfg(test)]
+#[test]
+fn test_star_age() {
+    use chrono::TimeZone;
+
+    let now = Utc.ymd(2022, 8, 1).and_hms(0, 0, 0);
+    assert_eq!(star_age(now, Utc.ymd(2022, 7, 20).and_hms(0, 0, 0)), 0);
+    assert_eq!(star_age(now, Utc.ymd(2022, 6, 1).and_hms(0, 0, 0)), 1);
+    assert_eq!(star_age(now, Utc.ymd(2021, 12, 1).and_hms(0, 0, 0)), 2);
+    assert_eq!(star_age(now, Utc.ymd(2021, 1, 1).and_hms(0, 0, 0)), 3);
+    assert_eq!(star_age(now, Utc.ymd(2015, 1, 1).and_hms(0, 0, 0)), 4);
+}
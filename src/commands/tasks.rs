@@ -0,0 +1,320 @@
+use crate::{
+    app::get_repo_id_for_cwd,
+    app_env::AppEnv,
+    commands::undo::record_not_undoable,
+    display::{emojify, AbsoluteTime, Markdown},
+    github_models::GhNewIssue,
+    repository_id::{IssueId, PartialRepoId},
+    types::MutationKind,
+};
+use anyhow::{bail, Error};
+use dialoguer::{Editor, MultiSelect};
+use futures::TryStreamExt;
+use octocrab::models::IssueState;
+use serde::{Deserialize, Serialize};
+use std::{env, io::Write};
+
+/// Undo data of an assignees update.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdatedAssignees {
+    pub number: u64,
+    /// Assignees before the update.
+    pub assignees: Vec<String>,
+}
+
+/// Undo data of a labels update.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdatedLabels {
+    pub number: u64,
+    /// Labels before the update.
+    pub labels: Vec<String>,
+}
+
+/// Undo data of closing an issue.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ClosedIssue {
+    pub number: u64,
+}
+
+/// Creates an issue in a repository, of the current directory when there is none, and prints
+/// its URL.
+///
+/// Without a body, the body is edited in `$SHUB_EDITOR` when it's set.
+pub async fn create_issue(
+    mut app_env: AppEnv<'_>,
+    repo_id: Option<PartialRepoId>,
+    title: &str,
+    body: Option<String>,
+    labels: &[String],
+    assignee: Option<String>,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = match repo_id {
+        Some(x) => x.complete(app_env.github_username),
+        None => get_repo_id_for_cwd().await?,
+    };
+
+    let body = match (body, env::var("SHUB_EDITOR")) {
+        (Some(body), _) => body,
+        (None, Ok(editor)) => Editor::new()
+            .executable(editor)
+            .extension(".md")
+            .edit("")?
+            .map(|x| x.trim().to_owned())
+            .unwrap_or_default(),
+        (None, Err(_)) => String::new(),
+    };
+    let assignees: Vec<_> = assignee
+        .iter()
+        .map(|x| match x.as_str() {
+            "me" => app_env.github_username,
+            x => x,
+        })
+        .collect();
+
+    let issue = GhNewIssue {
+        title,
+        body: &body,
+        labels,
+        assignees: &assignees,
+    };
+    if let Some(issue) = app_env.github_client.create_issue(&repo_id, &issue).await? {
+        let target = format!("{repo_id}#{}", issue.number);
+        record_not_undoable(&mut app_env.database, &target, "issue creation")?;
+        writeln!(out, "{}", issue.html_url)?;
+    }
+
+    Ok(())
+}
+
+/// Prints title, labels, and body of an issue or a pull request.
+pub async fn view_issue(app_env: AppEnv<'_>, issue_id: IssueId) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let IssueId { repo, number } = issue_id;
+    let repo_id = repo.complete(app_env.github_username);
+
+    let issue = app_env.github_client.get_issue(&repo_id, number).await?;
+    writeln!(out, "{} {repo_id}#{number}", emojify(&issue.title))?;
+    writeln!(out, "by {} at {}", issue.user.login, issue.created_at.at())?;
+    if !issue.labels.is_empty() {
+        let labels: Vec<_> = issue.labels.iter().map(|x| x.name.as_str()).collect();
+        writeln!(out, "labels: {}", labels.join(", "))?;
+    }
+    writeln!(out)?;
+    match &issue.body {
+        Some(body) if !body.trim().is_empty() => write!(out, "{}", Markdown::new(body))?,
+        _ => writeln!(out, "No description provided.")?,
+    }
+
+    Ok(())
+}
+
+/// Adds a user, defaulting to me, to assignees of an issue or a pull request, or removes them.
+pub async fn assign_issue(
+    mut app_env: AppEnv<'_>,
+    issue_id: IssueId,
+    user: Option<String>,
+    unassign: bool,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let IssueId { repo, number } = issue_id;
+    let repo_id = repo.complete(app_env.github_username);
+    let user = user.unwrap_or_else(|| app_env.github_username.to_owned());
+
+    let issue = app_env.github_client.get_issue(&repo_id, number).await?;
+    let old: Vec<_> = issue.assignees.into_iter().map(|x| x.login).collect();
+
+    let assigned = old.iter().any(|x| x.eq_ignore_ascii_case(&user));
+    let new: Vec<_> = if unassign {
+        if !assigned {
+            writeln!(out, "{user} is not assigned to {repo_id}#{number}.")?;
+            return Ok(());
+        }
+        old.iter()
+            .filter(|x| !x.eq_ignore_ascii_case(&user))
+            .cloned()
+            .collect()
+    } else {
+        if assigned {
+            writeln!(out, "{user} is already assigned to {repo_id}#{number}.")?;
+            return Ok(());
+        }
+        old.iter().cloned().chain([user.clone()]).collect()
+    };
+
+    app_env
+        .github_client
+        .update_issue_assignees(&repo_id, number, &new)
+        .await?;
+
+    if !app_env.dry_run {
+        let data = UpdatedAssignees {
+            number,
+            assignees: old,
+        };
+        app_env.database.put_mutation(
+            MutationKind::UpdateAssignees,
+            &repo_id.to_string(),
+            &serde_json::to_string(&data)?,
+        )?;
+        if unassign {
+            writeln!(out, "Unassigned {user} from {repo_id}#{number}.")?;
+        } else {
+            writeln!(out, "Assigned {user} to {repo_id}#{number}.")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds and removes labels of an issue or a pull request.
+///
+/// Label names are checked against labels of the repository. When there are no labels to add
+/// or to remove, prompts to pick the labels from the repository labels.
+pub async fn label_issue(
+    mut app_env: AppEnv<'_>,
+    issue_id: IssueId,
+    add: Vec<String>,
+    rm: Vec<String>,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let IssueId { repo, number } = issue_id;
+    let repo_id = repo.complete(app_env.github_username);
+
+    let issue = app_env.github_client.get_issue(&repo_id, number).await?;
+    let old: Vec<_> = issue.labels.into_iter().map(|x| x.name).collect();
+    let available: Vec<_> = app_env
+        .github_client
+        .list_labels(&repo_id)
+        .map_ok(|x| x.name)
+        .try_collect()
+        .await?;
+
+    let new = if add.is_empty() && rm.is_empty() {
+        let items: Vec<_> = available.iter().map(|x| (x, old.contains(x))).collect();
+        let selection = MultiSelect::new()
+            .with_prompt("Labels")
+            .items_checked(&items)
+            .interact_opt()?;
+        match selection {
+            Some(x) => x.into_iter().map(|idx| available[idx].clone()).collect(),
+            None => return Ok(()),
+        }
+    } else {
+        let add = add
+            .iter()
+            .map(|x| resolve_label(x, &available))
+            .collect::<Result<Vec<_>, _>>()?;
+        let rm = rm
+            .iter()
+            .map(|x| resolve_label(x, &available))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut new: Vec<_> = old.iter().filter(|x| !rm.contains(x)).cloned().collect();
+        for x in add {
+            if !new.contains(&x) {
+                new.push(x);
+            }
+        }
+        new
+    };
+
+    if new == old {
+        writeln!(out, "Labels of {repo_id}#{number} are unchanged.")?;
+        return Ok(());
+    }
+
+    app_env
+        .github_client
+        .update_issue_labels(&repo_id, number, &new)
+        .await?;
+
+    if !app_env.dry_run {
+        let data = UpdatedLabels {
+            number,
+            labels: old,
+        };
+        app_env.database.put_mutation(
+            MutationKind::UpdateLabels,
+            &repo_id.to_string(),
+            &serde_json::to_string(&data)?,
+        )?;
+        writeln!(out, "Labeled {repo_id}#{number} with {}.", new.join(", "))?;
+    }
+
+    Ok(())
+}
+
+/// Closes an issue or a pull request.
+pub async fn close_issue(mut app_env: AppEnv<'_>, issue_id: IssueId) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let IssueId { repo, number } = issue_id;
+    let repo_id = repo.complete(app_env.github_username);
+
+    let issue = app_env.github_client.get_issue(&repo_id, number).await?;
+    if matches!(issue.state, IssueState::Closed) {
+        writeln!(out, "{repo_id}#{number} is already closed.")?;
+        return Ok(());
+    }
+
+    app_env
+        .github_client
+        .update_issue_state(&repo_id, number, true)
+        .await?;
+
+    if !app_env.dry_run {
+        let data = ClosedIssue { number };
+        app_env.database.put_mutation(
+            MutationKind::CloseIssue,
+            &repo_id.to_string(),
+            &serde_json::to_string(&data)?,
+        )?;
+        writeln!(out, "Closed {repo_id}#{number}.")?;
+    }
+
+    Ok(())
+}
+
+/// Finds repository label with the given name, ignoring case.
+///
+/// Errors with the labels starting with the given name as suggestions when there is none.
+fn resolve_label(name: &str, available: &[String]) -> Result<String, Error> {
+    if let Some(x) = available.iter().find(|x| x.eq_ignore_ascii_case(name)) {
+        return Ok(x.clone());
+    }
+    let prefix = name.to_lowercase();
+    let suggestions: Vec<_> = available
+        .iter()
+        .filter(|x| x.to_lowercase().starts_with(&prefix))
+        .map(String::as_str)
+        .collect();
+    if suggestions.is_empty() {
+        bail!("Repository has no label `{}`.", name)
+    } else {
+        bail!(
+            "Repository has no label `{}`, did you mean {}?",
+            name,
+            suggestions.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_resolve_label() {
+    let available = [
+        "bug".to_owned(),
+        "good first issue".to_owned(),
+        "wontfix".to_owned(),
+    ];
+    assert_eq!(resolve_label("Bug", &available).unwrap(), "bug");
+    assert_eq!(
+        resolve_label("good", &available).unwrap_err().to_string(),
+        "Repository has no label `good`, did you mean good first issue?"
+    );
+    assert_eq!(
+        resolve_label("feature", &available)
+            .unwrap_err()
+            .to_string(),
+        "Repository has no label `feature`."
+    );
+}
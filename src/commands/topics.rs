@@ -0,0 +1,99 @@
+use crate::{
+    app_env::AppEnv,
+    repository_id::{FullRepoId, PartialRepoId},
+    types::MutationKind,
+};
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+/// Undo data of a topics update.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdatedTopics {
+    /// Topics before the update.
+    pub topics: Vec<String>,
+}
+
+/// Prints topics of a repository, or adds and removes its topics when given any.
+pub async fn edit_topics(
+    mut app_env: AppEnv<'_>,
+    repo_id: PartialRepoId,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let repo_id = repo_id.complete(app_env.github_username);
+
+    let old = app_env.github_client.get_topics(&repo_id).await?;
+    if add.is_empty() && remove.is_empty() {
+        if old.is_empty() {
+            writeln!(out, "Repository {repo_id} has no topics.")?;
+        }
+        for x in &old {
+            writeln!(out, "{}", x)?;
+        }
+        return Ok(());
+    }
+
+    let new = apply_topic_changes(&old, &add, &remove);
+    if new == old {
+        writeln!(out, "Topics of {repo_id} are already up to date.")?;
+        return Ok(());
+    }
+    replace_topics(&mut app_env, &repo_id, old, &new).await?;
+    if !app_env.dry_run {
+        writeln!(out, "Topics of {repo_id}: {}", new.join(", "))?;
+    }
+
+    Ok(())
+}
+
+/// Replaces topics of a repository, recording the old topics so they can be restored.
+pub(crate) async fn replace_topics(
+    app_env: &mut AppEnv<'_>,
+    repo_id: &FullRepoId,
+    old: Vec<String>,
+    new: &[String],
+) -> Result<(), Error> {
+    app_env.github_client.replace_topics(repo_id, new).await?;
+    if !app_env.dry_run {
+        let data = UpdatedTopics { topics: old };
+        app_env.database.put_mutation(
+            MutationKind::UpdateTopics,
+            &repo_id.to_string(),
+            &serde_json::to_string(&data)?,
+        )?;
+    }
+    Ok(())
+}
+
+/// Adds and removes topics, keeping the order of the existing ones.
+///
+/// Topics are lower cased like GitHub does.
+fn apply_topic_changes(old: &[String], add: &[String], remove: &[String]) -> Vec<String> {
+    let remove: Vec<_> = remove.iter().map(|x| x.to_lowercase()).collect();
+    let mut topics: Vec<_> = old
+        .iter()
+        .filter(|x| !remove.contains(x))
+        .cloned()
+        .collect();
+    for x in add {
+        let x = x.to_lowercase();
+        if !topics.contains(&x) && !remove.contains(&x) {
+            topics.push(x);
+        }
+    }
+    topics
+}
+
+#[cfg(test)]
+#[test]
+fn test_apply_topic_changes() {
+    let topics = |xs: &[&str]| xs.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    let old = topics(&["rust", "cli", "github"]);
+    assert_eq!(
+        apply_topic_changes(&old, &topics(&["Terminal", "rust"]), &topics(&["CLI"])),
+        topics(&["rust", "github", "terminal"])
+    );
+    assert_eq!(apply_topic_changes(&old, &[], &[]), old);
+}
@@ -0,0 +1,184 @@
+use super::{
+    rename_branch::{do_rename_branch, RenamedBranch},
+    repos::{do_rename_repository, ArchivedRepository, RenamedRepository},
+    settings::RepositorySettings,
+    stars::{StarredRepositories, UnstarredRepositories},
+    tasks::{ClosedIssue, UpdatedAssignees, UpdatedLabels},
+    topics::UpdatedTopics,
+};
+use crate::{
+    app_env::AppEnv, database::Database, display::AbsoluteTime, repository_id::FullRepoId,
+    types::MutationKind,
+};
+use anyhow::{bail, Context, Error};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::Write;
+
+/// Data of a mutation that can't be undone.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NotUndoable {
+    /// What was done, e.g. `repository deletion`.
+    pub description: String,
+}
+
+/// Records a mutation that can't be undone, so undo stops at it instead of reverting an older
+/// mutation.
+pub(crate) fn record_not_undoable(
+    db: &mut Database,
+    target: &str,
+    description: &str,
+) -> Result<(), Error> {
+    let data = NotUndoable {
+        description: description.to_owned(),
+    };
+    db.put_mutation(
+        MutationKind::NotUndoable,
+        target,
+        &serde_json::to_string(&data)?,
+    )?;
+    Ok(())
+}
+
+/// Reverts the most recent recorded mutation that has not been undone.
+///
+/// Errors at a mutation that can't be undone, marking it undone so the next undo goes past it.
+pub async fn undo_last_mutation(mut app_env: AppEnv<'_>) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let mutation = match app_env.database.get_last_mutation()? {
+        Some(x) => x,
+        None => {
+            writeln!(out, "Nothing to undo.")?;
+            return Ok(());
+        }
+    };
+
+    let repo_id = || -> Result<FullRepoId, Error> {
+        mutation
+            .target
+            .parse()
+            .with_context(|| format!("Invalid mutation target `{}`.", mutation.target))
+    };
+
+    match mutation.kind {
+        MutationKind::UpdateSettings => {
+            let repo_id = repo_id()?;
+            let settings: RepositorySettings = serde_json::from_str(&mutation.data)?;
+            app_env
+                .github_client
+                .update_repository(&repo_id, &settings)
+                .await?;
+            if !app_env.dry_run {
+                writeln!(out, "Restored {repo_id} settings.")?;
+            }
+        }
+        MutationKind::RenameBranch => {
+            let repo_id = repo_id()?;
+            let RenamedBranch { old, new } = serde_json::from_str(&mutation.data)?;
+            do_rename_branch(&app_env, &repo_id, &new, &old).await?;
+        }
+        MutationKind::UpdateAssignees => {
+            let repo_id = repo_id()?;
+            let UpdatedAssignees { number, assignees } = serde_json::from_str(&mutation.data)?;
+            app_env
+                .github_client
+                .update_issue_assignees(&repo_id, number, &assignees)
+                .await?;
+            if !app_env.dry_run {
+                writeln!(out, "Restored {repo_id}#{number} assignees.")?;
+            }
+        }
+        MutationKind::UpdateLabels => {
+            let repo_id = repo_id()?;
+            let UpdatedLabels { number, labels } = serde_json::from_str(&mutation.data)?;
+            app_env
+                .github_client
+                .update_issue_labels(&repo_id, number, &labels)
+                .await?;
+            if !app_env.dry_run {
+                writeln!(out, "Restored {repo_id}#{number} labels.")?;
+            }
+        }
+        MutationKind::Unstar => {
+            let UnstarredRepositories { repos } = serde_json::from_str(&mutation.data)?;
+            for x in &repos {
+                let repo_id: FullRepoId = x.parse()?;
+                app_env.github_client.star_repository(&repo_id).await?;
+            }
+            if !app_env.dry_run {
+                // clear the cache so it's fetched again with the restored stars
+                app_env.database.put_stars(&[])?;
+                writeln!(out, "Starred {} repositories again.", repos.len())?;
+            }
+        }
+        MutationKind::Star => {
+            let StarredRepositories { repos } = serde_json::from_str(&mutation.data)?;
+            for x in &repos {
+                let repo_id: FullRepoId = x.parse()?;
+                app_env.github_client.unstar_repository(&repo_id).await?;
+            }
+            if !app_env.dry_run {
+                // clear the cache so it's fetched again without the imported stars
+                app_env.database.put_stars(&[])?;
+                writeln!(out, "Unstarred {} repositories again.", repos.len())?;
+            }
+        }
+        MutationKind::UpdateTopics => {
+            let repo_id = repo_id()?;
+            let UpdatedTopics { topics } = serde_json::from_str(&mutation.data)?;
+            app_env
+                .github_client
+                .replace_topics(&repo_id, &topics)
+                .await?;
+            if !app_env.dry_run {
+                writeln!(out, "Restored {repo_id} topics.")?;
+            }
+        }
+        MutationKind::SetArchived => {
+            let repo_id = repo_id()?;
+            let ArchivedRepository { archived } = serde_json::from_str(&mutation.data)?;
+            app_env
+                .github_client
+                .update_repository(&repo_id, &json!({ "archived": archived }))
+                .await?;
+            if !app_env.dry_run {
+                let verb = if archived { "Archived" } else { "Unarchived" };
+                writeln!(out, "{verb} {repo_id} again.")?;
+            }
+        }
+        MutationKind::RenameRepository => {
+            let repo_id = repo_id()?;
+            let RenamedRepository { old } = serde_json::from_str(&mutation.data)?;
+            do_rename_repository(&app_env, &repo_id, &old).await?;
+        }
+        MutationKind::CloseIssue => {
+            let repo_id = repo_id()?;
+            let ClosedIssue { number } = serde_json::from_str(&mutation.data)?;
+            app_env
+                .github_client
+                .update_issue_state(&repo_id, number, false)
+                .await?;
+            if !app_env.dry_run {
+                writeln!(out, "Reopened {repo_id}#{number}.")?;
+            }
+        }
+        MutationKind::NotUndoable => {
+            let NotUndoable { description } = serde_json::from_str(&mutation.data)?;
+            if !app_env.dry_run {
+                app_env.database.set_mutation_undone(mutation.id)?;
+            }
+            bail!(
+                "Last mutation, {} of {} at {}, can not be undone. Undo again to revert the one before it.",
+                description,
+                mutation.target,
+                mutation.created_at.at()
+            );
+        }
+    }
+
+    if !app_env.dry_run {
+        app_env.database.set_mutation_undone(mutation.id)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,497 @@
+use crate::{
+    app::{create_fetch_options, create_push_options},
+    app_env::AppEnv,
+    list_local_repository_paths,
+};
+use anyhow::{bail, Error};
+use futures::{stream, StreamExt};
+use git2::{build::CheckoutBuilder, BranchType, FetchPrune, Repository, StatusOptions};
+use std::{fmt, io::Write, path::Path};
+use tabwriter::TabWriter;
+use tokio::task;
+
+/// Number of projects inspected at the same time.
+const STATUS_CONCURRENCY: usize = 8;
+
+/// Prints current branch, number of dirty files, and ahead/behind counts of local projects.
+///
+/// Rows are printed as soon as their project is inspected, so they are not in a particular
+/// order.
+pub async fn print_projects_status(app_env: AppEnv<'_>) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let root = app_env.workspace_root_dir;
+    let paths = task::block_in_place(|| list_local_repository_paths(root))?;
+
+    // rows are streamed, so columns are padded instead of using a tab writer
+    let width = paths
+        .iter()
+        .map(|x| project_name(root, x).len())
+        .max()
+        .unwrap_or_default();
+
+    let mut results = stream::iter(paths)
+        .map(|path| async move {
+            let name = project_name(root, &path);
+            let status = task::spawn_blocking(move || inspect_project(&path)).await;
+            (name, status)
+        })
+        .buffer_unordered(STATUS_CONCURRENCY);
+
+    while let Some((name, status)) = results.next().await {
+        match status.map_err(Error::from).and_then(|x| x) {
+            Ok(x) => writeln!(
+                out,
+                "{name:width$}  {:20}  {:>8}  {}",
+                x.branch,
+                format!("{} dirty", x.dirty),
+                x.upstream
+            )?,
+            Err(err) => writeln!(out, "{name:width$}  error: {err}")?,
+        }
+        out.flush()?;
+    }
+
+    Ok(())
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct ProjectStatus {
+    branch: String,
+    dirty: usize,
+    upstream: UpstreamStatus,
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+enum UpstreamStatus {
+    None,
+    Tracking(usize /* ahead */, usize /* behind */),
+}
+
+impl fmt::Display for UpstreamStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpstreamStatus::None => write!(f, "no upstream"),
+            UpstreamStatus::Tracking(0, 0) => write!(f, "up to date"),
+            UpstreamStatus::Tracking(ahead, behind) => write!(f, "{ahead} ahead {behind} behind"),
+        }
+    }
+}
+
+/// Reads current branch, number of dirty files, and ahead/behind counts against upstream of a
+/// project, without fetching.
+fn inspect_project(path: &Path) -> Result<ProjectStatus, Error> {
+    let repo = Repository::open(path)?;
+
+    let dirty = repo
+        .statuses(Some(
+            StatusOptions::new()
+                .include_untracked(true)
+                .include_ignored(false),
+        ))?
+        .iter()
+        .filter(|x| !x.status().is_ignored())
+        .count();
+
+    let head = match repo.head() {
+        Ok(x) => x,
+        // e.g. a new repository without commits
+        Err(err) if err.code() == git2::ErrorCode::UnbornBranch => {
+            return Ok(ProjectStatus {
+                branch: "(unborn)".to_owned(),
+                dirty,
+                upstream: UpstreamStatus::None,
+            })
+        }
+        Err(err) => return Err(err.into()),
+    };
+    if !head.is_branch() {
+        return Ok(ProjectStatus {
+            branch: "(detached)".to_owned(),
+            dirty,
+            upstream: UpstreamStatus::None,
+        });
+    }
+    let branch = head.shorthand().unwrap_or_default().to_owned();
+
+    let upstream = match repo.find_branch(&branch, BranchType::Local)?.upstream() {
+        Ok(upstream) => {
+            let local = head.target().expect("branch should point to a commit");
+            let remote = upstream
+                .get()
+                .target()
+                .expect("branch should point to a commit");
+            let (ahead, behind) = repo.graph_ahead_behind(local, remote)?;
+            UpstreamStatus::Tracking(ahead, behind)
+        }
+        Err(_) => UpstreamStatus::None,
+    };
+
+    Ok(ProjectStatus {
+        branch,
+        dirty,
+        upstream,
+    })
+}
+
+/// Prints local projects whose default branch differs from origin's.
+pub async fn print_diverged_projects(app_env: AppEnv<'_>) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let root = app_env.workspace_root_dir;
+    let paths = task::block_in_place(|| list_local_repository_paths(root))?;
+
+    let mut w = TabWriter::new(Vec::new());
+    let mut count = 0;
+    for path in &paths {
+        let name = project_name(root, path);
+        let (branch, divergence) =
+            match task::block_in_place(|| check_divergence(path, app_env.proxy)) {
+                Ok(x) => x,
+                Err(err) => {
+                    writeln!(w, "{name}\t\terror: {err}")?;
+                    count += 1;
+                    continue;
+                }
+            };
+        if divergence == Divergence::UpToDate {
+            continue;
+        }
+        writeln!(w, "{name}\t{branch}\t{divergence}")?;
+        count += 1;
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    if count == 0 {
+        writeln!(out, "All default branches are up to date.")?;
+    }
+
+    Ok(())
+}
+
+/// Pushes current branch of local projects that have unpushed commits.
+///
+/// Projects with uncommitted changes, without upstream, or behind their upstream are skipped.
+pub async fn push_all_projects(app_env: AppEnv<'_>) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let root = app_env.workspace_root_dir;
+    let paths = task::block_in_place(|| list_local_repository_paths(root))?;
+
+    let mut w = TabWriter::new(Vec::new());
+    let (mut pushed, mut skipped, mut failed) = (0, 0, 0);
+    for path in &paths {
+        let name = project_name(root, path);
+        match task::block_in_place(|| push_project(path, app_env.dry_run, app_env.proxy)) {
+            Ok(PushOutcome::UpToDate) => (),
+            Ok(PushOutcome::Pushed(branch, count)) => {
+                if app_env.dry_run {
+                    writeln!(w, "{name}\t{branch}\t[dry-run] push {count} commit(s)")?;
+                } else {
+                    writeln!(w, "{name}\t{branch}\tpushed {count} commit(s)")?;
+                }
+                pushed += 1;
+            }
+            Ok(PushOutcome::Skipped(branch, reason)) => {
+                writeln!(w, "{name}\t{branch}\tskipped, {reason}")?;
+                skipped += 1;
+            }
+            Err(err) => {
+                writeln!(w, "{name}\t\tfailed, {err}")?;
+                failed += 1;
+            }
+        }
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    writeln!(
+        out,
+        "
+{pushed} pushed, {skipped} skipped, {failed} failed."
+    )?;
+
+    Ok(())
+}
+
+/// Fetches `origin`, and `upstream` when there is one, of local projects with pruning.
+///
+/// Prints branches that can be fast-forwarded to their upstream. With `ff`, fast-forwards the
+/// default branch if it has no local commits and, when checked out, the project has no
+/// uncommitted changes.
+pub async fn sync_projects(app_env: AppEnv<'_>, ff: bool) -> Result<(), Error> {
+    let mut out = app_env.output;
+    let root = app_env.workspace_root_dir;
+    let paths = task::block_in_place(|| list_local_repository_paths(root))?;
+
+    let mut w = TabWriter::new(Vec::new());
+    let (mut synced, mut failed) = (0, 0);
+    for path in &paths {
+        let name = project_name(root, path);
+        match task::block_in_place(|| sync_project(path, ff, app_env.dry_run, app_env.proxy)) {
+            Ok(x) => {
+                let mut notes = Vec::new();
+                if let Some((branch, count)) = &x.fast_forwarded {
+                    if app_env.dry_run {
+                        notes.push(format!("[dry-run] fast-forward {branch} by {count}"));
+                    } else {
+                        notes.push(format!("fast-forwarded {branch} by {count}"));
+                    }
+                }
+                if !x.fast_forwardable.is_empty() {
+                    let branches: Vec<_> = x
+                        .fast_forwardable
+                        .iter()
+                        .map(|(branch, count)| format!("{branch} (+{count})"))
+                        .collect();
+                    notes.push(format!("can fast-forward {}", branches.join(", ")));
+                }
+                writeln!(
+                    w,
+                    "{name}\tfetched {}\t{}",
+                    x.remotes.join(", "),
+                    notes.join("; ")
+                )?;
+                synced += 1;
+            }
+            Err(err) => {
+                writeln!(w, "{name}\tfailed, {err}")?;
+                failed += 1;
+            }
+        }
+    }
+    write!(out, "{}", String::from_utf8(w.into_inner()?)?)?;
+
+    writeln!(
+        out,
+        "
+{synced} synced, {failed} failed."
+    )?;
+
+    Ok(())
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct SyncOutcome {
+    /// Fetched remotes.
+    remotes: Vec<&'static str>,
+    /// Local branches that are behind, and not ahead of, their upstream.
+    fast_forwardable: Vec<(String /* branch */, usize /* commits */)>,
+    fast_forwarded: Option<(String /* branch */, usize /* commits */)>,
+}
+
+fn sync_project(
+    path: &Path,
+    ff: bool,
+    dry_run: bool,
+    proxy: Option<&str>,
+) -> Result<SyncOutcome, Error> {
+    let repo = Repository::open(path)?;
+
+    let mut remotes = Vec::new();
+    for remote_name in ["origin", "upstream"] {
+        let mut remote = match repo.find_remote(remote_name) {
+            Ok(x) => x,
+            Err(_) if remote_name == "upstream" => continue,
+            Err(err) => return Err(err.into()),
+        };
+        let mut options = create_fetch_options(proxy);
+        options.prune(FetchPrune::On);
+        remote.fetch::<&str>(&[], Some(&mut options), None)?;
+        remotes.push(remote_name);
+    }
+
+    let mut fast_forwardable = Vec::new();
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        let upstream = match branch.upstream() {
+            Ok(x) => x,
+            Err(_) => continue,
+        };
+        let (local, remote) = match (branch.get().target(), upstream.get().target()) {
+            (Some(local), Some(remote)) => (local, remote),
+            _ => continue,
+        };
+        let (ahead, behind) = repo.graph_ahead_behind(local, remote)?;
+        if ahead == 0 && behind > 0 {
+            let name = branch.name()?.unwrap_or_default().to_owned();
+            fast_forwardable.push((name, behind));
+        }
+    }
+
+    let mut fast_forwarded = None;
+    if ff {
+        let default = default_branch(&repo)?;
+        if let Some((_, count)) = fast_forwardable.iter().find(|(x, _)| *x == default) {
+            if !dry_run {
+                fast_forward(&repo, &default)?;
+            }
+            fast_forwarded = Some((default, *count));
+        }
+    }
+    fast_forwardable.retain(|(x, _)| Some(x) != fast_forwarded.as_ref().map(|(x, _)| x));
+
+    Ok(SyncOutcome {
+        remotes,
+        fast_forwardable,
+        fast_forwarded,
+    })
+}
+
+/// Moves a local branch to its upstream, updating the working tree when it's checked out.
+///
+/// Fails when the branch is checked out and there are uncommitted changes.
+fn fast_forward(repo: &Repository, branch: &str) -> Result<(), Error> {
+    let mut local = repo.find_branch(branch, BranchType::Local)?;
+    let target = local
+        .upstream()?
+        .get()
+        .target()
+        .expect("branch should point to a commit");
+
+    if local.is_head() {
+        let dirty = repo
+            .statuses(Some(StatusOptions::new().include_untracked(false)))?
+            .iter()
+            .any(|x| !x.status().is_ignored());
+        if dirty {
+            bail!("{branch} is checked out and has uncommitted changes")
+        }
+        let commit = repo.find_commit(target)?;
+        repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))?;
+    }
+    local
+        .get_mut()
+        .set_target(target, &format!("shub: fast-forward {branch}"))?;
+
+    Ok(())
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum PushOutcome {
+    UpToDate,
+    Pushed(String /* branch */, usize /* commits */),
+    Skipped(String /* branch */, &'static str /* reason */),
+}
+
+fn push_project(path: &Path, dry_run: bool, proxy: Option<&str>) -> Result<PushOutcome, Error> {
+    let repo = Repository::open(path)?;
+
+    let head = repo.head()?;
+    if !head.is_branch() {
+        return Ok(PushOutcome::Skipped(String::new(), "detached HEAD"));
+    }
+    let branch_ref = head.name().unwrap_or_default().to_owned();
+    let branch = head.shorthand().unwrap_or_default().to_owned();
+
+    let upstream = match repo.find_branch(&branch, BranchType::Local)?.upstream() {
+        Ok(x) => x,
+        Err(_) => return Ok(PushOutcome::Skipped(branch, "no upstream")),
+    };
+    let upstream_ref = upstream.get().name().unwrap_or_default().to_owned();
+    let local = head.target().expect("branch should point to a commit");
+    let remote = upstream
+        .get()
+        .target()
+        .expect("branch should point to a commit");
+    let (ahead, behind) = repo.graph_ahead_behind(local, remote)?;
+    if ahead == 0 {
+        return Ok(PushOutcome::UpToDate);
+    }
+    if behind > 0 {
+        return Ok(PushOutcome::Skipped(branch, "behind upstream"));
+    }
+
+    let dirty = repo
+        .statuses(Some(StatusOptions::new().include_untracked(false)))?
+        .iter()
+        .any(|x| !x.status().is_ignored());
+    if dirty {
+        return Ok(PushOutcome::Skipped(branch, "uncommitted changes"));
+    }
+
+    let remote_name = repo.branch_upstream_remote(&branch_ref)?;
+    let remote_name = remote_name
+        .as_str()
+        .ok_or_else(|| Error::msg("remote name is not valid utf-8"))?;
+    let remote_branch = upstream_ref
+        .strip_prefix(&format!("refs/remotes/{remote_name}/"))
+        .unwrap_or(branch.as_str());
+    if dry_run {
+        return Ok(PushOutcome::Pushed(branch, ahead));
+    }
+    let mut remote = repo.find_remote(remote_name)?;
+    remote.push(
+        &[format!("{branch_ref}:refs/heads/{remote_branch}")],
+        Some(&mut create_push_options(proxy)),
+    )?;
+
+    Ok(PushOutcome::Pushed(branch, ahead))
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+enum Divergence {
+    UpToDate,
+    Behind(usize),
+    Ahead(usize),
+    Diverged(usize /* ahead */, usize /* behind */),
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Divergence::*;
+        match self {
+            UpToDate => write!(f, "up to date"),
+            Behind(x) => write!(f, "behind by {x}"),
+            Ahead(x) => write!(f, "ahead by {x}, unpushed"),
+            Diverged(ahead, behind) => write!(f, "diverged, {ahead} ahead {behind} behind"),
+        }
+    }
+}
+
+/// Fetches origin and compares local default branch against it.
+fn check_divergence(path: &Path, proxy: Option<&str>) -> Result<(String, Divergence), Error> {
+    let repo = Repository::open(path)?;
+
+    let mut remote = repo.find_remote("origin")?;
+    let mut options = create_fetch_options(proxy);
+    options.prune(FetchPrune::On);
+    remote.fetch::<&str>(&[], Some(&mut options), None)?;
+
+    let branch = default_branch(&repo)?;
+    let local = repo.refname_to_id(&format!("refs/heads/{branch}"))?;
+    let upstream = repo.refname_to_id(&format!("refs/remotes/origin/{branch}"))?;
+    let divergence = match repo.graph_ahead_behind(local, upstream)? {
+        (0, 0) => Divergence::UpToDate,
+        (0, behind) => Divergence::Behind(behind),
+        (ahead, 0) => Divergence::Ahead(ahead),
+        (ahead, behind) => Divergence::Diverged(ahead, behind),
+    };
+    Ok((branch, divergence))
+}
+
+/// Finds default branch name from `origin/HEAD`, falls back to a local `main` or `master`
+/// branch.
+fn default_branch(repo: &Repository) -> Result<String, Error> {
+    let from_origin_head = repo
+        .find_reference("refs/remotes/origin/HEAD")
+        .ok()
+        .and_then(|x| {
+            x.symbolic_target()
+                .and_then(|x| x.strip_prefix("refs/remotes/origin/"))
+                .map(ToOwned::to_owned)
+        });
+    if let Some(branch) = from_origin_head {
+        return Ok(branch);
+    }
+    for branch in ["main", "master"] {
+        if repo.find_branch(branch, BranchType::Local).is_ok() {
+            return Ok(branch.to_owned());
+        }
+    }
+    bail!("failed to find default branch")
+}
+
+/// Creates project name, i.e. `:owner/:name`, from its path.
+fn project_name(workspace_root_dir: &Path, path: &Path) -> String {
+    path.strip_prefix(workspace_root_dir)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
@@ -0,0 +1,269 @@
+//! Defines configuration file, `config.toml` in the config directory.
+//!
+//! Environment variables override values of the selected profile.
+
+use crate::token;
+use anyhow::{Context, Error};
+use sekret::Secret;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Default depth of directories searched for projects, i.e. `<root>/<owner>/<project>`.
+const DEFAULT_PROJECT_DEPTH: usize = 2;
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    /// Profile used when none is selected.
+    default_profile: Option<String>,
+
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Deserialize, Default, PartialEq, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+struct Profile {
+    username: Option<String>,
+    token: Option<String>,
+    /// Command line printing the token, like `SHUB_TOKEN_COMMAND`.
+    token_command: Option<String>,
+    /// GitHub API URL, e.g. of a GitHub Enterprise Server.
+    api_url: Option<String>,
+    /// Proxy of GitHub API and git requests, like `SHUB_PROXY`.
+    proxy: Option<String>,
+    workspace_root: Option<PathBuf>,
+    /// Directories where projects are searched, besides the workspace.
+    #[serde(default)]
+    project_roots: Vec<PathBuf>,
+    /// Depth of directories searched for projects.
+    project_depth: Option<usize>,
+}
+
+/// Settings of the selected profile, overridden by environment variables.
+#[derive(Debug)]
+pub struct Config {
+    /// Name of the selected profile, none without one.
+    pub profile: Option<String>,
+    pub github_username: String,
+    pub github_token: Secret<String>,
+    pub github_api_url: Option<String>,
+    pub proxy: Option<String>,
+    pub workspace_root_dir: PathBuf,
+    pub project_roots: Vec<PathBuf>,
+    pub project_depth: usize,
+}
+
+/// Loads configuration of a profile.
+///
+/// The profile is the given one, then the one in `SHUB_PROFILE`, then `default_profile` of the
+/// file. Without a profile, settings are only read from environment variables.
+pub fn load_config(config_dir: &Path, profile: Option<&str>) -> Result<Config, Error> {
+    let (name, profile) = read_profile(config_dir, profile)?;
+    resolve_config(name, profile, |x| env::var(x).ok())
+}
+
+/// Gets name of the selected profile, without reading its token, which may need a prompt.
+pub fn load_profile_name(
+    config_dir: &Path,
+    profile: Option<&str>,
+) -> Result<Option<String>, Error> {
+    read_profile(config_dir, profile).map(|(name, _)| name)
+}
+
+/// GitHub API settings of a profile, overridden by environment variables.
+#[derive(PartialEq, Debug)]
+pub struct ApiSettings {
+    pub api_url: Option<String>,
+    pub proxy: Option<String>,
+}
+
+/// Loads GitHub API settings of a profile, without the token, which auth commands manage.
+pub fn load_api_settings(config_dir: &Path, profile: Option<&str>) -> Result<ApiSettings, Error> {
+    let (_, profile) = read_profile(config_dir, profile)?;
+    Ok(resolve_api_settings(&|x| env::var(x).ok(), &profile))
+}
+
+fn resolve_api_settings(var: &impl Fn(&str) -> Option<String>, profile: &Profile) -> ApiSettings {
+    ApiSettings {
+        api_url: var("SHUB_API_URL").or_else(|| profile.api_url.clone()),
+        proxy: var("SHUB_PROXY").or_else(|| profile.proxy.clone()),
+    }
+}
+
+/// Directories searched for projects, the workspace first.
+#[derive(PartialEq, Debug)]
+pub struct ProjectDirs {
+    pub roots: Vec<PathBuf>,
+    pub depth: usize,
+}
+
+/// Loads directories searched for projects of a profile, without the token, which may need a
+/// prompt.
+pub fn load_project_dirs(config_dir: &Path, profile: Option<&str>) -> Result<ProjectDirs, Error> {
+    let (_, profile) = read_profile(config_dir, profile)?;
+    let var = |x: &str| env::var(x).ok();
+    let workspace_root_dir = resolve_workspace_root_dir(&var, profile.workspace_root)?;
+    let roots = [workspace_root_dir]
+        .into_iter()
+        .chain(profile.project_roots)
+        .collect();
+    let depth = profile.project_depth.unwrap_or(DEFAULT_PROJECT_DEPTH);
+    Ok(ProjectDirs { roots, depth })
+}
+
+fn read_profile(
+    config_dir: &Path,
+    profile: Option<&str>,
+) -> Result<(Option<String>, Profile), Error> {
+    let path = config_dir.join(CONFIG_FILE_NAME);
+    let file: ConfigFile = match fs::read_to_string(&path) {
+        Ok(text) => {
+            toml::from_str(&text).with_context(|| format!("Failed to parse {}.", path.display()))?
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => ConfigFile::default(),
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {}.", path.display())),
+    };
+    let name = profile
+        .map(ToOwned::to_owned)
+        .or_else(|| env::var("SHUB_PROFILE").ok());
+    select_profile(file, name.as_deref())
+        .with_context(|| format!("Invalid profile in {}.", path.display()))
+}
+
+fn select_profile(
+    mut file: ConfigFile,
+    name: Option<&str>,
+) -> Result<(Option<String>, Profile), Error> {
+    let name = match name.map(ToOwned::to_owned).or(file.default_profile) {
+        Some(x) => x,
+        None => return Ok((None, Profile::default())),
+    };
+    match file.profiles.remove(&name) {
+        Some(x) => Ok((Some(name), x)),
+        None => {
+            let names: Vec<_> = file.profiles.keys().map(String::as_str).collect();
+            anyhow::bail!(
+                "Profile `{}` not found, available profiles are: {}.",
+                name,
+                names.join(", ")
+            )
+        }
+    }
+}
+
+fn resolve_config(
+    name: Option<String>,
+    profile: Profile,
+    var: impl Fn(&str) -> Option<String>,
+) -> Result<Config, Error> {
+    let ApiSettings {
+        api_url: github_api_url,
+        proxy,
+    } = resolve_api_settings(&var, &profile);
+    let github_username = var("SHUB_USERNAME")
+        .or(profile.username)
+        .context("GitHub username not found, set SHUB_USERNAME or `username` of the profile.")?;
+    let workspace_root_dir = resolve_workspace_root_dir(&var, profile.workspace_root)?;
+    let github_token = match (var("SHUB_TOKEN"), profile.token, profile.token_command) {
+        (Some(x), _, _) | (None, Some(x), _) => x,
+        (None, None, Some(command_line)) => token::run_token_command(&command_line)?,
+        (None, None, None) => {
+            token::get_github_token(&token::api_host(github_api_url.as_deref())?)?
+        }
+    };
+    Ok(Config {
+        profile: name,
+        github_username,
+        github_token: Secret(github_token),
+        github_api_url,
+        proxy,
+        workspace_root_dir,
+        project_roots: profile.project_roots,
+        project_depth: profile.project_depth.unwrap_or(DEFAULT_PROJECT_DEPTH),
+    })
+}
+
+fn resolve_workspace_root_dir(
+    var: &impl Fn(&str) -> Option<String>,
+    workspace_root: Option<PathBuf>,
+) -> Result<PathBuf, Error> {
+    var("WORKSPACE_HOME")
+        .map(PathBuf::from)
+        .or(workspace_root)
+        .context("Workspace not found, set WORKSPACE_HOME or `workspace_root` of the profile.")
+}
+
+#[cfg(test)]
+#[test]
+fn test_load_profile() {
+    let text = r#"
+        default_profile = "personal"
+
+        [profiles.personal]
+        username = "kafji"
+        token = "ghp_personal"
+        workspace_root = "/home/kafji/ws"
+
+        [profiles.work]
+        username = "kafji-work"
+        token = "ghp_work"
+        api_url = "https://github.example.com/api/v3/"
+        proxy = "http://proxy.example.com:3128"
+        workspace_root = "/home/kafji/work"
+        project_roots = ["/home/kafji/src"]
+        project_depth = 3
+    "#;
+    let parse = || toml::from_str::<ConfigFile>(text).unwrap();
+
+    let (name, profile) = select_profile(parse(), None).unwrap();
+    assert_eq!(name.as_deref(), Some("personal"));
+    assert_eq!(profile.username.as_deref(), Some("kafji"));
+    let (name, profile) = select_profile(parse(), Some("work")).unwrap();
+    assert_eq!(name.as_deref(), Some("work"));
+    assert_eq!(profile.username.as_deref(), Some("kafji-work"));
+    assert!(select_profile(parse(), Some("play")).is_err());
+
+    let config = resolve_config(name.clone(), profile.clone(), |_| None).unwrap();
+    assert_eq!(config.profile.as_deref(), Some("work"));
+    assert_eq!(config.github_username, "kafji-work");
+    assert_eq!(config.github_token.0, "ghp_work");
+    assert_eq!(
+        config.github_api_url.as_deref(),
+        Some("https://github.example.com/api/v3/")
+    );
+    assert_eq!(
+        config.proxy.as_deref(),
+        Some("http://proxy.example.com:3128")
+    );
+    assert_eq!(config.workspace_root_dir, PathBuf::from("/home/kafji/work"));
+    assert_eq!(config.project_roots, [PathBuf::from("/home/kafji/src")]);
+    assert_eq!(config.project_depth, 3);
+
+    let settings = resolve_api_settings(
+        &|x| (x == "SHUB_PROXY").then(|| "http://localhost:3128".to_owned()),
+        &profile,
+    );
+    assert_eq!(
+        settings,
+        ApiSettings {
+            api_url: Some("https://github.example.com/api/v3/".to_owned()),
+            proxy: Some("http://localhost:3128".to_owned()),
+        }
+    );
+
+    let config = resolve_config(name, profile, |x| match x {
+        "SHUB_USERNAME" => Some("someone".to_owned()),
+        "WORKSPACE_HOME" => Some("/tmp/ws".to_owned()),
+        _ => None,
+    })
+    .unwrap();
+    assert_eq!(config.github_username, "someone");
+    assert_eq!(config.workspace_root_dir, PathBuf::from("/tmp/ws"));
+}
@@ -1,95 +1,725 @@
 use crate::{
-    repository_id::IsRepositoryId,
-    types::{BuildStatus, Repository},
+    repository_id::{FullRepoId, IsRepositoryId},
+    types::{BuildStatus, BuildStatusRecord, Mutation, MutationKind, Repository, Star},
 };
+use anyhow::bail;
+use chrono::{DateTime, Utc};
 use rusqlite::{
     params,
     types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, Value, ValueRef},
-    ToSql,
+    OptionalExtension, ToSql,
 };
-use std::{fmt, path::Path};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+};
+use tokio::task;
 use tracing::info;
 
 type Repositories = Vec<Repository>;
 
-/// Sql for databse migrations.
+/// A step of the database schema.
+enum Migration {
+    Sql(&'static str),
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+}
+
+/// Database migrations, in order.
 ///
-/// All operations must be idempotent.
-const MIGRATIONS: &'static str = "
-    CREATE TABLE IF NOT EXISTS repositories (
-        rid INTEGER PRIMARY KEY AUTOINCREMENT,
-        owner TEXT NOT NULL,
-        name TEXT NOT NULL,
-        a_fork Boll NOT NULL DEFAULT FALSE,
-        archived BOOL NOT NULL DEFAULT FALSE,
-        build_status TEXT NULL,
-        UNIQUE (owner, name) ON CONFLICT REPLACE
-    );
-";
-
-pub struct Database(rusqlite::Connection);
+/// The schema version, `user_version` of the database, is the number of applied migrations. New
+/// migrations are appended, applied migrations must not change.
+///
+/// Databases created before the schema was versioned have version 0 whatever migrations they
+/// have, so migrations up to [UNVERSIONED_MIGRATIONS] must be idempotent.
+const MIGRATIONS: &[Migration] = &[
+    // 1
+    Migration::Sql(
+        "CREATE TABLE IF NOT EXISTS repositories (
+            rid INTEGER PRIMARY KEY AUTOINCREMENT,
+            owner TEXT NOT NULL,
+            name TEXT NOT NULL,
+            a_fork Boll NOT NULL DEFAULT FALSE,
+            archived BOOL NOT NULL DEFAULT FALSE,
+            build_status TEXT NULL,
+            UNIQUE (owner, name) ON CONFLICT REPLACE
+        );",
+    ),
+    // 2
+    Migration::Sql(
+        "CREATE TABLE IF NOT EXISTS pinned_repositories (
+            owner TEXT NOT NULL,
+            name TEXT NOT NULL,
+            UNIQUE (owner, name) ON CONFLICT IGNORE
+        );
+        CREATE TABLE IF NOT EXISTS ignored_repositories (
+            owner TEXT NOT NULL,
+            name TEXT NOT NULL,
+            UNIQUE (owner, name) ON CONFLICT IGNORE
+        );",
+    ),
+    // 3
+    Migration::Sql(
+        "CREATE TABLE IF NOT EXISTS mutations (
+            mid INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            target TEXT NOT NULL,
+            data TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            undone BOOL NOT NULL DEFAULT FALSE
+        );",
+    ),
+    // 4
+    Migration::AddColumn {
+        table: "repositories",
+        column: "pushed_at",
+        definition: "TEXT NULL",
+    },
+    // 5
+    Migration::Sql(
+        "CREATE TABLE IF NOT EXISTS starred_repositories (
+            owner TEXT NOT NULL,
+            name TEXT NOT NULL,
+            description TEXT NULL,
+            language TEXT NULL,
+            UNIQUE (owner, name) ON CONFLICT REPLACE
+        );",
+    ),
+    // 6
+    Migration::AddColumn {
+        table: "starred_repositories",
+        column: "starred_at",
+        definition: "TEXT NULL",
+    },
+    // 7
+    Migration::AddColumn {
+        table: "starred_repositories",
+        column: "topics",
+        definition: "TEXT NULL",
+    },
+    // 8
+    Migration::Sql(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS starred_repositories_fts USING fts5 (
+            owner,
+            name,
+            description,
+            language,
+            topics
+        );
+        -- index stars cached before the search index existed
+        INSERT INTO starred_repositories_fts (owner, name, description, language, topics)
+            SELECT owner, name, description, language, topics
+                FROM starred_repositories
+                WHERE NOT EXISTS (SELECT 1 FROM starred_repositories_fts);",
+    ),
+    // 9
+    Migration::Sql(
+        "CREATE TABLE IF NOT EXISTS http_cache (
+            url TEXT NOT NULL,
+            etag TEXT NOT NULL,
+            link TEXT NULL,
+            body BLOB NOT NULL,
+            UNIQUE (url) ON CONFLICT REPLACE
+        );",
+    ),
+    // 10
+    Migration::AddColumn {
+        table: "repositories",
+        column: "open_pull_requests",
+        definition: "INTEGER NULL",
+    },
+    // 11
+    Migration::AddColumn {
+        table: "repositories",
+        column: "unmerged_branches",
+        definition: "INTEGER NULL",
+    },
+    // 12
+    Migration::AddColumn {
+        table: "repositories",
+        column: "build_status_sha",
+        definition: "TEXT NULL",
+    },
+    // 13
+    Migration::Sql(
+        "CREATE TABLE build_status_history (
+            hid INTEGER PRIMARY KEY AUTOINCREMENT,
+            owner TEXT NOT NULL,
+            name TEXT NOT NULL,
+            sha TEXT NOT NULL,
+            status TEXT NOT NULL,
+            recorded_at TEXT NOT NULL
+        );
+        CREATE INDEX build_status_history_repository ON build_status_history (owner, name);",
+    ),
+    // 14
+    Migration::Sql(
+        "CREATE TABLE backups (
+            owner TEXT NOT NULL,
+            name TEXT NOT NULL,
+            backed_up_at TEXT NOT NULL,
+            PRIMARY KEY (owner, name)
+        );",
+    ),
+];
+
+/// Number of migrations of databases created before the schema was versioned.
+const UNVERSIONED_MIGRATIONS: usize = 12;
+
+/// Database of shub.
+///
+/// Dashboard queries, which run during long updates, are async and run on the blocking thread
+/// pool. Other queries are quick and block.
+///
+/// Clones share the connection.
+#[derive(Clone)]
+pub struct Database(Arc<Mutex<rusqlite::Connection>>);
 
 impl Database {
     #[tracing::instrument]
     pub fn new(path: &Path) -> Result<Self, anyhow::Error> {
         let conn = rusqlite::Connection::open(path)?;
-        let db = Self(conn);
+        let db = Self(Arc::new(Mutex::new(conn)));
         migrate(&db)?;
         Ok(db)
     }
 
+    fn conn(&self) -> MutexGuard<'_, rusqlite::Connection> {
+        self.0.lock().expect("database lock should not be poisoned")
+    }
+
+    /// Runs a function with the connection on the blocking thread pool.
+    async fn spawn_blocking<T, F>(&self, f: F) -> Result<T, anyhow::Error>
+    where
+        F: FnOnce(&mut rusqlite::Connection) -> Result<T, anyhow::Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        let conn = self.0.clone();
+        task::spawn_blocking(move || {
+            let mut conn = conn.lock().expect("database lock should not be poisoned");
+            f(&mut conn)
+        })
+        .await?
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn put_repositories(
+        &self,
+        repositories: Vec<Repository>,
+    ) -> Result<(), anyhow::Error> {
+        self.spawn_blocking(move |conn| put_repositories(conn, &repositories))
+            .await
+    }
+
     #[tracing::instrument(skip(self))]
-    pub fn put_repositories(&mut self, repositories: &[Repository]) -> Result<(), anyhow::Error> {
-        put_repositories(self, repositories)
+    pub async fn get_dashboard_repositories(
+        &self,
+        owner: &str,
+    ) -> Result<Repositories, anyhow::Error> {
+        let owner = owner.to_owned();
+        self.spawn_blocking(move |conn| get_dashboard_repositories(conn, &owner))
+            .await
     }
 
+    /// Set build statuses of repositories and the commits they were computed for.
+    ///
+    /// A build status is also appended to the history when it differs from the last recorded one
+    /// of the repository.
     #[tracing::instrument(skip(self))]
-    pub fn get_dashboard_repositories(&self, owner: &str) -> Result<Repositories, anyhow::Error> {
-        get_dashboard_repositories(self, owner)
+    pub async fn set_build_statuses(
+        &self,
+        build_statuses: Vec<(
+            impl IsRepositoryId + fmt::Debug + Send + 'static,
+            Option<BuildStatus>,
+            Option<String>, /* sha */
+        )>,
+    ) -> Result<(), anyhow::Error> {
+        self.spawn_blocking(move |conn| set_build_statuses(conn, &build_statuses))
+            .await
     }
 
-    /// Set build statuses of repositories.
+    /// Set open pull requests and unmerged branches counts of repositories.
     #[tracing::instrument(skip(self))]
-    pub fn set_build_statuses(
-        &mut self,
-        build_statuses: &[(impl IsRepositoryId + fmt::Debug, BuildStatus)],
+    pub async fn set_repository_activities(
+        &self,
+        activities: Vec<(
+            impl IsRepositoryId + fmt::Debug + Send + 'static,
+            u64, /* open pull requests */
+            u64, /* unmerged branches */
+        )>,
     ) -> Result<(), anyhow::Error> {
-        let tx = self.0.transaction()?;
-        let mut stmt = tx.prepare_cached(
-            "UPDATE repositories
-                SET build_status = ?
-                WHERE
-                    owner = ? AND
-                    name = ?
+        self.spawn_blocking(move |conn| set_repository_activities(conn, &activities))
+            .await
+    }
+
+    /// Gets recorded build statuses of a repository, most recent first.
+    #[tracing::instrument(skip(self))]
+    pub fn get_build_status_history(
+        &self,
+        repo_id: &(impl IsRepositoryId + fmt::Debug),
+        limit: usize,
+    ) -> Result<Vec<BuildStatusRecord>, anyhow::Error> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "SELECT sha, status, recorded_at
+                FROM build_status_history
+                WHERE owner = ? AND name = ?
+                ORDER BY hid DESC
+                LIMIT ?
             ;",
         )?;
-        for (id, status) in build_statuses {
-            stmt.execute(params![status, id.owner(), id.name()])?;
+        let records = stmt
+            .query_map(params![repo_id.owner(), repo_id.name(), limit], |x| {
+                Ok(BuildStatusRecord {
+                    sha: x.get(0)?,
+                    status: x.get(1)?,
+                    recorded_at: x.get(2)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(records)
+    }
+
+    /// Gets success rates of the last finished builds of repositories of an owner, by name.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_success_rates(
+        &self,
+        owner: &str,
+        builds: usize,
+    ) -> Result<HashMap<String, f64>, anyhow::Error> {
+        let owner = owner.to_owned();
+        self.spawn_blocking(move |conn| get_success_rates(conn, &owner, builds))
+            .await
+    }
+
+    /// Replaces stored starred repositories.
+    #[tracing::instrument(skip(self, stars))]
+    pub fn put_stars(&mut self, stars: &[Star]) -> Result<(), anyhow::Error> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM starred_repositories;", [])?;
+        tx.execute("DELETE FROM starred_repositories_fts;", [])?;
+        for Star {
+            owner,
+            name,
+            description,
+            language,
+            starred_at,
+            topics,
+        } in stars
+        {
+            let topics = topics.join(" ");
+            tx.execute(
+                "INSERT INTO starred_repositories
+                    (owner, name, description, language, starred_at, topics)
+                    VALUES (?, ?, ?, ?, ?, ?)
+                ;",
+                params![owner, name, description, language, starred_at, topics],
+            )?;
+            tx.execute(
+                "INSERT INTO starred_repositories_fts (owner, name, description, language, topics)
+                    VALUES (?, ?, ?, ?, ?)
+                ;",
+                params![owner, name, description, language, topics],
+            )?;
         }
-        drop(stmt);
         tx.commit()?;
         Ok(())
     }
+
+    /// Removes a stored starred repository.
+    #[tracing::instrument(skip(self))]
+    pub fn delete_star(
+        &mut self,
+        repo_id: &(impl IsRepositoryId + fmt::Debug),
+    ) -> Result<(), anyhow::Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM starred_repositories WHERE owner = ? AND name = ?;",
+            params![owner, name],
+        )?;
+        tx.execute(
+            "DELETE FROM starred_repositories_fts WHERE owner = ? AND name = ?;",
+            params![owner, name],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Searches stored starred repositories by their name, description, language, and topics,
+    /// best matches first.
+    ///
+    /// Every word of the query must match a word, or a prefix of a word, of the repositories.
+    #[tracing::instrument(skip(self))]
+    pub fn search_stars(&self, query: &str) -> Result<Vec<Star>, anyhow::Error> {
+        let query = fts_query(query);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "SELECT s.owner, s.name, s.description, s.language, s.starred_at, s.topics
+                FROM starred_repositories_fts f
+                JOIN starred_repositories s ON s.owner = f.owner AND s.name = f.name
+                WHERE starred_repositories_fts MATCH ?
+                ORDER BY f.rank
+            ;",
+        )?;
+        let stars = stmt
+            .query_map([query], star_from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(stars)
+    }
+
+    /// Gets stored starred repositories.
+    #[tracing::instrument(skip(self))]
+    pub fn get_stars(&self) -> Result<Vec<Star>, anyhow::Error> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "SELECT owner, name, description, language, starred_at, topics
+                FROM starred_repositories
+                ORDER BY owner, name
+            ;",
+        )?;
+        let stars = stmt
+            .query_map([], star_from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(stars)
+    }
+
+    /// Pins or unpins a repository, pinned repositories are listed first in the dashboard.
+    #[tracing::instrument(skip(self))]
+    pub fn set_pinned(
+        &mut self,
+        repo_id: &(impl IsRepositoryId + fmt::Debug),
+        pinned: bool,
+    ) -> Result<(), anyhow::Error> {
+        set_flag(self, "pinned_repositories", repo_id, pinned)
+    }
+
+    /// Ignores or unignores a repository, ignored repositories are excluded from the dashboard.
+    #[tracing::instrument(skip(self))]
+    pub fn set_ignored(
+        &mut self,
+        repo_id: &(impl IsRepositoryId + fmt::Debug),
+        ignored: bool,
+    ) -> Result<(), anyhow::Error> {
+        set_flag(self, "ignored_repositories", repo_id, ignored)
+    }
+
+    /// Gets identifiers, `:owner/:name`, of stored owned and starred repositories.
+    #[tracing::instrument(skip(self))]
+    pub fn get_repository_ids(&self) -> Result<Vec<String>, anyhow::Error> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "SELECT owner || '/' || name FROM repositories
+                UNION
+                SELECT owner || '/' || name FROM starred_repositories
+                ORDER BY 1
+            ;",
+        )?;
+        let ids = stmt
+            .query_map([], |x| x.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(ids)
+    }
+
+    /// Checks integrity of the database file, returns the problems found.
+    #[tracing::instrument(skip(self))]
+    pub fn check_integrity(&self) -> Result<Vec<String>, anyhow::Error> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("PRAGMA integrity_check;")?;
+        let problems = stmt
+            .query_map([], |x| x.get::<_, String>(0))?
+            .filter(|x| !matches!(x.as_deref(), Ok("ok")))
+            .collect::<Result<_, _>>()?;
+        Ok(problems)
+    }
+
+    /// Gets ignored repositories.
+    #[tracing::instrument(skip(self))]
+    pub fn get_ignored(&self) -> Result<Vec<FullRepoId>, anyhow::Error> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare_cached("SELECT owner, name FROM ignored_repositories ORDER BY owner, name;")?;
+        let repos = stmt
+            .query_map([], |x| {
+                Ok(FullRepoId {
+                    owner: x.get(0)?,
+                    name: x.get(1)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(repos)
+    }
+
+    /// Records when a repository was last backed up.
+    #[tracing::instrument(skip(self))]
+    pub fn set_backed_up(
+        &self,
+        repo_id: &(impl IsRepositoryId + fmt::Debug),
+        backed_up_at: DateTime<Utc>,
+    ) -> Result<(), anyhow::Error> {
+        self.conn().execute(
+            "INSERT INTO backups (owner, name, backed_up_at) VALUES (?, ?, ?)
+                ON CONFLICT (owner, name) DO UPDATE SET backed_up_at = excluded.backed_up_at
+            ;",
+            params![repo_id.owner(), repo_id.name(), backed_up_at],
+        )?;
+        Ok(())
+    }
+
+    /// Gets when repositories were last backed up, by `:owner/:name`.
+    #[tracing::instrument(skip(self))]
+    pub fn get_backed_up_at(&self) -> Result<HashMap<String, DateTime<Utc>>, anyhow::Error> {
+        let conn = self.conn();
+        let mut stmt =
+            conn.prepare_cached("SELECT owner || '/' || name, backed_up_at FROM backups;")?;
+        let times = stmt
+            .query_map([], |x| Ok((x.get(0)?, x.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        Ok(times)
+    }
+
+    /// Records a mutation.
+    #[tracing::instrument(skip(self))]
+    pub fn put_mutation(
+        &mut self,
+        kind: MutationKind,
+        target: &str,
+        data: &str,
+    ) -> Result<(), anyhow::Error> {
+        self.conn().execute(
+            "INSERT INTO mutations (kind, target, data, created_at) VALUES (?, ?, ?, ?);",
+            params![kind, target, data, Utc::now()],
+        )?;
+        Ok(())
+    }
+
+    /// Gets the most recent mutation that has not been undone.
+    #[tracing::instrument(skip(self))]
+    pub fn get_last_mutation(&self) -> Result<Option<Mutation>, anyhow::Error> {
+        let mutation = self
+            .conn()
+            .query_row(
+                "SELECT mid, kind, target, data, created_at
+                    FROM mutations
+                    WHERE undone = FALSE
+                    ORDER BY mid DESC
+                    LIMIT 1
+                ;",
+                [],
+                |x| {
+                    let m = Mutation {
+                        id: x.get(0)?,
+                        kind: x.get(1)?,
+                        target: x.get(2)?,
+                        data: x.get(3)?,
+                        created_at: x.get(4)?,
+                    };
+                    Ok(m)
+                },
+            )
+            .optional()?;
+        Ok(mutation)
+    }
+
+    /// Marks a mutation as undone.
+    #[tracing::instrument(skip(self))]
+    pub fn set_mutation_undone(&mut self, id: i64) -> Result<(), anyhow::Error> {
+        self.conn()
+            .execute("UPDATE mutations SET undone = TRUE WHERE mid = ?;", [id])?;
+        Ok(())
+    }
 }
 
-/// Migrates database.
+/// Adds or removes repository from a flag table.
+fn set_flag(
+    db: &mut Database,
+    table: &str,
+    repo_id: &impl IsRepositoryId,
+    value: bool,
+) -> Result<(), anyhow::Error> {
+    let sql = if value {
+        format!("INSERT INTO {table} (owner, name) VALUES (?, ?);")
+    } else {
+        format!("DELETE FROM {table} WHERE owner = ? AND name = ?;")
+    };
+    db.conn()
+        .execute(&sql, params![repo_id.owner(), repo_id.name()])?;
+    Ok(())
+}
+
+/// Cache of GitHub responses by URL, for conditional requests.
+///
+/// Has its own connection to the database, so it can be shared by clones of the GitHub client.
+#[derive(Clone)]
+pub struct HttpCache(Arc<Mutex<rusqlite::Connection>>);
+
+/// Response body with the headers needed to reuse it.
+#[derive(PartialEq, Clone, Debug)]
+pub struct CachedResponse {
+    pub etag: String,
+    /// Pagination links.
+    pub link: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl HttpCache {
+    /// Opens the cache in a migrated database.
+    pub fn new(path: &Path) -> Result<Self, anyhow::Error> {
+        let conn = rusqlite::Connection::open(path)?;
+        Ok(Self(Arc::new(Mutex::new(conn))))
+    }
+
+    pub fn get(&self, url: &str) -> Result<Option<CachedResponse>, anyhow::Error> {
+        let conn = self
+            .0
+            .lock()
+            .expect("http cache lock should not be poisoned");
+        let response = conn
+            .query_row(
+                "SELECT etag, link, body FROM http_cache WHERE url = ?;",
+                [url],
+                |x| {
+                    Ok(CachedResponse {
+                        etag: x.get(0)?,
+                        link: x.get(1)?,
+                        body: x.get(2)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(response)
+    }
+
+    pub fn put(&self, url: &str, response: &CachedResponse) -> Result<(), anyhow::Error> {
+        let conn = self
+            .0
+            .lock()
+            .expect("http cache lock should not be poisoned");
+        conn.execute(
+            "INSERT INTO http_cache (url, etag, link, body) VALUES (?, ?, ?, ?);",
+            params![url, response.etag, response.link, response.body],
+        )?;
+        Ok(())
+    }
+}
+
+/// Applies migrations newer than the schema version of the database.
 fn migrate(db: &Database) -> Result<(), anyhow::Error> {
-    db.0.execute_batch(MIGRATIONS)?;
+    migrate_to(&db.conn(), MIGRATIONS.len())
+}
+
+/// Applies migrations up to a schema version, each in its own transaction.
+fn migrate_to(conn: &rusqlite::Connection, version: usize) -> Result<(), anyhow::Error> {
+    let current: usize = conn.pragma_query_value(None, "user_version", |x| x.get(0))?;
+    if current > MIGRATIONS.len() {
+        bail!(
+            "Database schema version {} is newer than the supported version {}, upgrade shub.",
+            current,
+            MIGRATIONS.len()
+        )
+    }
+    for (idx, migration) in MIGRATIONS.iter().enumerate().take(version).skip(current) {
+        info!("migrating database to version {}", idx + 1);
+        let tx = conn.unchecked_transaction()?;
+        match migration {
+            Migration::Sql(sql) => tx.execute_batch(sql)?,
+            Migration::AddColumn {
+                table,
+                column,
+                definition,
+            } => add_column(&tx, table, column, definition)?,
+        }
+        tx.pragma_update(None, "user_version", idx + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+fn star_from_row(x: &rusqlite::Row) -> Result<Star, rusqlite::Error> {
+    let topics: Option<String> = x.get(5)?;
+    let s = Star {
+        owner: x.get(0)?,
+        name: x.get(1)?,
+        description: x.get(2)?,
+        language: x.get(3)?,
+        starred_at: x.get(4)?,
+        topics: topics
+            .map(|x| x.split_whitespace().map(ToOwned::to_owned).collect())
+            .unwrap_or_default(),
+    };
+    Ok(s)
+}
+
+/// Turns words of a search query into an FTS5 query matching all of them as prefixes.
+fn fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|x| format!("\"{}\"*", x.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Adds column to a table if it doesn't have it yet.
+fn add_column(
+    conn: &rusqlite::Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), anyhow::Error> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT EXISTS (SELECT 1 FROM pragma_table_info('{table}') WHERE name = ?);"),
+        [column],
+        |x| x.get(0),
+    )?;
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {table} ADD COLUMN {column} {definition};"),
+            [],
+        )?;
+    }
     Ok(())
 }
 
 // todo(kfj): better name
 fn get_dashboard_repositories(
-    db: &Database,
+    conn: &rusqlite::Connection,
     owner: &str,
 ) -> Result<Vec<Repository>, anyhow::Error> {
-    let mut stmt = db.0.prepare_cached(
-        "SELECT owner, name, build_status
-            FROM repositories
+    let mut stmt = conn.prepare_cached(
+        "SELECT
+                owner,
+                name,
+                build_status,
+                pushed_at,
+                open_pull_requests,
+                unmerged_branches,
+                build_status_sha
+            FROM repositories r
             WHERE
                 owner = ? AND
                 a_fork = FALSE AND
-                archived = FALSE
+                archived = FALSE AND
+                NOT EXISTS (
+                    SELECT 1 FROM ignored_repositories i
+                        WHERE i.owner = r.owner AND i.name = r.name
+                )
+            ORDER BY
+                EXISTS (
+                    SELECT 1 FROM pinned_repositories p
+                        WHERE p.owner = r.owner AND p.name = r.name
+                ) DESC,
+                rid
         ;",
     )?;
     let repositories = stmt
@@ -97,12 +727,20 @@ fn get_dashboard_repositories(
             let owner = x.get(0)?;
             let name = x.get(1)?;
             let build_status = x.get(2)?;
+            let pushed_at = x.get(3)?;
+            let open_pull_requests = x.get(4)?;
+            let unmerged_branches = x.get(5)?;
+            let build_status_sha = x.get(6)?;
             let r = Repository {
                 name,
                 owner,
                 a_fork: false,
                 archived: false,
                 build_status,
+                pushed_at,
+                open_pull_requests,
+                unmerged_branches,
+                build_status_sha,
             };
             Ok(r)
         })?
@@ -110,17 +748,123 @@ fn get_dashboard_repositories(
     Ok(repositories)
 }
 
+fn set_build_statuses(
+    conn: &mut rusqlite::Connection,
+    build_statuses: &[(
+        impl IsRepositoryId,
+        Option<BuildStatus>,
+        Option<String>, /* sha */
+    )],
+) -> Result<(), anyhow::Error> {
+    let tx = conn.transaction()?;
+    let mut stmt = tx.prepare_cached(
+        "UPDATE repositories
+            SET build_status = ?, build_status_sha = ?
+            WHERE
+                owner = ? AND
+                name = ?
+        ;",
+    )?;
+    let mut history_stmt = tx.prepare_cached(
+        "INSERT INTO build_status_history (owner, name, sha, status, recorded_at)
+            SELECT ?1, ?2, ?3, ?4, ?5
+            WHERE NOT EXISTS (
+                SELECT 1 FROM (
+                    SELECT sha, status FROM build_status_history
+                        WHERE owner = ?1 AND name = ?2
+                        ORDER BY hid DESC
+                        LIMIT 1
+                )
+                WHERE sha = ?3 AND status = ?4
+            )
+        ;",
+    )?;
+    let now = Utc::now();
+    for (id, status, sha) in build_statuses {
+        stmt.execute(params![status, sha, id.owner(), id.name()])?;
+        if let (Some(status), Some(sha)) = (status, sha) {
+            history_stmt.execute(params![id.owner(), id.name(), sha, status, now])?;
+        }
+    }
+    drop(stmt);
+    drop(history_stmt);
+    tx.commit()?;
+    Ok(())
+}
+
+fn set_repository_activities(
+    conn: &mut rusqlite::Connection,
+    activities: &[(
+        impl IsRepositoryId,
+        u64, /* open pull requests */
+        u64, /* unmerged branches */
+    )],
+) -> Result<(), anyhow::Error> {
+    let tx = conn.transaction()?;
+    let mut stmt = tx.prepare_cached(
+        "UPDATE repositories
+            SET open_pull_requests = ?, unmerged_branches = ?
+            WHERE
+                owner = ? AND
+                name = ?
+        ;",
+    )?;
+    for (id, open_pull_requests, unmerged_branches) in activities {
+        stmt.execute(params![
+            open_pull_requests,
+            unmerged_branches,
+            id.owner(),
+            id.name()
+        ])?;
+    }
+    drop(stmt);
+    tx.commit()?;
+    Ok(())
+}
+
+fn get_success_rates(
+    conn: &rusqlite::Connection,
+    owner: &str,
+    builds: usize,
+) -> Result<HashMap<String, f64>, anyhow::Error> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT name, AVG(status = 'success')
+            FROM (
+                SELECT
+                    name,
+                    status,
+                    ROW_NUMBER() OVER (PARTITION BY name ORDER BY hid DESC) AS n
+                FROM build_status_history
+                WHERE owner = ? AND status IN ('success', 'failure')
+            )
+            WHERE n <= ?
+            GROUP BY name
+        ;",
+    )?;
+    let rates = stmt
+        .query_map(params![owner, builds], |x| Ok((x.get(0)?, x.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    Ok(rates)
+}
+
 /// Puts repositories into database.
 ///
 /// On conflict, will replace the stored repository.
-fn put_repositories(db: &mut Database, repositories: &[Repository]) -> Result<(), anyhow::Error> {
-    let tx = db.0.transaction()?;
+fn put_repositories(
+    conn: &mut rusqlite::Connection,
+    repositories: &[Repository],
+) -> Result<(), anyhow::Error> {
+    let tx = conn.transaction()?;
     for Repository {
         name,
         owner,
         a_fork,
         archived: acrhived,
         build_status,
+        build_status_sha,
+        pushed_at,
+        open_pull_requests,
+        unmerged_branches,
     } in repositories
     {
         tx.execute(
@@ -129,10 +873,24 @@ fn put_repositories(db: &mut Database, repositories: &[Repository]) -> Result<()
                 owner,
                 a_fork,
                 archived,
-                build_status
-            ) VALUES (?, ?, ?, ?, ?)
+                build_status,
+                pushed_at,
+                open_pull_requests,
+                unmerged_branches,
+                build_status_sha
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             ;",
-            params![name, owner, a_fork, acrhived, build_status],
+            params![
+                name,
+                owner,
+                a_fork,
+                acrhived,
+                build_status,
+                pushed_at,
+                open_pull_requests,
+                unmerged_branches,
+                build_status_sha
+            ],
         )?;
     }
     tx.commit()?;
@@ -155,6 +913,20 @@ impl FromSql for BuildStatus {
     }
 }
 
+impl ToSql for MutationKind {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let s = self.to_string();
+        Ok(ToSqlOutput::Owned(Value::Text(s)))
+    }
+}
+
+impl FromSql for MutationKind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let s = value.as_str()?;
+        s.parse().map_err(|x| FromSqlError::Other(Box::new(x)))
+    }
+}
+
 // end: to/from sql conversions ------------------------------
 
 #[cfg(test)]
@@ -164,13 +936,36 @@ mod test {
 
     fn connect() -> Database {
         let conn = Connection::open_in_memory().unwrap();
-        Database(conn)
+        Database(Arc::new(Mutex::new(conn)))
     }
 
     fn migrate_(db: &Database) {
         migrate(db).unwrap()
     }
 
+    #[test]
+    fn test_http_cache() {
+        let db = connect();
+        migrate_(&db);
+        let cache = HttpCache(db.0);
+
+        assert_eq!(cache.get("https://api.github.com/user").unwrap(), None);
+
+        let old = CachedResponse {
+            etag: "W/\"1\"".to_owned(),
+            link: None,
+            body: b"{}".to_vec(),
+        };
+        cache.put("https://api.github.com/user", &old).unwrap();
+        let new = CachedResponse {
+            etag: "W/\"2\"".to_owned(),
+            link: Some("<https://api.github.com/user?page=2>; rel=\"next\"".to_owned()),
+            body: b"[]".to_vec(),
+        };
+        cache.put("https://api.github.com/user", &new).unwrap();
+        assert_eq!(cache.get("https://api.github.com/user").unwrap(), Some(new));
+    }
+
     #[test]
     fn test_migration_safe_to_run_multiple_time() {
         let db = connect();
@@ -179,6 +974,58 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_migrate_from_each_version() {
+        let schema = |db: &Database| {
+            let conn = db.conn();
+            let mut stmt = conn
+                .prepare("SELECT name, sql FROM sqlite_master ORDER BY name;")
+                .unwrap();
+            let rows = stmt
+                .query_map([], |x| Ok((x.get(0)?, x.get(1)?)))
+                .unwrap()
+                .collect::<Result<Vec<(String, Option<String>)>, _>>()
+                .unwrap();
+            rows
+        };
+        let version = |db: &Database| -> usize {
+            db.conn()
+                .pragma_query_value(None, "user_version", |x| x.get(0))
+                .unwrap()
+        };
+
+        let latest = connect();
+        migrate_(&latest);
+        assert_eq!(version(&latest), MIGRATIONS.len());
+
+        for from in 0..=MIGRATIONS.len() {
+            let db = connect();
+            migrate_to(&db.conn(), from).unwrap();
+            assert_eq!(version(&db), from);
+            migrate_(&db);
+            assert_eq!(version(&db), MIGRATIONS.len(), "from version {from}");
+            assert_eq!(schema(&db), schema(&latest), "from version {from}");
+        }
+
+        // databases created before the schema was versioned
+        for from in 0..=UNVERSIONED_MIGRATIONS {
+            let db = connect();
+            migrate_to(&db.conn(), from).unwrap();
+            db.conn().pragma_update(None, "user_version", 0).unwrap();
+            migrate_(&db);
+            assert_eq!(schema(&db), schema(&latest), "from unversioned {from}");
+        }
+    }
+
+    #[test]
+    fn test_migrate_newer_version() {
+        let db = connect();
+        db.conn()
+            .pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+        assert!(migrate(&db).is_err());
+    }
+
     #[test]
     fn test_get_dashboard_repositories() {
         let mut db = connect();
@@ -191,11 +1038,15 @@ mod test {
                 a_fork: false,
                 archived: false,
                 build_status: None,
+                pushed_at: None,
+                open_pull_requests: None,
+                unmerged_branches: None,
+                build_status_sha: None,
             }];
-            put_repositories(&mut db, &rs).unwrap();
+            put_repositories(&mut db.conn(), &rs).unwrap();
         };
 
-        let rs = get_dashboard_repositories(&db, "Hello").unwrap();
+        let rs = get_dashboard_repositories(&db.conn(), "Hello").unwrap();
         assert_eq!(
             rs,
             [Repository {
@@ -204,7 +1055,225 @@ mod test {
                 a_fork: false,
                 archived: false,
                 build_status: None,
+                pushed_at: None,
+                open_pull_requests: None,
+                unmerged_branches: None,
+                build_status_sha: None,
             }]
         );
+
+        set_repository_activities(&mut db.conn(), &[(rs[0].clone(), 3, 1)]).unwrap();
+        let rs = get_dashboard_repositories(&db.conn(), "Hello").unwrap();
+        assert_eq!(rs[0].open_pull_requests, Some(3));
+        assert_eq!(rs[0].unmerged_branches, Some(1));
+
+        set_build_statuses(
+            &mut db.conn(),
+            &[(
+                rs[0].clone(),
+                Some(BuildStatus::Success),
+                Some("c0ffee".to_owned()),
+            )],
+        )
+        .unwrap();
+        let rs = get_dashboard_repositories(&db.conn(), "Hello").unwrap();
+        assert_eq!(rs[0].build_status, Some(BuildStatus::Success));
+        assert_eq!(rs[0].build_status_sha.as_deref(), Some("c0ffee"));
+    }
+
+    #[test]
+    fn test_put_repositories_with_build_status_sha() {
+        let db = connect();
+        migrate_(&db);
+
+        let r = Repository {
+            name: "World".to_owned(),
+            owner: "Hello".to_owned(),
+            a_fork: false,
+            archived: false,
+            build_status: Some(BuildStatus::Failure),
+            pushed_at: None,
+            open_pull_requests: None,
+            unmerged_branches: None,
+            build_status_sha: Some("deadbeef".to_owned()),
+        };
+        put_repositories(&mut db.conn(), &[r.clone()]).unwrap();
+
+        let rs = get_dashboard_repositories(&db.conn(), "Hello").unwrap();
+        assert_eq!(rs, [r]);
+    }
+
+    #[test]
+    fn test_build_status_history() {
+        let db = connect();
+        migrate_(&db);
+
+        let repo = |name: &str| FullRepoId {
+            owner: "Hello".to_owned(),
+            name: name.to_owned(),
+        };
+        let status =
+            |name: &str, status, sha: &str| (repo(name), Some(status), Some(sha.to_owned()));
+        for xs in [
+            [
+                status("A", BuildStatus::InProgress, "a1"),
+                status("B", BuildStatus::Failure, "b1"),
+            ],
+            [
+                status("A", BuildStatus::InProgress, "a1"),
+                status("B", BuildStatus::Success, "b2"),
+            ],
+            [
+                status("A", BuildStatus::Success, "a1"),
+                status("B", BuildStatus::Success, "b2"),
+            ],
+        ] {
+            set_build_statuses(&mut db.conn(), &xs).unwrap();
+        }
+
+        let history = db.get_build_status_history(&repo("A"), 10).unwrap();
+        let history: Vec<_> = history.into_iter().map(|x| (x.sha, x.status)).collect();
+        assert_eq!(
+            history,
+            [
+                ("a1".to_owned(), BuildStatus::Success),
+                ("a1".to_owned(), BuildStatus::InProgress)
+            ]
+        );
+        assert_eq!(db.get_build_status_history(&repo("B"), 1).unwrap().len(), 1);
+
+        let rates = get_success_rates(&db.conn(), "Hello", 10).unwrap();
+        assert_eq!(rates.get("A"), Some(&1.0));
+        assert_eq!(rates.get("B"), Some(&0.5));
+        let rates = get_success_rates(&db.conn(), "Hello", 1).unwrap();
+        assert_eq!(rates.get("B"), Some(&1.0));
+    }
+
+    #[test]
+    fn test_backups() {
+        let db = connect();
+        migrate_(&db);
+
+        let repo = FullRepoId {
+            owner: "Hello".to_owned(),
+            name: "World".to_owned(),
+        };
+        let earlier = "2022-08-01T00:00:00Z".parse().unwrap();
+        let later = "2022-08-02T00:00:00Z".parse().unwrap();
+        db.set_backed_up(&repo, earlier).unwrap();
+        db.set_backed_up(&repo, later).unwrap();
+
+        let times = db.get_backed_up_at().unwrap();
+        assert_eq!(times.len(), 1);
+        assert_eq!(times.get("Hello/World"), Some(&later));
+    }
+
+    #[test]
+    fn test_pinned_and_ignored_repositories() {
+        let mut db = connect();
+        migrate_(&db);
+
+        let repo = |name: &str| Repository {
+            name: name.to_owned(),
+            owner: "Hello".to_owned(),
+            a_fork: false,
+            archived: false,
+            build_status: None,
+            pushed_at: None,
+            open_pull_requests: None,
+            unmerged_branches: None,
+            build_status_sha: None,
+        };
+        put_repositories(&mut db.conn(), &[repo("A"), repo("B"), repo("C")]).unwrap();
+
+        db.set_pinned(&repo("C"), true).unwrap();
+        db.set_ignored(&repo("B"), true).unwrap();
+        let rs = get_dashboard_repositories(&db.conn(), "Hello").unwrap();
+        assert_eq!(rs, [repo("C"), repo("A")]);
+        let ignored = db.get_ignored().unwrap();
+        assert_eq!(ignored, ["Hello/B".parse::<FullRepoId>().unwrap()]);
+
+        db.set_pinned(&repo("C"), false).unwrap();
+        db.set_ignored(&repo("B"), false).unwrap();
+        let rs = get_dashboard_repositories(&db.conn(), "Hello").unwrap();
+        assert_eq!(rs, [repo("A"), repo("B"), repo("C")]);
+        assert!(db.get_ignored().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_last_mutation() {
+        let mut db = connect();
+        migrate_(&db);
+
+        assert_eq!(db.get_last_mutation().unwrap(), None);
+
+        db.put_mutation(MutationKind::UpdateSettings, "Hello/World", "{}")
+            .unwrap();
+        db.put_mutation(MutationKind::RenameBranch, "Hello/World", "{}")
+            .unwrap();
+        db.put_mutation(MutationKind::NotUndoable, "Hello/World", "{}")
+            .unwrap();
+
+        let m = db.get_last_mutation().unwrap().unwrap();
+        assert_eq!(m.kind, MutationKind::NotUndoable);
+
+        db.set_mutation_undone(m.id).unwrap();
+        let m = db.get_last_mutation().unwrap().unwrap();
+        assert_eq!(m.kind, MutationKind::RenameBranch);
+
+        db.set_mutation_undone(m.id).unwrap();
+        let m = db.get_last_mutation().unwrap().unwrap();
+        assert_eq!(m.kind, MutationKind::UpdateSettings);
+        assert_eq!(m.target, "Hello/World");
+    }
+
+    #[test]
+    fn test_stars() {
+        let mut db = connect();
+        migrate_(&db);
+
+        let star = |owner: &str, name: &str| Star {
+            owner: owner.to_owned(),
+            name: name.to_owned(),
+            description: None,
+            language: Some("Rust".to_owned()),
+            starred_at: None,
+            topics: vec!["cli".to_owned()],
+        };
+        db.put_stars(&[star("b", "B"), star("a", "A")]).unwrap();
+        assert_eq!(db.get_stars().unwrap(), [star("a", "A"), star("b", "B")]);
+
+        // replaces previously stored stars
+        db.put_stars(&[star("c", "C"), star("d", "D")]).unwrap();
+        assert_eq!(db.get_stars().unwrap(), [star("c", "C"), star("d", "D")]);
+
+        db.delete_star(&star("c", "C")).unwrap();
+        assert_eq!(db.get_stars().unwrap(), [star("d", "D")]);
+    }
+
+    #[test]
+    fn test_search_stars() {
+        let mut db = connect();
+        migrate_(&db);
+
+        let star = |name: &str, description: &str, topics: &[&str]| Star {
+            owner: "kafji".to_owned(),
+            name: name.to_owned(),
+            description: Some(description.to_owned()),
+            language: Some("Rust".to_owned()),
+            starred_at: None,
+            topics: topics.iter().map(|x| x.to_string()).collect(),
+        };
+        let shub = star("shub", "Workspace hub CLI", &["github", "cli"]);
+        let sekret = star("sekret", "Secret wrapper", &["security"]);
+        db.put_stars(&[shub.clone(), sekret.clone()]).unwrap();
+
+        assert_eq!(db.search_stars("work").unwrap(), [shub.clone()]);
+        assert_eq!(db.search_stars("rust secu").unwrap(), [sekret.clone()]);
+        assert!(db.search_stars("cli \"quoted").unwrap().is_empty());
+        assert!(db.search_stars("  ").unwrap().is_empty());
+
+        db.delete_star(&shub).unwrap();
+        assert!(db.search_stars("github").unwrap().is_empty());
     }
 }
@@ -1,15 +1,30 @@
-use crate::{github_models::*, OwnedRepository, StarredRepository};
+use crate::{
+    github_models::*,
+    kceh::humanize,
+    token::api_host,
+    types::{BuildStatus, Star},
+    OwnedRepository, StarredRepository,
+};
 use anyhow::anyhow;
 use bstr::BStr;
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Local, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use console::{style, Term};
 use octocrab::models::Repository;
+use serde::Serialize;
 use std::{
     borrow::Cow,
+    env,
     fmt::{self, Display, Formatter},
     io::Write,
     str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
 };
 use tabwriter::TabWriter;
+use termimad::{ansi, MadSkin};
 use unicode_segmentation::UnicodeSegmentation;
 
 macro_rules! write_col {
@@ -47,6 +62,77 @@ const COMMIT_MSG_LEN: u8 = 40;
 const LANG_NAME_LEN: u8 = 10;
 const PUSHED_AT_LEN: u8 = 12;
 
+/// Whether emoji shortcodes are replaced with their emojis.
+static EMOJI_SHORTCODES: AtomicBool = AtomicBool::new(true);
+
+/// Enables or disables replacing emoji shortcodes, it's enabled by default.
+pub fn set_emoji_shortcodes(enabled: bool) {
+    EMOJI_SHORTCODES.store(enabled, Ordering::Relaxed);
+}
+
+/// Replaces GitHub emoji shortcodes, e.g. `:rocket:`, with their emojis.
+///
+/// Unknown shortcodes and shortcodes glued to a word or a path, e.g. `a::b::c`, are kept as is.
+pub fn emojify(text: &str) -> Cow<'_, str> {
+    if !EMOJI_SHORTCODES.load(Ordering::Relaxed) || !text.contains(':') {
+        return text.into();
+    }
+
+    let is_shortcode_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-');
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut prev = None;
+    while let Some(start) = rest.find(':') {
+        let glued = rest[..start]
+            .chars()
+            .last()
+            .or(prev)
+            .map(|c| c.is_ascii_alphanumeric() || c == ':')
+            .unwrap_or_default();
+        let after = &rest[start + 1..];
+        let end = after.find(|c| !is_shortcode_char(c));
+        let emoji = match end {
+            Some(end) if !glued && end > 0 && after[end..].starts_with(':') => {
+                let followed = after[end + 1..]
+                    .chars()
+                    .next()
+                    .map(|c| c.is_ascii_alphanumeric())
+                    .unwrap_or_default();
+                emojis::get_by_shortcode(&after[..end])
+                    .filter(|_| !followed)
+                    .map(|x| (x, end))
+            }
+            _ => None,
+        };
+        match emoji {
+            Some((emoji, end)) => {
+                result.push_str(&rest[..start]);
+                result.push_str(emoji.as_str());
+                rest = &after[end + 1..];
+                prev = None;
+            }
+            None => {
+                result.push_str(&rest[..=start]);
+                rest = after;
+                prev = Some(':');
+            }
+        }
+    }
+    result.push_str(rest);
+    result.into()
+}
+
+#[cfg(test)]
+#[test]
+fn test_emojify() {
+    assert_eq!(emojify("Launch :rocket:"), "Launch 🚀");
+    assert_eq!(emojify(":tada::rocket: done"), "🎉🚀 done");
+    assert_eq!(emojify(":not_an_emoji: here"), ":not_an_emoji: here");
+    assert_eq!(emojify("std::fmt::Display"), "std::fmt::Display");
+    assert_eq!(emojify("at 10:30:00"), "at 10:30:00");
+}
+
 pub fn ellipsize(text: &str, threshold: usize) -> Cow<'_, str> {
     debug_assert!(threshold > 2);
     if text.len() <= threshold {
@@ -105,6 +191,64 @@ where
     }
 }
 
+/// Absolute time in the configured timezone and format.
+pub trait AbsoluteTime {
+    fn at(&self) -> At;
+}
+
+impl AbsoluteTime for DateTime<Utc> {
+    fn at(&self) -> At {
+        At(*self)
+    }
+}
+
+/// Default format of absolute timestamps.
+const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Checks timezone and time format in `SHUB_TIMEZONE` and `SHUB_TIME_FORMAT`.
+pub fn check_time_settings() -> Result<(), anyhow::Error> {
+    time_zone()?;
+    let format = time_format();
+    if StrftimeItems::new(&format).any(|x| matches!(x, Item::Error)) {
+        return Err(anyhow!("Invalid time format `{}`.", format));
+    }
+    Ok(())
+}
+
+/// Timezone in `SHUB_TIMEZONE`, `None` means the local timezone.
+fn time_zone() -> Result<Option<Tz>, anyhow::Error> {
+    match env::var("SHUB_TIMEZONE") {
+        Ok(x) => x
+            .parse()
+            .map(Some)
+            .map_err(|_| anyhow!("Unknown timezone `{}`.", x)),
+        Err(_) => Ok(None),
+    }
+}
+
+fn time_format() -> String {
+    env::var("SHUB_TIME_FORMAT").unwrap_or_else(|_| DEFAULT_TIME_FORMAT.to_owned())
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct At(DateTime<Utc>);
+
+impl Display for At {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // settings are checked on start, fall back to the defaults if they're invalid anyway
+        let format = time_format();
+        let format = if StrftimeItems::new(&format).any(|x| matches!(x, Item::Error)) {
+            DEFAULT_TIME_FORMAT
+        } else {
+            &format
+        };
+        match time_zone().ok().flatten() {
+            Some(tz) => write!(f, "{}", self.0.with_timezone(&tz).format(format)),
+            None => write!(f, "{}", self.0.with_timezone(&Local).format(format)),
+        }
+    }
+}
+
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub struct Since(chrono::Duration);
 
@@ -194,18 +338,18 @@ impl Display for RepositoryAttrs {
 }
 
 #[derive(Debug)]
-struct RepositoryDescription<'a>(&'a str, usize);
+struct RepositoryDescription<'a>(Cow<'a, str>, usize);
 
 impl<'a> RepositoryDescription<'a> {
     fn from_repository(repository: &'a Repository, length: usize) -> Self {
         let desc = repository.description.as_deref().unwrap_or_default();
-        Self(desc, length)
+        Self(emojify(desc), length)
     }
 }
 
 impl Display for RepositoryDescription<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write_col!(f, self.1, self.0)?;
+        write_col!(f, self.1, &self.0)?;
         Ok(())
     }
 }
@@ -295,27 +439,73 @@ impl Display for StarredRepository {
     }
 }
 
-/// Transform `snake_case` to `Statement`.
-fn snake_case_to_statement(text: &str) -> String {
-    text.grapheme_indices(true)
-        .map(|(i, c)| -> Cow<str> {
-            if i == 0 {
-                c.to_uppercase().into()
-            } else if c == "_" {
-                " ".into()
-            } else {
-                c.into()
-            }
-        })
-        .collect()
+impl Display for Star {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_col!(f, 15, &self.name)?;
+
+        let desc = emojify(self.description.as_deref().unwrap_or_default());
+        write_col!(, f, 60, &desc)?;
+
+        write_col!(, f, OWNER_NAME_LEN, &self.owner)?;
+
+        let starred = self
+            .starred_at
+            .as_ref()
+            .map(|x| x.since().to_string())
+            .unwrap_or_default();
+        write_col!(, f, PUSHED_AT_LEN, &starred)?;
+
+        write_col!(, f, LANG_NAME_LEN, self.language.as_deref().unwrap_or_default())?;
+
+        Ok(())
+    }
 }
 
-#[cfg(test)]
-#[test]
-fn test_snake_case_to_statement() {
-    let input = "hello_world";
-    let output = snake_case_to_statement(input);
-    assert_eq!("Hello world", output);
+/// Width to wrap text at when the output is not a terminal.
+const DEFAULT_WIDTH: usize = 80;
+
+/// Markdown text rendered for the terminal.
+#[derive(Debug)]
+pub struct Markdown<'a> {
+    text: &'a str,
+    width: usize,
+}
+
+impl<'a> Markdown<'a> {
+    /// Creates markdown wrapped at the terminal width.
+    pub fn new(text: &'a str) -> Self {
+        Self::with_width(text, terminal_width())
+    }
+
+    pub fn with_width(text: &'a str, width: usize) -> Self {
+        Self { text, width }
+    }
+}
+
+impl Display for Markdown<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let skin = markdown_skin();
+        let text = emojify(self.text);
+        write!(f, "{}", skin.text(&text, Some(self.width)))
+    }
+}
+
+fn markdown_skin() -> MadSkin {
+    let mut skin = MadSkin::default();
+    skin.set_headers_fg(ansi(178));
+    skin.bold.set_fg(ansi(255));
+    skin.inline_code.set_fg(ansi(180));
+    skin.code_block.set_fg(ansi(180));
+    skin.code_block.set_bg(ansi(236));
+    skin
+}
+
+/// Width of the terminal, or the default width when stdout is not a terminal.
+fn terminal_width() -> usize {
+    Term::stdout()
+        .size_checked()
+        .map(|(_, cols)| cols as _)
+        .unwrap_or(DEFAULT_WIDTH)
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -355,7 +545,7 @@ impl Display for CommitInfo<'_> {
         } else if let Some(author_email) = self.author_email {
             write!(f, "{author_email} - ")?;
         }
-        writeln!(f, "{}", self.timestamp.since())?;
+        writeln!(f, "{} ({})", self.timestamp.since(), self.timestamp.at())?;
         writeln!(f, "{}", &self.hash[..8])?;
         writeln!(
             f,
@@ -412,16 +602,69 @@ impl<'a> BuildInfo<'a> {
 
 impl Display for BuildInfo<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let status = humanize(self.status);
+        let status = match build_status_from_check_run_status(self.status) {
+            Some(x) => BuildStatusBadge::with_text(&x, &status).to_string(),
+            None => status,
+        };
         write!(
             f,
             "{}: {} - {}",
-            self.name,
-            snake_case_to_statement(self.status),
+            humanize(self.name),
+            status,
             self.timestamp.since()
         )
     }
 }
 
+/// Build status of a check run status or conclusion, `None` for the neutral ones.
+fn build_status_from_check_run_status(status: &str) -> Option<BuildStatus> {
+    match status {
+        "success" => Some(BuildStatus::Success),
+        "failure" | "timed_out" | "cancelled" | "action_required" | "startup_failure" => {
+            Some(BuildStatus::Failure)
+        }
+        "queued" | "in_progress" | "waiting" | "pending" | "requested" => {
+            Some(BuildStatus::InProgress)
+        }
+        _ => None,
+    }
+}
+
+/// Build status with its symbol, colored when colors are enabled.
+#[derive(Debug)]
+pub struct BuildStatusBadge<'a> {
+    status: &'a BuildStatus,
+    text: Cow<'a, str>,
+}
+
+impl<'a> BuildStatusBadge<'a> {
+    pub fn new(status: &'a BuildStatus) -> Self {
+        Self {
+            status,
+            text: status.to_string().into(),
+        }
+    }
+
+    fn with_text(status: &'a BuildStatus, text: &'a str) -> Self {
+        Self {
+            status,
+            text: text.into(),
+        }
+    }
+}
+
+impl Display for BuildStatusBadge<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let badge = match self.status {
+            BuildStatus::Success => style(format!("✓ {}", self.text)).green(),
+            BuildStatus::Failure => style(format!("✗ {}", self.text)).red(),
+            BuildStatus::InProgress => style(format!("● {}", self.text)).yellow(),
+        };
+        write!(f, "{}", badge)
+    }
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub struct TaskInfos<'a> {
     infos: Vec<TaskInfo<'a>>,
@@ -432,6 +675,10 @@ impl<'a> TaskInfos<'a> {
         let infos = issues.iter().map(TaskInfo::from_github_issue).collect();
         Self { infos }
     }
+
+    pub fn infos(&self) -> &[TaskInfo<'a>] {
+        &self.infos
+    }
 }
 
 impl Display for TaskInfos<'_> {
@@ -451,25 +698,20 @@ impl Display for TaskInfos<'_> {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
-struct TaskInfo<'a> {
-    title: &'a str,
+#[derive(Serialize, PartialEq, Clone, Debug)]
+pub struct TaskInfo<'a> {
+    title: Cow<'a, str>,
     state: TaskState,
-    repository: &'a str,
+    repository: Cow<'a, str>,
     task_type: TaskType,
 }
 
 impl<'a> TaskInfo<'a> {
     fn from_github_issue(issue: &'a GhIssue) -> Self {
-        let title = &issue.inner.title;
+        let title = emojify(&issue.inner.title);
         let state = issue.inner.state.parse().unwrap();
-        let repository = &issue.repository.full_name;
-        let task_type = issue
-            .inner
-            .pull_request
-            .as_ref()
-            .map(|_| TaskType::PullRequest)
-            .unwrap_or(TaskType::Issue);
+        let repository = task_repository(issue);
+        let task_type = task_type(issue);
         Self {
             title,
             state,
@@ -479,7 +721,73 @@ impl<'a> TaskInfo<'a> {
     }
 }
 
-#[derive(PartialEq, Copy, Clone, Debug)]
+fn task_repository(issue: &GhIssue) -> Cow<'_, str> {
+    match &issue.repository {
+        Some(x) => x.full_name.as_str().into(),
+        None => {
+            // search results only have the API URL of the repository
+            let url = issue.inner.repository_url.path();
+            url.trim_start_matches("/repos/").to_owned().into()
+        }
+    }
+}
+
+fn task_type(issue: &GhIssue) -> TaskType {
+    match &issue.inner.pull_request {
+        Some(_) if issue.review_requested => TaskType::ReviewRequest,
+        Some(_) => TaskType::PullRequest,
+        None => TaskType::Issue,
+    }
+}
+
+/// Tasks grouped by repository, the repository with the most recently updated task first.
+pub struct TaskBoard<'a> {
+    groups: Vec<(Cow<'a, str>, Vec<&'a GhIssue>)>,
+}
+
+impl<'a> TaskBoard<'a> {
+    pub fn from_github_issues(issues: &'a [GhIssue]) -> Self {
+        let mut groups: Vec<(Cow<'a, str>, Vec<&'a GhIssue>)> = Vec::new();
+        for issue in issues {
+            let repository = task_repository(issue);
+            match groups.iter_mut().find(|(x, _)| *x == repository) {
+                Some((_, xs)) => xs.push(issue),
+                None => groups.push((repository, vec![issue])),
+            }
+        }
+        for (_, xs) in &mut groups {
+            xs.sort_by(|a, b| b.inner.updated_at.cmp(&a.inner.updated_at));
+        }
+        groups.sort_by(|(_, a), (_, b)| b[0].inner.updated_at.cmp(&a[0].inner.updated_at));
+        Self { groups }
+    }
+}
+
+impl Display for TaskBoard<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut w = TabWriter::new(Vec::new());
+        for (repository, issues) in &self.groups {
+            writeln!(w, "{} ({})", style(repository).bold(), issues.len()).unwrap();
+            for x in issues {
+                let labels: Vec<_> = x.inner.labels.iter().map(|x| x.name.as_str()).collect();
+                writeln!(
+                    w,
+                    "  #{}\t{}\t{}\t{}\t{}",
+                    x.inner.number,
+                    emojify(&x.inner.title),
+                    task_type(x),
+                    labels.join(", "),
+                    x.inner.created_at.since()
+                )
+                .unwrap();
+            }
+        }
+        write!(f, "{}", String::from_utf8(w.into_inner().unwrap()).unwrap())
+    }
+}
+
+#[derive(Serialize, PartialEq, Copy, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
 enum TaskState {
     Open,
 }
@@ -505,10 +813,13 @@ impl Display for TaskState {
     }
 }
 
-#[derive(PartialEq, Copy, Clone, Debug)]
+#[derive(Serialize, PartialEq, Copy, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
 enum TaskType {
     Issue,
     PullRequest,
+    /// Pull request requesting my review.
+    ReviewRequest,
 }
 
 impl Display for TaskType {
@@ -517,7 +828,121 @@ impl Display for TaskType {
         let x = match self {
             Issue => "Issue",
             PullRequest => "Pull request",
+            ReviewRequest => "Review request",
         };
         write!(f, "{}", x)
     }
 }
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct FeedEntries<'a> {
+    entries: Vec<FeedEntry<'a>>,
+}
+
+impl<'a> FeedEntries<'a> {
+    /// Creates feed entries from events, events that are not part of the feed are skipped.
+    pub fn from_github_events(events: &'a [GhEvent]) -> Self {
+        let entries = events
+            .iter()
+            .filter_map(FeedEntry::from_github_event)
+            .collect();
+        Self { entries }
+    }
+}
+
+impl Display for FeedEntries<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut w = TabWriter::new(Vec::new());
+        for e in &self.entries {
+            w.write_all(
+                format!(
+                    "{}\t{}\t{}\t{}\t{}\n",
+                    e.actor,
+                    e.kind,
+                    e.repository,
+                    e.url,
+                    e.timestamp.since()
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+        }
+        write!(f, "{}", String::from_utf8(w.into_inner().unwrap()).unwrap())
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct FeedEntry<'a> {
+    actor: &'a str,
+    kind: FeedEntryKind<'a>,
+    repository: &'a str,
+    url: Cow<'a, str>,
+    timestamp: &'a DateTime<Utc>,
+}
+
+impl<'a> FeedEntry<'a> {
+    fn from_github_event(event: &'a GhEvent) -> Option<Self> {
+        let (kind, url) = match event.r#type.as_str() {
+            "ReleaseEvent" if event.payload.action.as_deref() == Some("published") => {
+                let release = event.payload.release.as_ref()?;
+                let kind = FeedEntryKind::Release(&release.tag_name);
+                (kind, release.html_url.as_str().into())
+            }
+            "CreateEvent" if event.payload.ref_type.as_deref() == Some("repository") => {
+                // events have no page URL of the repository, it's on the host of its API URL
+                let host = api_host(Some(&event.repo.url)).ok()?;
+                let url = format!("https://{host}/{}", event.repo.name);
+                (FeedEntryKind::Repository, url.into())
+            }
+            _ => return None,
+        };
+        let s = Self {
+            actor: &event.actor.login,
+            kind,
+            repository: &event.repo.name,
+            url,
+            timestamp: &event.created_at,
+        };
+        Some(s)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_feed_entry_of_created_repository() {
+    let event = |url: &str| -> GhEvent {
+        serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "type": "CreateEvent",
+            "actor": { "login": "kafji" },
+            "repo": { "name": "kafji/shub", "url": url },
+            "payload": { "ref_type": "repository" },
+            "created_at": "2022-08-01T00:00:00Z",
+        }))
+        .unwrap()
+    };
+
+    let github = event("https://api.github.com/repos/kafji/shub");
+    let entry = FeedEntry::from_github_event(&github).unwrap();
+    assert_eq!(entry.kind, FeedEntryKind::Repository);
+    assert_eq!(entry.url, "https://github.com/kafji/shub");
+
+    let enterprise = event("https://github.example.com/api/v3/repos/kafji/shub");
+    let entry = FeedEntry::from_github_event(&enterprise).unwrap();
+    assert_eq!(entry.url, "https://github.example.com/kafji/shub");
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+enum FeedEntryKind<'a> {
+    Release(&'a str /* tag */),
+    Repository,
+}
+
+impl Display for FeedEntryKind<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedEntryKind::Release(tag) => write!(f, "Released {tag}"),
+            FeedEntryKind::Repository => write!(f, "Created repository"),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Defines the error returned by the library.
+//!
+//! Commands return [anyhow::Error] with context describing what failed, [ShubError] classifies it
+//! by its cause while keeping the context in its message.
+
+use crate::rate_limit::ApiError;
+use http::StatusCode;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ShubError {
+    /// GitHub responded with an error.
+    #[error("{error:#}")]
+    Api {
+        /// HTTP status of the response, when known.
+        status: Option<u16>,
+        /// Message of the response.
+        message: String,
+        error: anyhow::Error,
+    },
+
+    /// GitHub rejected the token.
+    #[error("{error:#}")]
+    Auth { error: anyhow::Error },
+
+    /// A git operation on a local repository failed.
+    #[error("{error:#}")]
+    Git {
+        code: git2::ErrorCode,
+        error: anyhow::Error,
+    },
+
+    /// Reading or writing the database failed.
+    #[error("{error:#}")]
+    Database { error: anyhow::Error },
+
+    /// The configuration is missing or invalid.
+    #[error("{error:#}")]
+    Config { error: anyhow::Error },
+
+    #[error("{error:#}")]
+    Other { error: anyhow::Error },
+}
+
+impl ShubError {
+    pub(crate) fn config(error: anyhow::Error) -> Self {
+        Self::Config { error }
+    }
+}
+
+impl From<anyhow::Error> for ShubError {
+    fn from(error: anyhow::Error) -> Self {
+        // already classified where it failed
+        let error = match error.downcast::<ShubError>() {
+            Ok(x) => return x,
+            Err(x) => x,
+        };
+        let github = error
+            .chain()
+            .find_map(|x| x.downcast_ref::<ApiError>())
+            .map(|x| {
+                let message = x.message().map_or_else(|| x.status.to_string(), From::from);
+                (x.status, message)
+            });
+        let http = || {
+            error
+                .chain()
+                .find_map(|x| x.downcast_ref::<reqwest::Error>())
+                .and_then(|x| x.status())
+                .map(|x| (x, x.to_string()))
+        };
+        if let Some((status, message)) = github.or_else(http) {
+            if status == StatusCode::UNAUTHORIZED {
+                return Self::Auth { error };
+            }
+            return Self::Api {
+                status: Some(status.as_u16()),
+                message,
+                error,
+            };
+        }
+        let git = error
+            .chain()
+            .find_map(|x| x.downcast_ref::<git2::Error>())
+            .map(|x| x.code());
+        if let Some(code) = git {
+            return Self::Git { code, error };
+        }
+        if error
+            .chain()
+            .any(|x| x.downcast_ref::<rusqlite::Error>().is_some())
+        {
+            return Self::Database { error };
+        }
+        Self::Other { error }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_from_anyhow_error() {
+    use anyhow::Context;
+
+    let err: Result<(), _> = Err(git2::Error::from_str("not a repository"));
+    let err = ShubError::from(err.context("Failed to open kafji/shub.").unwrap_err());
+    assert!(matches!(err, ShubError::Git { .. }));
+    assert_eq!(
+        err.to_string(),
+        "Failed to open kafji/shub.: not a repository"
+    );
+
+    let err = ShubError::from(anyhow::Error::from(rusqlite::Error::InvalidQuery));
+    assert!(matches!(err, ShubError::Database { .. }));
+
+    let err = ShubError::from(anyhow::anyhow!("Workspace not found."));
+    assert!(matches!(err, ShubError::Other { .. }));
+
+    let err = ShubError::config(anyhow::anyhow!("Invalid profile."));
+    let err = ShubError::from(anyhow::Error::from(err));
+    assert!(matches!(err, ShubError::Config { .. }));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_from_api_error() {
+    let from_response = |status: u16, message: &str| {
+        let body = serde_json::json!({
+            "message": message,
+            "documentation_url": "https://docs.github.com/rest",
+        });
+        let response: reqwest::Response = http::Response::builder()
+            .status(status)
+            .body(body.to_string())
+            .unwrap()
+            .into();
+        async move {
+            let err = crate::rate_limit::map_github_error(response)
+                .await
+                .unwrap_err();
+            ShubError::from(anyhow::Error::from(err).context("Failed to create label."))
+        }
+    };
+
+    let err = from_response(403, "Resource not accessible by integration").await;
+    assert!(matches!(
+        err,
+        ShubError::Api { status: Some(403), ref message, .. }
+            if message == "Resource not accessible by integration"
+    ));
+
+    let err = from_response(422, "Validation Failed").await;
+    assert!(matches!(
+        err,
+        ShubError::Api { status: Some(422), ref message, .. } if message == "Validation Failed"
+    ));
+
+    let err = from_response(401, "Bad credentials").await;
+    assert!(matches!(err, ShubError::Auth { .. }));
+}
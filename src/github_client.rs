@@ -1,56 +1,67 @@
-use crate::{app::GitHubClient, github_models::*, FullRepoId};
+use crate::{
+    app::GitHubClient,
+    github_models::*,
+    pagination,
+    rate_limit::{self, ApiClient},
+    types::TaskFilter,
+    FullRepoId,
+};
 use anyhow::{bail, Error};
-use async_stream::try_stream;
 use async_trait::async_trait;
 use futures::{
     stream::{self, LocalBoxStream},
-    Future, Stream, StreamExt, TryStreamExt,
+    StreamExt, TryStreamExt,
 };
-use http::header::HeaderName;
-use octocrab::{Octocrab, Page};
 use sekret::Secret;
 use serde::Deserialize;
-use std::{borrow::Cow, env};
 
 #[derive(Clone, Debug)]
 pub struct GitHubClientImpl {
-    client: Octocrab,
+    client: ApiClient,
 }
 
 impl GitHubClientImpl {
-    pub fn new(token: impl Into<Secret<String>>) -> Result<Self, Error> {
+    pub fn new(
+        token: impl Into<Secret<String>>,
+        api_url: Option<&str>,
+        proxy: Option<&str>,
+    ) -> Result<Self, Error> {
         let user_agent = concat!(
             env!("CARGO_PKG_NAME"),
             concat!("/", env!("CARGO_PKG_VERSION"))
-        )
-        .to_owned();
+        );
         let token: Secret<_> = token.into();
-        let client = Octocrab::builder()
-            .add_header(HeaderName::from_static("user-agent"), user_agent)
-            .personal_token(token.0)
-            .build()?;
+        let client = ApiClient::new(&token.0, api_url, proxy, user_agent, &[])?;
         let s = Self { client };
         Ok(s)
     }
+
+    /// https://docs.github.com/en/rest/search#search-issues-and-pull-requests
+    fn search_issues(
+        &self,
+        query: String,
+        sort: Option<&str>,
+    ) -> LocalBoxStream<'_, Result<GhIssue, Error>> {
+        let mut url = match self.client.absolute_url("search/issues") {
+            Ok(x) => x,
+            Err(err) => return stream::once(async { Err(err) }).boxed_local(),
+        };
+        {
+            let mut pairs = url.query_pairs_mut();
+            pairs.append_pair("q", &query);
+            if let Some(sort) = sort {
+                pairs.append_pair("sort", sort).append_pair("order", "desc");
+            }
+        }
+        pagination::follow_links(&self.client, None, url.into()).boxed_local()
+    }
 }
 
 #[async_trait]
 impl<'a> GitHubClient<'a> for GitHubClientImpl {
     fn list_stared_repositories(&'a self) -> LocalBoxStream<'a, Result<GhRepository, Error>> {
-        let this = self.clone();
-        let items = unpage(move |page_num| {
-            let client = this.client.clone();
-            async move {
-                let path: Cow<_> = if let Some(page_num) = page_num {
-                    format!("user/starred?sort=updated&per_page=100&page={page_num}").into()
-                } else {
-                    "user/starred?sort=updated&per_page=100".into()
-                };
-                let items: Page<_> = client.get::<_, _, ()>(path, None).await?;
-                Ok(items)
-            }
-        });
-        items.boxed_local()
+        pagination::follow_links(&self.client, None, "user/starred?sort=updated".to_owned())
+            .boxed_local()
     }
 
     fn list_repository_commits<'b>(
@@ -60,17 +71,9 @@ impl<'a> GitHubClient<'a> for GitHubClientImpl {
     where
         'a: 'b,
     {
-        let items = unpage(move |page_num| async move {
-            let FullRepoId { owner, name } = repo_id;
-            let path = if let Some(page_num) = page_num {
-                format!("repos/{owner}/{name}/commits?per_page=100&page={page_num}")
-            } else {
-                format!("repos/{owner}/{name}/commits?per_page=100")
-            };
-            let items: Page<_> = self.client.get::<_, _, ()>(path, None).await?;
-            Ok(items)
-        });
-        items.boxed_local()
+        let FullRepoId { owner, name } = repo_id;
+        let route = format!("repos/{owner}/{name}/commits");
+        pagination::follow_links(&self.client, None, route).boxed_local()
     }
 
     async fn get_check_runs_for_gitref<'b>(
@@ -88,96 +91,74 @@ impl<'a> GitHubClient<'a> for GitHubClientImpl {
         struct Envelope {
             check_runs: Vec<GhCheckRun>,
         }
-        let res: Envelope = self.client.get::<_, _, ()>(path, None).await?;
+        let res: Envelope = rate_limit::get(&self.client, None, path).await?;
         Ok(res.check_runs)
     }
 
+    async fn get_commit<'b>(
+        &'a self,
+        repo_id: &'b FullRepoId,
+        gitref: &'b str,
+    ) -> Result<GhCommit, Error>
+    where
+        'a: 'b,
+    {
+        let FullRepoId { owner, name } = repo_id;
+        let path = format!("repos/{owner}/{name}/commits/{gitref}");
+        let commit = rate_limit::get(&self.client, None, path).await?;
+        Ok(commit)
+    }
+
+    async fn get_pull_request<'b>(
+        &'a self,
+        repo_id: &'b FullRepoId,
+        number: u64,
+    ) -> Result<GhPullRequest, Error>
+    where
+        'a: 'b,
+    {
+        let FullRepoId { owner, name } = repo_id;
+        let path = format!("repos/{owner}/{name}/pulls/{number}");
+        let pr = rate_limit::get(&self.client, None, path).await?;
+        Ok(pr)
+    }
+
     async fn get_repository(&'a self, repo_id: FullRepoId) -> Result<GhRepository, Error> {
-        let client = &self.client;
-        let repo = client.repos(&repo_id.owner, &repo_id.name).get().await;
+        let route = format!("repos/{}/{}", repo_id.owner, repo_id.name);
+        let repo = rate_limit::get(&self.client, None, route).await;
         let repo = match repo {
             Ok(x) => x,
             Err(err) => {
-                if matches!(&err, octocrab::Error::GitHub { source, .. } if source.message == "Not Found")
-                {
+                if rate_limit::error_status(&err) == Some(http::StatusCode::NOT_FOUND) {
                     bail!("Repository {repo_id} does not exist.")
                 } else {
-                    return Err(err.into());
+                    return Err(err);
                 }
             }
         };
         Ok(repo)
     }
 
-    fn list_user_issues(&'a self) -> LocalBoxStream<'a, Result<GhIssue, Error>> {
-        stream::try_unfold::<PageNum, _, _, Page<GhIssue>>(
-            PageNum::Init,
-            move |page_num| async move {
-                let path: Option<Cow<str>> = match page_num {
-                    PageNum::Init => Some("issues".into()),
-                    PageNum::Num(x) => Some(format!("issues?per_page=100&page={x}").into()),
-                    PageNum::End => None,
-                };
-                match path {
-                    Some(path) => {
-                        let page: Page<GhIssue> = self.client.get::<_, _, ()>(path, None).await?;
-                        let next_page_num = page
-                            .next
-                            .as_ref()
-                            .map(|_| page_num.succ())
-                            .unwrap_or(PageNum::End);
-                        Ok(Some((page, next_page_num)))
-                    }
-                    None => Result::<_, Error>::Ok(None),
-                }
-            },
-        )
-        .map_ok(|x: Page<GhIssue>| {
-            let x: Vec<_> = x.into_iter().collect();
-            stream::iter(x).map(Ok)
-        })
-        .try_flatten()
-        .boxed_local()
-    }
-}
-
-#[derive(PartialEq, Copy, Clone, Debug)]
-enum PageNum {
-    Init,
-    Num(u8),
-    End,
-}
-
-impl PageNum {
-    fn succ(self) -> PageNum {
-        let num = match self {
-            PageNum::Init => 1,
-            PageNum::Num(x) => x + 1,
-            PageNum::End => panic!(),
-        };
-        PageNum::Num(num)
+    fn list_user_issues(
+        &'a self,
+        filter: &TaskFilter,
+    ) -> LocalBoxStream<'a, Result<GhIssue, Error>> {
+        self.search_issues(filter.search_query(), filter.search_sort())
     }
-}
 
-fn unpage<'a, T, F, Fut>(factory: F) -> impl Stream<Item = Result<T, Error>> + 'a
-where
-    T: Send + 'static,
-    F: Fn(Option<u8>) -> Fut + 'a,
-    Fut: Future<Output = Result<Page<T>, Error>> + 'a,
-{
-    try_stream! {
-        let mut page_num = None;
-        loop {
-            let req = factory(page_num);
-            let page = req.await?;
-            let has_next = page.next.is_some();
-            for repo in page {
-                yield repo;
-            }
-            if !has_next {
-                break;
-            }
-            page_num = (page_num.unwrap_or(1) + 1).into();
+    fn list_review_requests(
+        &'a self,
+        filter: &TaskFilter,
+    ) -> LocalBoxStream<'a, Result<GhIssue, Error>> {
+        match filter.review_requests_query() {
+            Some(query) => self
+                .search_issues(query, filter.search_sort())
+                .map_ok(|mut x| {
+                    x.review_requested = true;
+                    x
+                })
+                .boxed_local(),
+            None => stream::empty().boxed_local(),
         }
     }
 }
@@ -1,15 +1,20 @@
-use std::fmt;
+use std::{collections::HashMap, fmt, fmt::Write as _};
 
 use crate::{
-    github_models::{GhCheckRun, GhCommit, GhRepository},
+    database::HttpCache,
+    github_models::*,
+    pagination,
+    rate_limit::{self, ApiClient},
     repository_id::IsRepositoryId,
 };
-use anyhow::Error;
-use futures::{stream, Stream, StreamExt, TryStreamExt};
-use http::header::HeaderName;
-use octocrab::{Octocrab, Page};
+use anyhow::{bail, Error};
+use chrono::{DateTime, Utc};
+use futures::{Stream, TryStreamExt};
+use octocrab::Page;
 use sekret::Secret;
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::debug;
 
 const USER_AGENT: &str = concat!(
     env!("CARGO_PKG_NAME"),
@@ -18,76 +23,297 @@ const USER_AGENT: &str = concat!(
 
 /// Defines a higher level queries to GitHub server.
 ///
-/// Newtype of Octocrab.
+/// Newtype of [ApiClient].
 #[derive(Clone)]
-pub struct GithubClient2(Octocrab);
+pub struct GithubClient2(ApiClient, bool /* dry run */, Option<HttpCache>);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum PageCursor {
-    Page(u8),
-    End,
-}
+impl GithubClient2 {
+    /// Creates a client of the GitHub API at `api_url`, defaults to github.com, sending requests
+    /// through `proxy` when given one.
+    pub fn new(
+        token: Secret<&str>,
+        api_url: Option<&str>,
+        proxy: Option<&str>,
+    ) -> Result<Self, Error> {
+        let client = ApiClient::new(
+            token.into_inner(),
+            api_url,
+            proxy,
+            USER_AGENT,
+            // dependency graph
+            &["hawkgirl"],
+        )?;
+        Ok(Self(client, false, None))
+    }
 
-impl Default for PageCursor {
-    fn default() -> Self {
-        Self::Page(0)
+    /// Prints mutating requests instead of sending them when dry run is enabled.
+    pub fn with_dry_run(self, dry_run: bool) -> Self {
+        Self(self.0, dry_run, self.2)
     }
-}
 
-impl GithubClient2 {
-    pub fn new(token: Secret<&str>) -> Result<Self, Error> {
-        let client = Octocrab::builder()
-            .add_header(
-                HeaderName::from_static("user-agent"),
-                USER_AGENT.to_string(),
-            )
-            .personal_token(token.into_inner().to_owned())
-            .build()?;
-        Ok(Self(client))
+    /// Makes GET requests conditional, reusing cached responses that have not changed.
+    pub fn with_cache(self, cache: HttpCache) -> Self {
+        Self(self.0, self.1, Some(cache))
+    }
+
+    /// Returns true and prints the request if dry run is enabled.
+    fn dry_run(
+        &self,
+        method: &str,
+        route: &str,
+        body: Option<&impl Serialize>,
+    ) -> Result<bool, Error> {
+        if self.1 {
+            print_dry_run_request(method, &self.0.absolute_url(route)?, body)?;
+        }
+        Ok(self.1)
     }
 
-    /// Lists current user repositories.
+    /// Lists current user repositories, most recently updated first.
     pub fn list_owned_repositories(&self) -> impl Stream<Item = Result<GhRepository, Error>> + '_ {
-        stream::try_unfold(PageCursor::default(), move |cursor| async move {
-            // convert page cursor to literal page number
-            let page_num = match cursor {
-                PageCursor::Page(x) => x,
-                PageCursor::End => {
-                    // short circuit when page cursor is at the end
-                    return Result::<_, Error>::Ok(None);
-                }
-            };
-            // do the thing
-            let mut page = self
-                .0
-                .current()
-                .list_repos_for_authenticated_user()
-                .affiliation("owner")
-                .sort("updated")
-                .direction("desc")
-                .per_page(100 /* max */)
-                .page(page_num)
-                .send()
-                .await?;
-            // take items from response envelope, this will do memswap
-            let items = page.take_items();
-            // create updated page cursor for the next iteration
-            let cursor = if page.next.is_none() {
-                // has no next page, must be the end
-                PageCursor::End
-            } else {
-                PageCursor::Page(page_num + 1)
-            };
-            // yield
-            Ok(Some((items, cursor)))
-        })
-        .map_ok(|x|
-            // convert items into its own stream
-            stream::iter(x)
-            // align inner stream type to the outer stream
-            .map(Result::<_, Error>::Ok))
-        // flatten the inner stream
-        .try_flatten()
+        self.list_owned_repositories_sorted(None, Some("updated"), Some("desc"))
+    }
+
+    /// Lists current user repositories of a type in the given order, owned ones in API default
+    /// order when none.
+    ///
+    /// https://docs.github.com/en/rest/repos/repos#list-repositories-for-the-authenticated-user
+    pub fn list_owned_repositories_sorted<'a>(
+        &'a self,
+        ty: Option<&'a str>,
+        sort: Option<&'a str>,
+        direction: Option<&'a str>,
+    ) -> impl Stream<Item = Result<GhRepository, Error>> + 'a {
+        // type can't be combined with affiliation
+        let mut route = match ty {
+            Some(x) => format!("user/repos?type={x}"),
+            None => "user/repos?affiliation=owner".to_owned(),
+        };
+        if let Some(x) = sort {
+            route.push_str(&format!("&sort={x}"));
+        }
+        if let Some(x) = direction {
+            route.push_str(&format!("&direction={x}"));
+        }
+        self.list_pages(route)
+    }
+
+    /// Lists unread notifications of the current user, most recently updated first.
+    ///
+    /// https://docs.github.com/en/rest/activity/notifications#list-notifications-for-the-authenticated-user
+    pub fn list_notifications(&self) -> impl Stream<Item = Result<GhNotification, Error>> + '_ {
+        self.list_pages("notifications".to_owned())
+    }
+
+    /// https://docs.github.com/en/rest/activity/notifications#mark-a-thread-as-read
+    pub async fn mark_notification_read(&self, thread_id: &str) -> Result<(), Error> {
+        self.send_without_content(
+            http::Method::PATCH,
+            &format!("notifications/threads/{thread_id}"),
+        )
+        .await
+    }
+
+    /// https://docs.github.com/en/rest/activity/notifications#mark-notifications-as-read
+    pub async fn mark_all_notifications_read(&self) -> Result<(), Error> {
+        self.send_without_content(http::Method::PUT, "notifications")
+            .await
+    }
+
+    /// https://docs.github.com/en/rest/repos/repos#list-organization-repositories
+    pub fn list_organization_repositories<'a>(
+        &'a self,
+        org: &str,
+    ) -> impl Stream<Item = Result<GhRepository, Error>> + 'a {
+        self.list_organization_repositories_sorted(org, None, None, None)
+    }
+
+    /// Lists repositories of an organization of a type in the given order, all of them in API
+    /// default order when none.
+    pub fn list_organization_repositories_sorted<'a>(
+        &'a self,
+        org: &str,
+        ty: Option<&str>,
+        sort: Option<&str>,
+        direction: Option<&str>,
+    ) -> impl Stream<Item = Result<GhRepository, Error>> + 'a {
+        let ty = ty.unwrap_or("all");
+        let mut route = format!("orgs/{org}/repos?type={ty}");
+        if let Some(x) = sort {
+            route.push_str(&format!("&sort={x}"));
+        }
+        if let Some(x) = direction {
+            route.push_str(&format!("&direction={x}"));
+        }
+        self.list_pages(route)
+    }
+
+    /// https://docs.github.com/en/rest/users/users#get-the-authenticated-user
+    pub async fn get_current_user(&self) -> Result<GhUser, Error> {
+        let user = rate_limit::get(&self.0, self.2.as_ref(), "user").await?;
+        Ok(user)
+    }
+
+    /// Gets OAuth scopes of the token.
+    ///
+    /// Returns `None` if the token has no scopes, e.g. a fine-grained token.
+    ///
+    /// https://docs.github.com/en/developers/apps/building-oauth-apps/scopes-for-oauth-apps
+    pub async fn get_token_scopes(&self) -> Result<Option<Vec<String>>, Error> {
+        let headers = rate_limit::get_headers(&self.0, "user").await?;
+        let scopes = headers
+            .get("x-oauth-scopes")
+            .and_then(|x| x.to_str().ok())
+            .map(parse_scopes);
+        Ok(scopes)
+    }
+
+    /// https://docs.github.com/en/rest/rate-limit#get-rate-limit-status-for-the-authenticated-user
+    pub async fn get_rate_limit(&self) -> Result<GhRateLimit, Error> {
+        let rate_limit = rate_limit::get(&self.0, self.2.as_ref(), "rate_limit").await?;
+        Ok(rate_limit)
+    }
+
+    /// https://docs.github.com/en/rest/repos/repos#get-a-repository
+    pub async fn get_repository(
+        &self,
+        repo_id: &impl IsRepositoryId,
+    ) -> Result<GhRepository, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let repo =
+            rate_limit::get(&self.0, self.2.as_ref(), format!("repos/{owner}/{name}")).await?;
+        Ok(repo)
+    }
+
+    /// Returns `None` if the repository does not exist or is not visible to the token.
+    ///
+    /// A renamed or transferred repository is returned under its new name.
+    ///
+    /// https://docs.github.com/en/rest/repos/repos#get-a-repository
+    pub async fn find_repository(
+        &self,
+        repo_id: &impl IsRepositoryId,
+    ) -> Result<Option<GhRepository>, Error> {
+        match self.get_repository(repo_id).await {
+            Ok(x) => Ok(Some(x)),
+            Err(err) if is_not_found(&err) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// https://docs.github.com/en/rest/repos/repos#update-a-repository
+    pub async fn update_repository(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        body: &impl Serialize,
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}");
+        if self.dry_run("PATCH", &route, Some(body))? {
+            return Ok(());
+        }
+        let _: Value = rate_limit::patch(&self.0, route, Some(body)).await?;
+        Ok(())
+    }
+
+    /// https://docs.github.com/en/rest/repos/repos#get-all-repository-topics
+    pub async fn get_topics(&self, repo_id: &impl IsRepositoryId) -> Result<Vec<String>, Error> {
+        #[derive(Deserialize)]
+        struct Topics {
+            names: Vec<String>,
+        }
+
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let topics: Topics = rate_limit::get(
+            &self.0,
+            self.2.as_ref(),
+            format!("repos/{owner}/{name}/topics"),
+        )
+        .await?;
+        Ok(topics.names)
+    }
+
+    /// https://docs.github.com/en/rest/repos/repos#replace-all-repository-topics
+    pub async fn replace_topics(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        topics: &[String],
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}/topics");
+        let body = json!({ "names": topics });
+        if self.dry_run("PUT", &route, Some(&body))? {
+            return Ok(());
+        }
+        let _: Value = rate_limit::put(&self.0, route, Some(&body)).await?;
+        Ok(())
+    }
+
+    /// Renames a repository, returns the renamed repository or `None` on dry run.
+    ///
+    /// https://docs.github.com/en/rest/repos/repos#update-a-repository
+    pub async fn rename_repository(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        new_name: &str,
+    ) -> Result<Option<GhRepository>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}");
+        let body = json!({ "name": new_name });
+        if self.dry_run("PATCH", &route, Some(&body))? {
+            return Ok(None);
+        }
+        let repo = rate_limit::patch(&self.0, route, Some(&body)).await?;
+        Ok(Some(repo))
+    }
+
+    /// https://docs.github.com/en/rest/repos/repos#delete-a-repository
+    pub async fn delete_repository(&self, repo_id: &impl IsRepositoryId) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.send_without_content(http::Method::DELETE, &format!("repos/{owner}/{name}"))
+            .await
+    }
+
+    /// Creates a repository in current user account, returns `None` on dry run.
+    ///
+    /// https://docs.github.com/en/rest/repos/repos#create-a-repository-for-the-authenticated-user
+    pub async fn create_repository(
+        &self,
+        repo: &GhNewRepository<'_>,
+    ) -> Result<Option<GhRepository>, Error> {
+        let route = "user/repos";
+        if self.dry_run("POST", route, Some(repo))? {
+            return Ok(None);
+        }
+        let repo = rate_limit::post(&self.0, route, Some(repo)).await?;
+        Ok(Some(repo))
+    }
+
+    /// Forks a repository to current user account, returns `None` on dry run.
+    ///
+    /// The fork is created asynchronously, it may not be ready to be cloned yet when this
+    /// returns.
+    ///
+    /// https://docs.github.com/en/rest/repos/forks#create-a-fork
+    pub async fn create_fork(
+        &self,
+        repo_id: &impl IsRepositoryId,
+    ) -> Result<Option<GhRepository>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}/forks");
+        let body = json!({});
+        if self.dry_run("POST", &route, Some(&body))? {
+            return Ok(None);
+        }
+        let fork = rate_limit::post(&self.0, route, Some(&body)).await?;
+        Ok(Some(fork))
     }
 
     /// Gets the latest commit of a repository.
@@ -97,14 +323,35 @@ impl GithubClient2 {
     ) -> Result<Option<GhCommit>, Error> {
         let owner = repo_id.owner();
         let name = repo_id.name();
-        let commits: Page<_> = self
-            .0
-            .get::<_, _, ()>(format!("repos/{owner}/{name}/commits"), None)
-            .await?;
+        let commits: Page<_> = rate_limit::get(
+            &self.0,
+            self.2.as_ref(),
+            format!("repos/{owner}/{name}/commits"),
+        )
+        .await?;
         let commit = commits.into_iter().next();
         Ok(commit)
     }
 
+    /// Gets the commit a branch, tag, or SHA points to.
+    ///
+    /// https://docs.github.com/en/rest/commits/commits#get-a-commit
+    pub async fn get_commit(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        gitref: &str,
+    ) -> Result<GhCommit, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let commit = rate_limit::get(
+            &self.0,
+            self.2.as_ref(),
+            format!("repos/{owner}/{name}/commits/{gitref}"),
+        )
+        .await?;
+        Ok(commit)
+    }
+
     /// https://docs.github.com/en/rest/checks/runs#list-check-runs-for-a-git-reference
     #[tracing::instrument(skip(self))]
     pub async fn get_check_runs_for_gitref(
@@ -123,7 +370,1313 @@ impl GithubClient2 {
             check_runs: Vec<GhCheckRun>,
         }
 
-        let response: Envelope = self.0.get::<_, _, ()>(path, None).await?;
+        let response: Envelope = rate_limit::get(&self.0, self.2.as_ref(), path).await?;
         Ok(response.check_runs)
     }
+
+    /// Lists workflow runs of a repository, most recent first.
+    ///
+    /// https://docs.github.com/en/rest/actions/workflow-runs#list-workflow-runs-for-a-repository
+    pub fn list_workflow_runs<'a>(
+        &'a self,
+        repo_id: &impl IsRepositoryId,
+    ) -> impl Stream<Item = Result<GhWorkflowRun, Error>> + 'a {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        // octocrab's page takes the runs out of their `workflow_runs` envelope
+        self.list_pages(format!("repos/{owner}/{name}/actions/runs"))
+    }
+
+    /// Lists workflow runs triggered for a commit, up to 100 of them.
+    ///
+    /// https://docs.github.com/en/rest/actions/workflow-runs#list-workflow-runs-for-a-repository
+    pub async fn list_workflow_runs_for_commit(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        sha: &str,
+    ) -> Result<Vec<GhWorkflowRun>, Error> {
+        #[derive(Deserialize)]
+        struct Runs {
+            workflow_runs: Vec<GhWorkflowRun>,
+        }
+
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let runs: Runs = rate_limit::get(
+            &self.0,
+            self.2.as_ref(),
+            format!("repos/{owner}/{name}/actions/runs?head_sha={sha}&per_page=100"),
+        )
+        .await?;
+        Ok(runs.workflow_runs)
+    }
+
+    /// https://docs.github.com/en/rest/actions/workflow-runs#re-run-failed-jobs-from-a-workflow-run
+    pub async fn rerun_failed_jobs(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        run_id: u64,
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.send_without_content(
+            http::Method::POST,
+            &format!("repos/{owner}/{name}/actions/runs/{run_id}/rerun-failed-jobs"),
+        )
+        .await
+    }
+
+    /// Downloads logs of a workflow run as a zip archive, a file per job step.
+    ///
+    /// https://docs.github.com/en/rest/actions/workflow-runs#download-workflow-run-logs
+    pub async fn download_workflow_run_logs(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        run_id: u64,
+    ) -> Result<Vec<u8>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        rate_limit::get_bytes(
+            &self.0,
+            format!("repos/{owner}/{name}/actions/runs/{run_id}/logs"),
+        )
+        .await
+    }
+
+    /// Downloads log of a workflow job as plain text.
+    ///
+    /// https://docs.github.com/en/rest/actions/workflow-jobs#download-job-logs-for-a-workflow-run
+    pub async fn download_job_logs(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        job_id: u64,
+    ) -> Result<Vec<u8>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        rate_limit::get_bytes(
+            &self.0,
+            format!("repos/{owner}/{name}/actions/jobs/{job_id}/logs"),
+        )
+        .await
+    }
+
+    /// https://docs.github.com/en/rest/actions/workflow-runs#delete-a-workflow-run
+    pub async fn delete_workflow_run(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        run_id: u64,
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.send_without_content(
+            http::Method::DELETE,
+            &format!("repos/{owner}/{name}/actions/runs/{run_id}"),
+        )
+        .await
+    }
+
+    /// Lists events received by a user, i.e. activities of the users they follow and the
+    /// repositories they watch.
+    ///
+    /// https://docs.github.com/en/rest/activity/events#list-events-received-by-the-authenticated-user
+    pub fn list_received_events<'a>(
+        &'a self,
+        username: &str,
+    ) -> impl Stream<Item = Result<GhEvent, Error>> + 'a {
+        self.list_pages(format!("users/{username}/received_events"))
+    }
+
+    /// Lists users followed by current user.
+    ///
+    /// https://docs.github.com/en/rest/users/followers#list-the-people-the-authenticated-user-follows
+    pub fn list_followed_users(&self) -> impl Stream<Item = Result<GhUser, Error>> + '_ {
+        self.list_pages("user/following".to_owned())
+    }
+
+    /// Gets content of a file in a repository default branch.
+    ///
+    /// Returns `None` if the file does not exist.
+    ///
+    /// https://docs.github.com/en/rest/repos/contents#get-repository-content
+    pub async fn get_file_content(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        path: &str,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let content: GhContent = match rate_limit::get(
+            &self.0,
+            self.2.as_ref(),
+            format!("repos/{owner}/{name}/contents/{path}"),
+        )
+        .await
+        {
+            Ok(x) => x,
+            Err(err) if is_not_found(&err) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        decode_content(content).map(Some)
+    }
+
+    /// Gets content of the preferred README of a repository.
+    ///
+    /// Returns `None` if the repository has no README.
+    ///
+    /// https://docs.github.com/en/rest/repos/contents#get-a-repository-readme
+    pub async fn get_readme(
+        &self,
+        repo_id: &impl IsRepositoryId,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let content: GhContent = match rate_limit::get(
+            &self.0,
+            self.2.as_ref(),
+            format!("repos/{owner}/{name}/readme"),
+        )
+        .await
+        {
+            Ok(x) => x,
+            Err(err) if is_not_found(&err) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        decode_content(content).map(Some)
+    }
+
+    /// Returns `None` if the repository has no published release.
+    ///
+    /// https://docs.github.com/en/rest/releases/releases#get-the-latest-release
+    pub async fn get_latest_release(
+        &self,
+        repo_id: &impl IsRepositoryId,
+    ) -> Result<Option<GhRelease>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        match rate_limit::get(
+            &self.0,
+            self.2.as_ref(),
+            format!("repos/{owner}/{name}/releases/latest"),
+        )
+        .await
+        {
+            Ok(x) => Ok(Some(x)),
+            Err(err) if is_not_found(&err) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Counts open pull requests of a repository without listing them.
+    ///
+    /// https://docs.github.com/en/rest/search#search-issues-and-pull-requests
+    pub async fn count_open_pull_requests(
+        &self,
+        repo_id: &impl IsRepositoryId,
+    ) -> Result<u64, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+
+        #[derive(Deserialize)]
+        struct Envelope {
+            total_count: u64,
+        }
+
+        let response: Envelope = rate_limit::get(
+            &self.0,
+            self.2.as_ref(),
+            format!("search/issues?q=repo:{owner}/{name}+is:pr+is:open&per_page=1"),
+        )
+        .await?;
+        Ok(response.total_count)
+    }
+
+    /// Gets all files and directories of a repository default branch.
+    ///
+    /// https://docs.github.com/en/rest/git/trees#get-a-tree
+    pub async fn get_tree(&self, repo_id: &impl IsRepositoryId) -> Result<GhTree, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let tree = rate_limit::get(
+            &self.0,
+            self.2.as_ref(),
+            format!("repos/{owner}/{name}/git/trees/HEAD?recursive=1"),
+        )
+        .await?;
+        Ok(tree)
+    }
+
+    /// https://docs.github.com/en/rest/repos/repos#list-codeowners-errors
+    pub async fn list_codeowners_errors(
+        &self,
+        repo_id: &impl IsRepositoryId,
+    ) -> Result<Vec<GhCodeownersError>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+
+        #[derive(Deserialize)]
+        struct Envelope {
+            errors: Vec<GhCodeownersError>,
+        }
+
+        let response: Envelope = rate_limit::get(
+            &self.0,
+            self.2.as_ref(),
+            format!("repos/{owner}/{name}/codeowners/errors"),
+        )
+        .await?;
+        Ok(response.errors)
+    }
+
+    /// Gets protection of a branch.
+    ///
+    /// Returns `None` if the branch is not protected.
+    ///
+    /// https://docs.github.com/en/rest/branches/branch-protection#get-branch-protection
+    pub async fn get_branch_protection(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        branch: &str,
+    ) -> Result<Option<GhBranchProtection>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let protection = rate_limit::get(
+            &self.0,
+            self.2.as_ref(),
+            format!("repos/{owner}/{name}/branches/{branch}/protection"),
+        )
+        .await;
+        match protection {
+            Ok(x) => Ok(Some(x)),
+            // not found for a branch without protection
+            Err(err) if is_not_found(&err) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Renames a branch.
+    ///
+    /// https://docs.github.com/en/rest/branches/branches#rename-a-branch
+    pub async fn rename_branch(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        branch: &str,
+        new_name: &str,
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}/branches/{branch}/rename");
+        let body = json!({ "new_name": new_name });
+        if self.dry_run("POST", &route, Some(&body))? {
+            return Ok(());
+        }
+        let _: Value = rate_limit::post(&self.0, route, Some(&body)).await?;
+        Ok(())
+    }
+
+    /// https://docs.github.com/en/rest/issues/issues#create-an-issue
+    pub async fn create_issue(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        issue: &GhNewIssue<'_>,
+    ) -> Result<Option<octocrab::models::issues::Issue>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}/issues");
+        if self.dry_run("POST", &route, Some(issue))? {
+            return Ok(None);
+        }
+        let issue = rate_limit::post(&self.0, route, Some(issue)).await?;
+        Ok(Some(issue))
+    }
+
+    /// https://docs.github.com/en/rest/issues/issues#get-an-issue
+    pub async fn get_issue(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        number: u64,
+    ) -> Result<octocrab::models::issues::Issue, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let issue = rate_limit::get(
+            &self.0,
+            self.2.as_ref(),
+            format!("repos/{owner}/{name}/issues/{number}"),
+        )
+        .await?;
+        Ok(issue)
+    }
+
+    /// Replaces assignees of an issue or a pull request.
+    ///
+    /// https://docs.github.com/en/rest/issues/issues#update-an-issue
+    pub async fn update_issue_assignees(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        number: u64,
+        assignees: &[String],
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}/issues/{number}");
+        let body = json!({ "assignees": assignees });
+        if self.dry_run("PATCH", &route, Some(&body))? {
+            return Ok(());
+        }
+        let _: Value = rate_limit::patch(&self.0, route, Some(&body)).await?;
+        Ok(())
+    }
+
+    /// Closes or reopens an issue or a pull request.
+    ///
+    /// https://docs.github.com/en/rest/issues/issues#update-an-issue
+    pub async fn update_issue_state(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        number: u64,
+        closed: bool,
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}/issues/{number}");
+        let state = if closed { "closed" } else { "open" };
+        let body = json!({ "state": state });
+        if self.dry_run("PATCH", &route, Some(&body))? {
+            return Ok(());
+        }
+        let _: Value = rate_limit::patch(&self.0, route, Some(&body)).await?;
+        Ok(())
+    }
+
+    /// https://docs.github.com/en/rest/issues/labels#list-labels-for-a-repository
+    pub fn list_labels<'a>(
+        &'a self,
+        repo_id: &impl IsRepositoryId,
+    ) -> impl Stream<Item = Result<GhLabel, Error>> + 'a {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.list_pages(format!("repos/{owner}/{name}/labels"))
+    }
+
+    /// https://docs.github.com/en/rest/issues/labels#create-a-label
+    pub async fn create_label(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        label: &impl Serialize,
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}/labels");
+        if self.dry_run("POST", &route, Some(label))? {
+            return Ok(());
+        }
+        let _: Value = rate_limit::post(&self.0, route, Some(label)).await?;
+        Ok(())
+    }
+
+    /// https://docs.github.com/en/rest/issues/labels#update-a-label
+    pub async fn update_label(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        label_name: &str,
+        label: &impl Serialize,
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let label_name = encode_path_segment(label_name);
+        let route = format!("repos/{owner}/{name}/labels/{label_name}");
+        if self.dry_run("PATCH", &route, Some(label))? {
+            return Ok(());
+        }
+        let _: Value = rate_limit::patch(&self.0, route, Some(label)).await?;
+        Ok(())
+    }
+
+    /// https://docs.github.com/en/rest/issues/labels#delete-a-label
+    pub async fn delete_label(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        label_name: &str,
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let label_name = encode_path_segment(label_name);
+        self.send_without_content(
+            http::Method::DELETE,
+            &format!("repos/{owner}/{name}/labels/{label_name}"),
+        )
+        .await
+    }
+
+    /// https://docs.github.com/en/rest/deploy-keys#list-deploy-keys
+    pub fn list_deploy_keys<'a>(
+        &'a self,
+        repo_id: &impl IsRepositoryId,
+    ) -> impl Stream<Item = Result<GhDeployKey, Error>> + 'a {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.list_pages(format!("repos/{owner}/{name}/keys"))
+    }
+
+    /// Adds a deploy key, returns `None` on dry run.
+    ///
+    /// https://docs.github.com/en/rest/deploy-keys#create-a-deploy-key
+    pub async fn create_deploy_key(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        key: &GhNewDeployKey<'_>,
+    ) -> Result<Option<GhDeployKey>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}/keys");
+        if self.dry_run("POST", &route, Some(key))? {
+            return Ok(None);
+        }
+        let key = rate_limit::post(&self.0, route, Some(key)).await?;
+        Ok(Some(key))
+    }
+
+    /// https://docs.github.com/en/rest/deploy-keys#delete-a-deploy-key
+    pub async fn delete_deploy_key(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        key_id: u64,
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.send_without_content(
+            http::Method::DELETE,
+            &format!("repos/{owner}/{name}/keys/{key_id}"),
+        )
+        .await
+    }
+
+    /// Lists collaborators added to a repository, without the ones having access through an
+    /// organization.
+    ///
+    /// https://docs.github.com/en/rest/collaborators/collaborators#list-repository-collaborators
+    pub fn list_collaborators<'a>(
+        &'a self,
+        repo_id: &impl IsRepositoryId,
+    ) -> impl Stream<Item = Result<GhCollaborator, Error>> + 'a {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.list_pages(format!(
+            "repos/{owner}/{name}/collaborators?affiliation=direct"
+        ))
+    }
+
+    /// Invites a user to collaborate on a repository, or changes the permission of a
+    /// collaborator.
+    ///
+    /// Returns the invitation, `None` when the user already is a collaborator or on dry run.
+    ///
+    /// https://docs.github.com/en/rest/collaborators/collaborators#add-a-repository-collaborator
+    pub async fn add_collaborator(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        username: &str,
+        permission: &str,
+    ) -> Result<Option<GhRepositoryInvitation>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}/collaborators/{username}");
+        let body = json!({ "permission": permission });
+        if self.dry_run("PUT", &route, Some(&body))? {
+            return Ok(None);
+        }
+        let invitation = rate_limit::put_optional(&self.0, route, Some(&body)).await?;
+        Ok(invitation)
+    }
+
+    /// https://docs.github.com/en/rest/collaborators/collaborators#remove-a-repository-collaborator
+    pub async fn remove_collaborator(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        username: &str,
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.send_without_content(
+            http::Method::DELETE,
+            &format!("repos/{owner}/{name}/collaborators/{username}"),
+        )
+        .await
+    }
+
+    /// https://docs.github.com/en/rest/collaborators/invitations#list-repository-invitations
+    pub fn list_repository_invitations<'a>(
+        &'a self,
+        repo_id: &impl IsRepositoryId,
+    ) -> impl Stream<Item = Result<GhRepositoryInvitation, Error>> + 'a {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.list_pages(format!("repos/{owner}/{name}/invitations"))
+    }
+
+    /// https://docs.github.com/en/rest/collaborators/invitations#delete-a-repository-invitation
+    pub async fn delete_repository_invitation(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        invitation_id: u64,
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.send_without_content(
+            http::Method::DELETE,
+            &format!("repos/{owner}/{name}/invitations/{invitation_id}"),
+        )
+        .await
+    }
+
+    /// https://docs.github.com/en/rest/webhooks/repos#list-repository-webhooks
+    pub fn list_hooks<'a>(
+        &'a self,
+        repo_id: &impl IsRepositoryId,
+    ) -> impl Stream<Item = Result<GhHook, Error>> + 'a {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.list_pages(format!("repos/{owner}/{name}/hooks"))
+    }
+
+    /// Creates a webhook, returns `None` on dry run.
+    ///
+    /// https://docs.github.com/en/rest/webhooks/repos#create-a-repository-webhook
+    pub async fn create_hook(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        hook: &GhNewHook<'_>,
+    ) -> Result<Option<GhHook>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}/hooks");
+        if self.dry_run("POST", &route, Some(hook))? {
+            return Ok(None);
+        }
+        let hook = rate_limit::post(&self.0, route, Some(hook)).await?;
+        Ok(Some(hook))
+    }
+
+    /// https://docs.github.com/en/rest/webhooks/repos#delete-a-repository-webhook
+    pub async fn delete_hook(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        hook_id: u64,
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.send_without_content(
+            http::Method::DELETE,
+            &format!("repos/{owner}/{name}/hooks/{hook_id}"),
+        )
+        .await
+    }
+
+    /// Triggers a ping event to be sent to a webhook.
+    ///
+    /// https://docs.github.com/en/rest/webhooks/repos#ping-a-repository-webhook
+    pub async fn ping_hook(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        hook_id: u64,
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.send_without_content(
+            http::Method::POST,
+            &format!("repos/{owner}/{name}/hooks/{hook_id}/pings"),
+        )
+        .await
+    }
+
+    /// Replaces labels of an issue or a pull request.
+    ///
+    /// https://docs.github.com/en/rest/issues/issues#update-an-issue
+    pub async fn update_issue_labels(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        number: u64,
+        labels: &[String],
+    ) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}/issues/{number}");
+        let body = json!({ "labels": labels });
+        if self.dry_run("PATCH", &route, Some(&body))? {
+            return Ok(());
+        }
+        let _: Value = rate_limit::patch(&self.0, route, Some(&body)).await?;
+        Ok(())
+    }
+
+    /// Lists open pull requests of a repository.
+    ///
+    /// Mergeability is not computed in this listing, use [Self::get_pull_request] to get it.
+    ///
+    /// https://docs.github.com/en/rest/pulls/pulls#list-pull-requests
+    pub fn list_open_pull_requests<'a>(
+        &'a self,
+        repo_id: &impl IsRepositoryId,
+    ) -> impl Stream<Item = Result<GhPullRequest, Error>> + 'a {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.list_pages(format!("repos/{owner}/{name}/pulls?state=open"))
+    }
+
+    /// Creates a pull request, returns `None` on dry run.
+    ///
+    /// https://docs.github.com/en/rest/pulls/pulls#create-a-pull-request
+    pub async fn create_pull_request(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        pull_request: &GhNewPullRequest<'_>,
+    ) -> Result<Option<GhPullRequest>, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let route = format!("repos/{owner}/{name}/pulls");
+        if self.dry_run("POST", &route, Some(pull_request))? {
+            return Ok(None);
+        }
+        let pr = rate_limit::post(&self.0, route, Some(pull_request)).await?;
+        Ok(Some(pr))
+    }
+
+    /// https://docs.github.com/en/rest/pulls/pulls#get-a-pull-request
+    pub async fn get_pull_request(
+        &self,
+        repo_id: &impl IsRepositoryId,
+        number: u64,
+    ) -> Result<GhPullRequest, Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        let pr = rate_limit::get(
+            &self.0,
+            self.2.as_ref(),
+            format!("repos/{owner}/{name}/pulls/{number}"),
+        )
+        .await?;
+        Ok(pr)
+    }
+
+    /// Lists dependency manifests of a repository with their dependencies.
+    ///
+    /// Only the first 100 dependencies of each manifest are listed.
+    ///
+    /// https://docs.github.com/en/graphql/reference/objects#dependencygraphmanifest
+    pub async fn list_dependency_manifests(
+        &self,
+        repo_id: &impl IsRepositoryId,
+    ) -> Result<Vec<GhDependencyManifest>, Error> {
+        const QUERY: &str = "
+            query($owner: String!, $name: String!, $cursor: String) {
+                repository(owner: $owner, name: $name) {
+                    dependencyGraphManifests(first: 20, after: $cursor) {
+                        pageInfo { hasNextPage endCursor }
+                        nodes {
+                            filename
+                            dependenciesCount
+                            dependencies(first: 100) {
+                                nodes {
+                                    packageName
+                                    packageManager
+                                    requirements
+                                    repository { nameWithOwner }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        ";
+
+        #[derive(Deserialize)]
+        struct Data {
+            repository: Repository,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Repository {
+            dependency_graph_manifests: Connection<Manifest>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Manifest {
+            filename: String,
+            dependencies_count: Option<u64>,
+            dependencies: Option<Connection<GhDependency>>,
+        }
+
+        pagination::paginate(|cursor: Option<String>| async move {
+            let variables = json!({
+                "owner": repo_id.owner(),
+                "name": repo_id.name(),
+                "cursor": cursor,
+            });
+            let data: Data = self.graphql(QUERY, variables).await?;
+            let connection = data.repository.dependency_graph_manifests;
+            let manifests = connection
+                .nodes
+                .into_iter()
+                .map(|x| {
+                    let dependencies = x.dependencies.map(|x| x.nodes).unwrap_or_default();
+                    GhDependencyManifest {
+                        filename: x.filename,
+                        dependencies_count: x.dependencies_count.unwrap_or(dependencies.len() as _),
+                        dependencies,
+                    }
+                })
+                .collect();
+            Result::<_, Error>::Ok((manifests, connection.page_info.next_cursor()))
+        })
+        .try_collect()
+        .await
+    }
+
+    /// Lists repositories starred by current user, most recently starred first.
+    ///
+    /// Uses GraphQL, the REST API only tells when a repository was starred with a custom media
+    /// type.
+    ///
+    /// https://docs.github.com/en/graphql/reference/objects#starredrepositoryconnection
+    pub async fn list_stars(&self) -> Result<Vec<GhStar>, Error> {
+        const QUERY: &str = "
+            query($cursor: String) {
+                viewer {
+                    starredRepositories(
+                        first: 100,
+                        after: $cursor,
+                        orderBy: { field: STARRED_AT, direction: DESC }
+                    ) {
+                        pageInfo { hasNextPage endCursor }
+                        edges {
+                            starredAt
+                            node {
+                                name
+                                owner { login }
+                                description
+                                primaryLanguage { name }
+                                isArchived
+                                pushedAt
+                                repositoryTopics(first: 20) {
+                                    nodes { topic { name } }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        ";
+
+        #[derive(Deserialize)]
+        struct Data {
+            viewer: Viewer,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Viewer {
+            starred_repositories: Edges,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Edges {
+            page_info: PageInfo,
+            edges: Vec<Edge>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Edge {
+            starred_at: DateTime<Utc>,
+            node: Repository,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Repository {
+            name: String,
+            owner: Owner,
+            description: Option<String>,
+            primary_language: Option<Language>,
+            is_archived: bool,
+            pushed_at: Option<DateTime<Utc>>,
+            repository_topics: Topics,
+        }
+
+        #[derive(Deserialize)]
+        struct Topics {
+            nodes: Vec<TopicNode>,
+        }
+
+        #[derive(Deserialize)]
+        struct TopicNode {
+            topic: Topic,
+        }
+
+        #[derive(Deserialize)]
+        struct Topic {
+            name: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Owner {
+            login: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Language {
+            name: String,
+        }
+
+        pagination::paginate(|cursor: Option<String>| async move {
+            let data: Data = self.graphql(QUERY, json!({ "cursor": cursor })).await?;
+            let connection = data.viewer.starred_repositories;
+            let stars = connection
+                .edges
+                .into_iter()
+                .map(|x| GhStar {
+                    owner: x.node.owner.login,
+                    name: x.node.name,
+                    description: x.node.description,
+                    language: x.node.primary_language.map(|x| x.name),
+                    starred_at: x.starred_at,
+                    archived: x.node.is_archived,
+                    pushed_at: x.node.pushed_at,
+                    topics: x
+                        .node
+                        .repository_topics
+                        .nodes
+                        .into_iter()
+                        .map(|x| x.topic.name)
+                        .collect(),
+                })
+                .collect();
+            Result::<_, Error>::Ok((stars, connection.page_info.next_cursor()))
+        })
+        .try_collect()
+        .await
+    }
+
+    /// Lists repositories starred by a user.
+    ///
+    /// https://docs.github.com/en/rest/activity/starring#list-repositories-starred-by-a-user
+    pub fn list_user_starred_repositories<'a>(
+        &'a self,
+        username: &str,
+    ) -> impl Stream<Item = Result<GhRepository, Error>> + 'a {
+        self.list_pages(format!("users/{username}/starred"))
+    }
+
+    /// Stars a repository.
+    ///
+    /// https://docs.github.com/en/rest/activity/starring#star-a-repository-for-the-authenticated-user
+    pub async fn star_repository(&self, repo_id: &impl IsRepositoryId) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.send_without_content(http::Method::PUT, &format!("user/starred/{owner}/{name}"))
+            .await
+    }
+
+    /// Unstars a repository.
+    ///
+    /// https://docs.github.com/en/rest/activity/starring#unstar-a-repository-for-the-authenticated-user
+    pub async fn unstar_repository(&self, repo_id: &impl IsRepositoryId) -> Result<(), Error> {
+        let owner = repo_id.owner();
+        let name = repo_id.name();
+        self.send_without_content(
+            http::Method::DELETE,
+            &format!("user/starred/{owner}/{name}"),
+        )
+        .await
+    }
+
+    /// Gets default branch heads of repositories and their status check rollup states, in the
+    /// same order as the repositories.
+    ///
+    /// The rollup is none for repositories that are empty or can't be found, its state is none
+    /// for commits without checks.
+    ///
+    /// https://docs.github.com/en/graphql/reference/objects#statuscheckrollup
+    pub async fn get_status_check_rollup_states(
+        &self,
+        repo_ids: &[impl IsRepositoryId],
+    ) -> Result<Vec<Option<GhStatusCheckRollup>>, Error> {
+        if repo_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // one aliased field per repository
+        let mut params = Vec::with_capacity(repo_ids.len());
+        let mut fields = String::new();
+        let mut variables = serde_json::Map::new();
+        for (idx, repo_id) in repo_ids.iter().enumerate() {
+            params.push(format!("$owner{idx}: String!, $name{idx}: String!"));
+            writeln!(
+                fields,
+                "r{idx}: repository(owner: $owner{idx}, name: $name{idx}) {{
+                    defaultBranchRef {{
+                        target {{ ... on Commit {{ oid statusCheckRollup {{ state }} }} }}
+                    }}
+                }}"
+            )
+            .unwrap();
+            variables.insert(format!("owner{idx}"), repo_id.owner().into());
+            variables.insert(format!("name{idx}"), repo_id.name().into());
+        }
+        let query = format!("query({}) {{\n{}}}", params.join(", "), fields);
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Repository {
+            default_branch_ref: Option<Ref>,
+        }
+
+        #[derive(Deserialize)]
+        struct Ref {
+            target: Target,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Target {
+            oid: String,
+            #[serde(default)]
+            status_check_rollup: Option<Rollup>,
+        }
+
+        #[derive(Deserialize)]
+        struct Rollup {
+            state: String,
+        }
+
+        let (mut data, errors): (HashMap<String, Option<Repository>>, _) = self
+            .graphql_partial(&query, Value::Object(variables))
+            .await?;
+        log_graphql_errors(&errors);
+        let states = (0..repo_ids.len())
+            .map(|idx| {
+                data.remove(&format!("r{idx}"))
+                    .flatten()
+                    .and_then(|x| x.default_branch_ref)
+                    .map(|x| GhStatusCheckRollup {
+                        sha: x.target.oid,
+                        state: x.target.status_check_rollup.map(|x| x.state),
+                    })
+            })
+            .collect();
+        Ok(states)
+    }
+
+    /// Gets open pull requests and unmerged branches counts of repositories, in the same order as
+    /// the repositories.
+    ///
+    /// The activity is none for repositories that can't be found or have no default branch.
+    /// Branches are compared to the default branch in a second query, once its name is known.
+    ///
+    /// https://docs.github.com/en/graphql/reference/objects#comparison
+    pub async fn get_repository_activities(
+        &self,
+        repo_ids: &[impl IsRepositoryId],
+    ) -> Result<Vec<Option<GhRepositoryActivity>>, Error> {
+        if repo_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Repository {
+            pull_requests: Count,
+            default_branch_ref: Option<DefaultBranch>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Count {
+            total_count: u64,
+        }
+
+        #[derive(Deserialize)]
+        struct DefaultBranch {
+            name: String,
+        }
+
+        // one aliased field per repository
+        let mut params = Vec::with_capacity(repo_ids.len());
+        let mut fields = String::new();
+        let mut variables = serde_json::Map::new();
+        for (idx, repo_id) in repo_ids.iter().enumerate() {
+            params.push(format!("$owner{idx}: String!, $name{idx}: String!"));
+            writeln!(
+                fields,
+                "r{idx}: repository(owner: $owner{idx}, name: $name{idx}) {{
+                    pullRequests(states: OPEN) {{ totalCount }}
+                    defaultBranchRef {{ name }}
+                }}"
+            )
+            .unwrap();
+            variables.insert(format!("owner{idx}"), repo_id.owner().into());
+            variables.insert(format!("name{idx}"), repo_id.name().into());
+        }
+        let query = format!("query({}) {{\n{}}}", params.join(", "), fields);
+        let (mut data, errors): (HashMap<String, Option<Repository>>, _) = self
+            .graphql_partial(&query, Value::Object(variables))
+            .await?;
+        log_graphql_errors(&errors);
+        let repos: Vec<_> = (0..repo_ids.len())
+            .map(|idx| data.remove(&format!("r{idx}")).flatten())
+            .collect();
+
+        #[derive(Deserialize)]
+        struct Refs {
+            refs: Connection<Ref>,
+        }
+
+        #[derive(Deserialize)]
+        struct Ref {
+            compare: Option<Comparison>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Comparison {
+            /// Commits of the branch missing from the default branch.
+            behind_by: u64,
+        }
+
+        // the branch is the base, the default branch is the head of the comparison
+        let mut params = Vec::new();
+        let mut fields = String::new();
+        let mut variables = serde_json::Map::new();
+        for (idx, (repo_id, repo)) in repo_ids.iter().zip(&repos).enumerate() {
+            let default_branch = match repo.as_ref().and_then(|x| x.default_branch_ref.as_ref()) {
+                Some(x) => &x.name,
+                None => continue,
+            };
+            params.push(format!(
+                "$owner{idx}: String!, $name{idx}: String!, $default{idx}: String!"
+            ));
+            writeln!(
+                fields,
+                "r{idx}: repository(owner: $owner{idx}, name: $name{idx}) {{
+                    refs(refPrefix: \"refs/heads/\", first: 100) {{
+                        nodes {{ compare(headRef: $default{idx}) {{ behindBy }} }}
+                    }}
+                }}"
+            )
+            .unwrap();
+            variables.insert(format!("owner{idx}"), repo_id.owner().into());
+            variables.insert(format!("name{idx}"), repo_id.name().into());
+            variables.insert(format!("default{idx}"), default_branch.as_str().into());
+        }
+        let mut data: HashMap<String, Option<Refs>> = if params.is_empty() {
+            HashMap::new()
+        } else {
+            let query = format!("query({}) {{\n{}}}", params.join(", "), fields);
+            let (data, errors) = self
+                .graphql_partial(&query, Value::Object(variables))
+                .await?;
+            log_graphql_errors(&errors);
+            data
+        };
+
+        let activities = repos
+            .into_iter()
+            .enumerate()
+            .map(|(idx, repo)| {
+                let repo = repo.filter(|x| x.default_branch_ref.is_some())?;
+                let refs = data.remove(&format!("r{idx}")).flatten()?;
+                let unmerged_branches = refs
+                    .refs
+                    .nodes
+                    .iter()
+                    .filter_map(|x| x.compare.as_ref())
+                    .filter(|x| x.behind_by > 0)
+                    .count() as u64;
+                Some(GhRepositoryActivity {
+                    open_pull_requests: repo.pull_requests.total_count,
+                    unmerged_branches,
+                })
+            })
+            .collect();
+        Ok(activities)
+    }
+
+    /// Sends a request whose response has no content, e.g. `204 No Content`.
+    async fn send_without_content(&self, method: http::Method, route: &str) -> Result<(), Error> {
+        if self.dry_run(method.as_str(), route, None::<&()>)? {
+            return Ok(());
+        }
+        rate_limit::send_without_content(&self.0, method, route).await
+    }
+
+    /// Sends a GraphQL query, failing on any error.
+    async fn graphql<T>(&self, query: &str, variables: Value) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let (data, errors) = self.graphql_partial(query, variables).await?;
+        if let Some(err) = errors.first() {
+            bail!("GraphQL query failed, {}", err.message)
+        }
+        Ok(data)
+    }
+
+    /// Sends a GraphQL query, returns the data with the errors of the fields that failed, e.g.
+    /// of aliased repositories that can't be found, which are null in the data.
+    ///
+    /// Fails only when there is no data.
+    async fn graphql_partial<T>(
+        &self,
+        query: &str,
+        variables: Value,
+    ) -> Result<(T, Vec<GhGraphqlError>), Error>
+    where
+        T: DeserializeOwned,
+    {
+        #[derive(Deserialize)]
+        struct Envelope<D> {
+            data: Option<D>,
+            #[serde(default)]
+            errors: Vec<GhGraphqlError>,
+        }
+
+        let response: Envelope<T> = rate_limit::post(
+            &self.0,
+            "graphql",
+            Some(&json!({ "query": query, "variables": variables })),
+        )
+        .await?;
+        match response.data {
+            Some(data) => Ok((data, response.errors)),
+            None => match response.errors.first() {
+                Some(err) => bail!("GraphQL query failed, {}", err.message),
+                None => bail!("GraphQL response has no data"),
+            },
+        }
+    }
+
+    /// Streams items of a paginated endpoint.
+    fn list_pages<'a, T>(&'a self, route: String) -> impl Stream<Item = Result<T, Error>> + 'a
+    where
+        T: DeserializeOwned + 'a,
+    {
+        pagination::follow_links(&self.0, self.2.as_ref(), route)
+    }
+}
+
+/// Prints a request that would have been sent.
+///
+/// Dry run output goes to stderr, like the rest of it, keeping the output of the command clean.
+pub(crate) fn print_dry_run_request(
+    method: &str,
+    url: &reqwest::Url,
+    body: Option<&impl Serialize>,
+) -> Result<(), Error> {
+    eprintln!("[dry-run] {method} {url}");
+    if let Some(body) = body {
+        eprintln!("{}", serde_json::to_string_pretty(body)?);
+    }
+    Ok(())
+}
+
+/// Percent-encodes a path segment, e.g. a label name with spaces.
+fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for b in segment.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(b as char)
+            }
+            _ => write!(encoded, "%{:02X}", b).expect("writing to a string should not fail"),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+#[test]
+fn test_encode_path_segment() {
+    assert_eq!(encode_path_segment("bug"), "bug");
+    assert_eq!(
+        encode_path_segment("good first issue"),
+        "good%20first%20issue"
+    );
+    assert_eq!(encode_path_segment("type: bug/fix"), "type%3A%20bug%2Ffix");
+}
+
+/// Parses comma separated scopes of `x-oauth-scopes`.
+fn parse_scopes(header: &str) -> Vec<String> {
+    header
+        .split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_scopes() {
+    assert_eq!(
+        parse_scopes("delete_repo, repo, read:org"),
+        ["delete_repo", "repo", "read:org"]
+    );
+    assert!(parse_scopes("").is_empty());
+}
+
+/// Decodes base64 encoded content of a file.
+fn decode_content(content: GhContent) -> Result<Vec<u8>, Error> {
+    if content.encoding != "base64" {
+        bail!(
+            "unexpected encoding for `{}`, was `{}`",
+            content.path,
+            content.encoding
+        )
+    }
+    // the encoded content is wrapped at 60 characters
+    let encoded: String = content
+        .content
+        .chars()
+        .filter(|x| !x.is_whitespace())
+        .collect();
+    let content = base64::decode(encoded)?;
+    Ok(content)
+}
+
+/// Logs errors of fields of a GraphQL response that still has data, e.g. repositories that can't
+/// be found.
+fn log_graphql_errors(errors: &[GhGraphqlError]) {
+    for err in errors {
+        debug!(message = %err.message, "GraphQL field failed.");
+    }
+}
+
+/// Returns true if the error is GitHub's not found response.
+fn is_not_found(err: &Error) -> bool {
+    rate_limit::error_status(err) == Some(http::StatusCode::NOT_FOUND)
+}
+
+/// GraphQL connection.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Connection<T> {
+    #[serde(default)]
+    page_info: PageInfo,
+    nodes: Vec<T>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+impl PageInfo {
+    /// Returns the cursor of the next page, none at the last page.
+    fn next_cursor(self) -> Option<String> {
+        self.end_cursor.filter(|_| self.has_next_page)
+    }
 }
@@ -1,7 +1,9 @@
 use crate::repository_id::IsPartialRepositoryId;
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
+pub use octocrab::models::Label as GhLabel;
 pub use octocrab::models::Repository as GhRepository;
 
 impl IsPartialRepositoryId for GhRepository {
@@ -63,12 +65,66 @@ pub struct GhCheckRunOutput {
     pub text: Option<String>,
 }
 
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhWorkflowRun {
+    pub id: u64,
+    pub name: Option<String>,
+    pub head_branch: Option<String>,
+    pub status: Option<String>,
+    pub conclusion: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhNotification {
+    /// Thread id.
+    pub id: String,
+    pub reason: String,
+    pub updated_at: DateTime<Utc>,
+    pub subject: GhNotificationSubject,
+    pub repository: GhNotificationRepository,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhNotificationSubject {
+    pub title: String,
+    /// E.g. `Issue`, `PullRequest`, or `Release`.
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhNotificationRepository {
+    pub full_name: String,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhRateLimit {
+    /// Rate limits by resource, e.g. `core`, `search`, and `graphql`.
+    pub resources: BTreeMap<String, GhRateLimitResource>,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhRateLimitResource {
+    pub limit: u64,
+    pub used: u64,
+    pub remaining: u64,
+    /// Seconds since the Unix epoch.
+    pub reset: i64,
+}
+
 #[derive(Deserialize, PartialEq, Clone, Debug)]
 pub struct GhIssue {
     #[serde(flatten)]
     pub inner: octocrab::models::issues::Issue,
 
-    pub repository: GhIssueRepository,
+    /// Missing from search results.
+    #[serde(default)]
+    pub repository: Option<GhIssueRepository>,
+
+    /// Whether it's a pull request found by requesting my review, not part of the response.
+    #[serde(skip)]
+    pub review_requested: bool,
 }
 
 #[derive(Deserialize, PartialEq, Clone, Debug)]
@@ -76,3 +132,274 @@ pub struct GhIssueRepository {
     pub name: String,
     pub full_name: String,
 }
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhEvent {
+    pub id: String,
+    pub r#type: String,
+    pub actor: GhEventActor,
+    pub repo: GhEventRepository,
+    #[serde(default)]
+    pub payload: GhEventPayload,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhEventActor {
+    pub login: String,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhEventRepository {
+    pub name: String,
+    /// API URL of the repository.
+    pub url: String,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Default, Debug)]
+pub struct GhEventPayload {
+    pub action: Option<String>,
+    pub ref_type: Option<String>,
+    pub release: Option<GhRelease>,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhRelease {
+    pub tag_name: String,
+    pub name: Option<String>,
+    pub html_url: String,
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhContent {
+    pub path: String,
+    pub encoding: String,
+    pub content: String,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhCodeownersError {
+    pub line: u32,
+    pub column: u32,
+    pub kind: String,
+    pub source: String,
+    pub suggestion: Option<String>,
+    pub message: String,
+    pub path: String,
+}
+
+/// Files and directories of a git tree.
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhTree {
+    pub tree: Vec<GhTreeEntry>,
+    /// Whether entries were left out, the API limits the number of entries.
+    pub truncated: bool,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhTreeEntry {
+    pub path: String,
+    /// `blob` for files, `tree` for directories, `commit` for submodules.
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhPullRequest {
+    pub number: u64,
+    pub title: String,
+    pub html_url: String,
+    pub user: Option<GhUser>,
+    pub head: GhPullRequestRef,
+    pub base: GhPullRequestRef,
+    #[serde(default)]
+    pub draft: bool,
+    pub mergeable: Option<bool>,
+    pub mergeable_state: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Latest commit of the default branch of a repository and the combined state of its checks.
+#[derive(PartialEq, Clone, Debug)]
+pub struct GhStatusCheckRollup {
+    pub sha: String,
+    /// None when the commit has no checks.
+    pub state: Option<String>,
+}
+
+/// Open pull requests and unmerged branches of a repository.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct GhRepositoryActivity {
+    pub open_pull_requests: u64,
+    /// Branches with commits not merged to the default branch, of the first 100 branches.
+    pub unmerged_branches: u64,
+}
+
+#[derive(Serialize, PartialEq, Clone, Debug)]
+pub struct GhNewRepository<'a> {
+    pub name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<&'a str>,
+    pub private: bool,
+    /// Creates the repository with an initial commit, needed for the license and gitignore.
+    pub auto_init: bool,
+    /// License keyword, e.g. `mit`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license_template: Option<&'a str>,
+    /// Gitignore template name, e.g. `Rust`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gitignore_template: Option<&'a str>,
+}
+
+#[derive(Serialize, PartialEq, Clone, Debug)]
+pub struct GhNewIssue<'a> {
+    pub title: &'a str,
+    pub body: &'a str,
+    pub labels: &'a [String],
+    pub assignees: &'a [&'a str],
+}
+
+#[derive(Serialize, PartialEq, Clone, Debug)]
+pub struct GhNewPullRequest<'a> {
+    pub title: &'a str,
+    pub body: &'a str,
+    /// Branch to merge.
+    pub head: &'a str,
+    /// Branch to merge into.
+    pub base: &'a str,
+    pub draft: bool,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhPullRequestRef {
+    pub r#ref: String,
+    pub sha: String,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhGraphqlError {
+    pub message: String,
+}
+
+/// Repository starred by current user.
+#[derive(PartialEq, Clone, Debug)]
+pub struct GhStar {
+    pub owner: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub language: Option<String>,
+    pub starred_at: DateTime<Utc>,
+    pub archived: bool,
+    pub pushed_at: Option<DateTime<Utc>>,
+    pub topics: Vec<String>,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct GhDependencyManifest {
+    pub filename: String,
+    pub dependencies_count: u64,
+    pub dependencies: Vec<GhDependency>,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GhDependency {
+    pub package_name: String,
+    pub package_manager: Option<String>,
+    pub requirements: String,
+    /// Repository of the package, when GitHub knows it.
+    pub repository: Option<GhDependencyRepository>,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GhDependencyRepository {
+    pub name_with_owner: String,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhBranchProtection {
+    pub required_status_checks: Option<GhRequiredStatusChecks>,
+    pub required_pull_request_reviews: Option<GhRequiredPullRequestReviews>,
+    pub enforce_admins: Option<GhEnabled>,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhRequiredStatusChecks {
+    pub strict: bool,
+    #[serde(default)]
+    pub contexts: Vec<String>,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhRequiredPullRequestReviews {
+    #[serde(default)]
+    pub required_approving_review_count: u32,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhEnabled {
+    pub enabled: bool,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhDeployKey {
+    pub id: u64,
+    pub key: String,
+    pub title: String,
+    pub read_only: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, PartialEq, Clone, Debug)]
+pub struct GhNewDeployKey<'a> {
+    pub title: &'a str,
+    pub key: &'a str,
+    pub read_only: bool,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhHook {
+    pub id: u64,
+    pub active: bool,
+    pub events: Vec<String>,
+    pub config: GhHookConfig,
+    pub last_response: Option<GhHookResponse>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct GhHookConfig {
+    pub url: Option<String>,
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhHookResponse {
+    pub code: Option<u16>,
+    pub status: Option<String>,
+}
+
+#[derive(Serialize, PartialEq, Clone, Debug)]
+pub struct GhNewHook<'a> {
+    pub config: GhHookConfig,
+    pub events: &'a [String],
+    pub active: bool,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhCollaborator {
+    pub login: String,
+    pub role_name: Option<String>,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+pub struct GhRepositoryInvitation {
+    pub id: u64,
+    pub invitee: Option<GhUser>,
+    pub permissions: String,
+    pub created_at: DateTime<Utc>,
+}
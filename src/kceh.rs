@@ -0,0 +1,94 @@
+//! Case transformations, e.g. `snake_case` to `Sentence case`.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Words of an identifier, lowercased.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Words(Vec<String>);
+
+impl Words {
+    /// Splits `snake_case` and `SCREAMING_SNAKE_CASE`.
+    pub fn from_snake_case(text: &str) -> Self {
+        Self::split(text, &['_'])
+    }
+
+    /// Splits `kebab-case`.
+    pub fn from_kebab_case(text: &str) -> Self {
+        Self::split(text, &['-'])
+    }
+
+    /// Splits any of the supported cases.
+    pub fn from_any_case(text: &str) -> Self {
+        Self::split(text, &['_', '-'])
+    }
+
+    fn split(text: &str, separators: &[char]) -> Self {
+        let words = text
+            .split(separators)
+            .filter(|x| !x.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        Self(words)
+    }
+
+    /// Joins words as `Title Case`.
+    pub fn to_title_case(&self) -> String {
+        self.0
+            .iter()
+            .map(|x| capitalize(x))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Joins words as `Sentence case`.
+    pub fn to_sentence_case(&self) -> String {
+        let text = self.0.join(" ");
+        capitalize(&text)
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    text.graphemes(true)
+        .enumerate()
+        .map(|(i, c)| if i == 0 { c.to_uppercase() } else { c.into() })
+        .collect()
+}
+
+/// Humanizes an identifier, e.g. a check run conclusion, as `Sentence case`.
+///
+/// Text that is not an identifier, i.e. that has whitespaces, is kept as is.
+pub fn humanize(text: &str) -> String {
+    if text.contains(char::is_whitespace) {
+        return text.to_owned();
+    }
+    Words::from_any_case(text).to_sentence_case()
+}
+
+#[cfg(test)]
+#[test]
+fn test_words() {
+    assert_eq!(
+        Words::from_snake_case("hello_world").to_sentence_case(),
+        "Hello world"
+    );
+    assert_eq!(
+        Words::from_snake_case("ACTION_REQUIRED").to_title_case(),
+        "Action Required"
+    );
+    assert_eq!(
+        Words::from_kebab_case("release-please").to_title_case(),
+        "Release Please"
+    );
+    assert_eq!(
+        Words::from_any_case("timed_out-twice").to_sentence_case(),
+        "Timed out twice"
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_humanize() {
+    assert_eq!(humanize("timed_out"), "Timed out");
+    assert_eq!(humanize("deploy-docs"), "Deploy docs");
+    assert_eq!(humanize("CI / build (ubuntu)"), "CI / build (ubuntu)");
+}
@@ -1,22 +1,35 @@
 mod app;
 mod app2;
 mod app_env;
+mod cassette;
 mod cli;
 mod commands;
+mod config;
 mod database;
 mod display;
+mod error;
 mod github_client;
 mod github_client2;
 mod github_models;
+mod kceh;
+mod output;
+mod pagination;
+mod process;
+mod rate_limit;
 mod repository_id;
+mod token;
 mod types;
 
 /// Run application;
 pub use crate::app2::start as start_app;
+pub use crate::error::ShubError;
 
 use crate::github_models::{GhCommit, GhRepository};
 use repository_id::FullRepoId;
-use std::path::{Path, PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 fn create_local_repository_path(
     workspace_root_dir: impl AsRef<Path>,
@@ -29,6 +42,97 @@ fn create_local_repository_path(
         .join(&repo_id.name)
 }
 
+/// Lists paths of local repositories, i.e. directories in `workspace_root_dir/:owner/:name`
+/// that have a `.git` directory.
+fn list_local_repository_paths(
+    workspace_root_dir: impl AsRef<Path>,
+) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut paths = Vec::new();
+    for owner_dir in fs::read_dir(workspace_root_dir)? {
+        let owner_dir = owner_dir?.path();
+        if !owner_dir.is_dir() {
+            continue;
+        }
+        for repo_dir in fs::read_dir(owner_dir)? {
+            let repo_dir = repo_dir?.path();
+            if repo_dir.join(".git").exists() {
+                paths.push(repo_dir);
+            }
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Lists paths of projects, i.e. directories that have a `.git` directory, in roots and in their
+/// directories up to `depth` levels deep.
+///
+/// Hidden directories and directories of projects aren't searched.
+fn find_project_paths(
+    roots: &[impl AsRef<Path>],
+    depth: usize,
+) -> Result<Vec<PathBuf>, std::io::Error> {
+    fn visit(dir: &Path, depth: usize, paths: &mut Vec<PathBuf>) -> Result<(), std::io::Error> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let hidden = path
+                .file_name()
+                .map(|x| x.to_string_lossy().starts_with('.'))
+                .unwrap_or_default();
+            if hidden || !path.is_dir() {
+                continue;
+            }
+            if path.join(".git").exists() {
+                paths.push(path);
+            } else if depth > 1 {
+                visit(&path, depth - 1, paths)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut paths = Vec::new();
+    if depth > 0 {
+        for root in roots {
+            visit(root.as_ref(), depth, &mut paths)?;
+        }
+    }
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+#[cfg(test)]
+#[test]
+fn test_find_project_paths() {
+    let ws = tempfile::tempdir().unwrap();
+    let other = tempfile::tempdir().unwrap();
+    for dir in [
+        ws.path().join("kafji/shub/.git"),
+        ws.path().join("kafji/shub/vendor/dep/.git"),
+        ws.path().join("kafji/notes"),
+        ws.path().join("gitlab/group/sub/deep/.git"),
+        ws.path().join(".cache/x/.git"),
+        other.path().join("scratch/.git"),
+    ] {
+        fs::create_dir_all(dir).unwrap();
+    }
+
+    let paths = find_project_paths(&[ws.path(), other.path()], 2).unwrap();
+    let mut expected = vec![ws.path().join("kafji/shub"), other.path().join("scratch")];
+    expected.sort();
+    assert_eq!(paths, expected);
+
+    let paths = find_project_paths(&[ws.path()], 4).unwrap();
+    assert_eq!(
+        paths,
+        [
+            ws.path().join("gitlab/group/sub/deep"),
+            ws.path().join("kafji/shub")
+        ]
+    );
+}
+
 #[derive(PartialEq, Clone, Debug)]
 struct StarredRepository(GhRepository);
 
@@ -1,11 +1,12 @@
 use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
 
 #[tokio::main]
-async fn main() {
+async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::from_default_env())
         .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
         .with_thread_ids(true)
         .init();
-    shub::start_app().await.unwrap();
+    shub::start_app().await?;
+    Ok(())
 }
@@ -0,0 +1,124 @@
+//! Defines destination of commands' primary output.
+
+use crate::cli::OutputFormat;
+use anyhow::{Context, Error};
+use serde::Serialize;
+use std::{
+    cell::RefCell,
+    fmt, fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+/// Destination of commands' primary output, e.g. tables and reports.
+///
+/// Writes go to stdout, or when a file is given, are buffered and written to the file on
+/// [Output::finish].
+pub struct Output {
+    file: Option<PathBuf>,
+    format: OutputFormat,
+    buf: RefCell<Vec<u8>>,
+}
+
+impl Output {
+    pub fn new(file: Option<PathBuf>, format: OutputFormat) -> Self {
+        Self {
+            file,
+            format,
+            buf: Default::default(),
+        }
+    }
+
+    /// Format of listings.
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Writes records as a JSON array, as a JSON object per line, or as CSV, following the
+    /// format.
+    ///
+    /// Returns `false` without writing anything when the format is [OutputFormat::Table], the
+    /// caller prints its table instead.
+    pub fn write_records<T: Serialize>(&self, records: &[T]) -> Result<bool, Error> {
+        let mut out = self;
+        match self.format {
+            OutputFormat::Table => return Ok(false),
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut out, records)?;
+                writeln!(out)?;
+            }
+            OutputFormat::Ndjson => {
+                for x in records {
+                    serde_json::to_writer(&mut out, x)?;
+                    writeln!(out)?;
+                }
+            }
+            OutputFormat::Csv => {
+                let mut w = csv::Writer::from_writer(out);
+                for x in records {
+                    w.serialize(x)?;
+                }
+                w.flush()?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Writes a record following the format, like [Output::write_records].
+    pub fn write_record<T: Serialize>(&self, record: &T) -> Result<bool, Error> {
+        let mut out = self;
+        match self.format {
+            OutputFormat::Table => return Ok(false),
+            OutputFormat::Json => serde_json::to_writer_pretty(&mut out, record)?,
+            OutputFormat::Ndjson => serde_json::to_writer(&mut out, record)?,
+            OutputFormat::Csv => return self.write_records(&[record]),
+        }
+        writeln!(out)?;
+        Ok(true)
+    }
+
+    /// Writes buffered output to the file, if there is one.
+    ///
+    /// The output is written to a temporary file next to the target and then renamed, so the
+    /// target is never left half written.
+    pub fn finish(self) -> Result<(), Error> {
+        let path = match self.file {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+        let file_name = path
+            .file_name()
+            .with_context(|| format!("Invalid output path {}.", path.display()))?;
+        let tmp_path = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+        fs::write(&tmp_path, self.buf.into_inner())
+            .with_context(|| format!("Failed to write {}.", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("Failed to write {}.", path.display()))?;
+        Ok(())
+    }
+}
+
+impl Write for &Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.file {
+            Some(_) => self.buf.borrow_mut().write(buf),
+            None => io::stdout().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file {
+            Some(_) => Ok(()),
+            None => io::stdout().flush(),
+        }
+    }
+}
+
+impl fmt::Debug for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Output")
+            .field("file", &self.file)
+            .field("format", &self.format)
+            .finish()
+    }
+}
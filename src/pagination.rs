@@ -0,0 +1,86 @@
+//! Defines pagination of GitHub listings, shared by the GitHub clients.
+//!
+//! REST listings follow the `next` link of the `Link` header of their pages instead of counting
+//! page numbers, so listings paginated by `since` or by cursor work the same as the ones
+//! paginated by page number, and there's no limit on the number of pages.
+
+use crate::{
+    database::HttpCache,
+    rate_limit::{self, ApiClient},
+};
+use anyhow::Error;
+use futures::{stream, Future, Stream, StreamExt, TryStreamExt};
+use octocrab::Page;
+use serde::de::DeserializeOwned;
+
+/// Number of items requested per page, the maximum of most listings.
+pub const PER_PAGE: u32 = 100;
+
+/// Streams items of a paginated listing.
+///
+/// `fetch` gets the page at a cursor, the first page when none, and returns its items with the
+/// cursor of the next page, none at the last page.
+pub fn paginate<'a, C, T, F, Fut>(fetch: F) -> impl Stream<Item = Result<T, Error>> + 'a
+where
+    C: 'a,
+    T: 'a,
+    F: Fn(Option<C>) -> Fut + 'a,
+    Fut: Future<Output = Result<(Vec<T>, Option<C>), Error>> + 'a,
+{
+    // none when past the last page
+    stream::try_unfold(Some(None), move |cursor: Option<Option<C>>| {
+        let page = cursor.map(&fetch);
+        async move {
+            match page {
+                Some(page) => {
+                    let (items, next) = page.await?;
+                    Ok(Some((items, next.map(Some))))
+                }
+                None => Result::<_, Error>::Ok(None),
+            }
+        }
+    })
+    .map_ok(|x| stream::iter(x).map(Result::<_, Error>::Ok))
+    .try_flatten()
+}
+
+/// Streams items of a REST listing, starting at `route` with the maximum page size and following
+/// the `next` links.
+pub fn follow_links<'a, T>(
+    client: &'a ApiClient,
+    cache: Option<&'a HttpCache>,
+    route: String,
+) -> impl Stream<Item = Result<T, Error>> + 'a
+where
+    T: DeserializeOwned + 'a,
+{
+    let sep = if route.contains('?') { '&' } else { '?' };
+    let first = format!("{route}{sep}per_page={PER_PAGE}");
+    paginate(move |next: Option<String>| {
+        let route = next.unwrap_or_else(|| first.clone());
+        async move {
+            let page: Page<T> = rate_limit::get(client, cache, route).await?;
+            Result::<_, Error>::Ok(split_page(page))
+        }
+    })
+}
+
+/// Returns items of a page and the URL of the next page.
+fn split_page<T>(mut page: Page<T>) -> (Vec<T>, Option<String>) {
+    let items = page.take_items();
+    (items, page.next.map(String::from))
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_paginate() {
+    // more pages than fit in a u8
+    let items: Vec<u32> = paginate(|cursor: Option<u32>| async move {
+        let page = cursor.unwrap_or_default();
+        Result::<_, Error>::Ok((vec![page], (page < 299).then_some(page + 1)))
+    })
+    .try_collect()
+    .await
+    .unwrap();
+    assert_eq!(items, (0..300).collect::<Vec<_>>());
+}
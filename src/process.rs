@@ -0,0 +1,71 @@
+//! Cross-platform helpers for running external programs.
+
+use anyhow::{bail, Context, Error};
+use std::process::Command;
+
+/// Runs a program in place of shub, replacing the process with `exec`.
+///
+/// Returns only if the program fails to start.
+#[cfg(unix)]
+pub fn hand_over(mut cmd: Command) -> Result<(), Error> {
+    use std::os::unix::process::CommandExt;
+
+    let err = cmd.exec();
+    Err(err).with_context(|| format!("Failed to run {}.", cmd.get_program().to_string_lossy()))
+}
+
+/// Runs a program in the foreground and exits with its exit status once it's done.
+///
+/// This stands in for `exec`, which is not available outside of Unix.
+#[cfg(not(unix))]
+pub fn hand_over(mut cmd: Command) -> Result<(), Error> {
+    let status = tokio::task::block_in_place(|| cmd.status())
+        .with_context(|| format!("Failed to run {}.", cmd.get_program().to_string_lossy()))?;
+    std::process::exit(status.code().unwrap_or(1))
+}
+
+/// Creates a command running a command line with the platform's shell.
+pub fn shell(command_line: &str) -> Command {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+    cmd.arg(command_line);
+    cmd
+}
+
+/// Opens an URL with the platform's default handler.
+pub fn open_url(url: &str) -> Result<(), Error> {
+    let status = opener(url).status()?;
+    if !status.success() {
+        bail!("Failed to open {}.", url)
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn opener(url: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    // the empty argument is the window title, start treats the first quoted argument as one
+    cmd.args(["/C", "start", ""]).arg(url);
+    cmd
+}
+
+#[cfg(target_os = "macos")]
+fn opener(url: &str) -> Command {
+    let mut cmd = Command::new("open");
+    cmd.arg(url);
+    cmd
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn opener(url: &str) -> Command {
+    let mut cmd = Command::new("xdg-open");
+    cmd.arg(url);
+    cmd
+}
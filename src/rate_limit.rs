@@ -0,0 +1,492 @@
+//! Defines requests to GitHub that wait and retry when they hit a rate limit.
+//!
+//! Both GitHub clients send their requests through here, so paginated listings resume from the
+//! page that was rate limited instead of failing. GET requests can also be made conditional
+//! with an [HttpCache]. Requests are recorded or replayed by the [Cassette] of the task, if any.
+
+use crate::{
+    cassette::Cassette,
+    database::{CachedResponse, HttpCache},
+};
+use anyhow::bail;
+use http::{header, HeaderMap, HeaderValue, StatusCode};
+use octocrab::{FromResponse, Octocrab};
+use reqwest::{Method, Proxy, RequestBuilder, Url};
+use serde::Serialize;
+use std::{
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::time;
+use tracing::warn;
+
+/// Maximum number of retries of a rate limited request.
+const MAX_RETRIES: u32 = 3;
+
+/// Wait before retrying a request hitting the secondary rate limit without `retry-after`,
+/// doubled on each retry.
+const SECONDARY_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Client of the GitHub API.
+///
+/// Octocrab resolves routes and maps responses, but its HTTP client can't be given a proxy, so
+/// requests are sent with an HTTP client of our own carrying the same headers.
+#[derive(Clone, Debug)]
+pub struct ApiClient {
+    octocrab: Octocrab,
+    http: reqwest::Client,
+}
+
+impl ApiClient {
+    /// Creates a client of the API at `api_url`, defaults to github.com, sending requests through
+    /// `proxy` when given one, otherwise through the proxy of the environment, if any.
+    ///
+    /// `previews` are names of API previews to enable, e.g. `hawkgirl`.
+    pub fn new(
+        token: &str,
+        api_url: Option<&str>,
+        proxy: Option<&str>,
+        user_agent: &str,
+        previews: &[&str],
+    ) -> Result<Self, anyhow::Error> {
+        let mut builder = Octocrab::builder();
+        if let Some(x) = api_url {
+            builder = builder.base_url(x)?;
+        }
+        let octocrab = builder.build()?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::USER_AGENT, HeaderValue::from_str(user_agent)?);
+        let mut authorization = HeaderValue::from_str(&format!("Bearer {token}"))?;
+        authorization.set_sensitive(true);
+        headers.insert(header::AUTHORIZATION, authorization);
+        headers.append(
+            header::ACCEPT,
+            HeaderValue::from_static("application/vnd.github.v3+json"),
+        );
+        for x in previews {
+            let accept = format!("application/vnd.github.{x}-preview+json");
+            headers.append(header::ACCEPT, HeaderValue::from_str(&accept)?);
+        }
+        let mut http = reqwest::Client::builder().default_headers(headers);
+        if let Some(x) = proxy {
+            http = http.proxy(Proxy::all(x)?);
+        }
+        let http = http.build()?;
+
+        Ok(Self { octocrab, http })
+    }
+
+    /// Resolves a route against the API URL, an absolute URL is kept as is.
+    pub fn absolute_url(&self, route: impl AsRef<str>) -> Result<Url, anyhow::Error> {
+        Ok(self.octocrab.absolute_url(route)?)
+    }
+
+    fn request(&self, method: Method, url: &Url) -> RequestBuilder {
+        self.http.request(method, url.clone())
+    }
+
+    /// Builds a request with a JSON body, if any.
+    fn request_with_body<B>(
+        &self,
+        method: Method,
+        url: &Url,
+        body: Option<&B>,
+    ) -> Result<RequestBuilder, anyhow::Error>
+    where
+        B: Serialize + ?Sized,
+    {
+        let mut req = self.request(method, url);
+        if let Some(x) = body {
+            req = req
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(serde_json::to_vec(x)?);
+        }
+        Ok(req)
+    }
+}
+
+/// Error response of GitHub, with the HTTP status that octocrab's error doesn't keep.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    error: octocrab::Error,
+}
+
+impl ApiError {
+    /// Gets message of the response, `None` when its body is not a GitHub error.
+    pub fn message(&self) -> Option<&str> {
+        match &self.error {
+            octocrab::Error::GitHub { source, .. } => Some(&source.message),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Gets HTTP status of an error response of GitHub, `None` when the error is not one.
+pub fn error_status(err: &anyhow::Error) -> Option<StatusCode> {
+    err.chain()
+        .find_map(|x| x.downcast_ref::<ApiError>())
+        .map(|x| x.status)
+}
+
+/// Passes through a successful response, maps an error response into an [ApiError].
+pub(crate) async fn map_github_error(
+    response: reqwest::Response,
+) -> Result<reqwest::Response, ApiError> {
+    let status = response.status();
+    octocrab::map_github_error(response)
+        .await
+        .map_err(|error| ApiError { status, error })
+}
+
+/// Sends a GET request.
+///
+/// With a cache, sends the ETag of the cached response and reuses the cached body when GitHub
+/// responds with `304 Not Modified`, which doesn't count against the rate limit.
+pub async fn get<R>(
+    client: &ApiClient,
+    cache: Option<&HttpCache>,
+    route: impl AsRef<str>,
+) -> Result<R, anyhow::Error>
+where
+    R: FromResponse,
+{
+    let url = client.absolute_url(route)?;
+    // a broken cache only makes requests slower
+    let cached = match cache.map(|x| x.get(url.as_str())) {
+        Some(Ok(x)) => x,
+        Some(Err(err)) => {
+            warn!(?err, "Failed to read cached response.");
+            None
+        }
+        None => None,
+    };
+    let response = send_with_retry(&Method::GET, &url, || {
+        let mut req = client.request(Method::GET, &url);
+        if let Some(x) = &cached {
+            req = req.header(header::IF_NONE_MATCH, &x.etag);
+        }
+        Ok(req)
+    })
+    .await?;
+    let response = match (cache, cached) {
+        (Some(_), Some(cached)) if response.status() == StatusCode::NOT_MODIFIED => {
+            response_from_cache(cached)
+        }
+        (Some(cache), _) if response.status() == StatusCode::OK => {
+            cache_response(cache, url.as_str(), response).await?
+        }
+        _ => response,
+    };
+    let response = map_github_error(response).await?;
+    Ok(R::from_response(response).await?)
+}
+
+/// Sends a GET request, returns the response body as is.
+///
+/// Redirects are followed, e.g. to the storage of workflow run logs.
+pub async fn get_bytes(
+    client: &ApiClient,
+    route: impl AsRef<str>,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let url = client.absolute_url(route)?;
+    let response =
+        send_with_retry(&Method::GET, &url, || Ok(client.request(Method::GET, &url))).await?;
+    let response = map_github_error(response).await?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Sends a GET request, returns the response headers.
+pub async fn get_headers(
+    client: &ApiClient,
+    route: impl AsRef<str>,
+) -> Result<HeaderMap, anyhow::Error> {
+    let url = client.absolute_url(route)?;
+    let response =
+        send_with_retry(&Method::GET, &url, || Ok(client.request(Method::GET, &url))).await?;
+    let response = map_github_error(response).await?;
+    Ok(response.headers().clone())
+}
+
+/// Stores a response that has an ETag, returns a response with the same body.
+async fn cache_response(
+    cache: &HttpCache,
+    url: &str,
+    response: reqwest::Response,
+) -> Result<reqwest::Response, anyhow::Error> {
+    let get_header = |name: header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|x| x.to_str().ok())
+            .map(ToOwned::to_owned)
+    };
+    let etag = match get_header(header::ETAG) {
+        Some(x) => x,
+        None => return Ok(response),
+    };
+    let link = get_header(header::LINK);
+    let body = response.bytes().await?.to_vec();
+    let cached = CachedResponse { etag, link, body };
+    if let Err(err) = cache.put(url, &cached) {
+        warn!(?err, "Failed to cache response.");
+    }
+    Ok(response_from_cache(cached))
+}
+
+fn response_from_cache(cached: CachedResponse) -> reqwest::Response {
+    let mut builder = http::Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(link) = cached.link {
+        builder = builder.header(header::LINK, link);
+    }
+    builder
+        .body(cached.body)
+        .expect("cached headers should be valid")
+        .into()
+}
+
+/// Sends a POST request.
+pub async fn post<B, R>(
+    client: &ApiClient,
+    route: impl AsRef<str>,
+    body: Option<&B>,
+) -> Result<R, anyhow::Error>
+where
+    B: Serialize + ?Sized,
+    R: FromResponse,
+{
+    let url = client.absolute_url(route)?;
+    let response = send_with_retry(&Method::POST, &url, || {
+        client.request_with_body(Method::POST, &url, body)
+    })
+    .await?;
+    Ok(R::from_response(map_github_error(response).await?).await?)
+}
+
+/// Sends a PATCH request.
+pub async fn patch<B, R>(
+    client: &ApiClient,
+    route: impl AsRef<str>,
+    body: Option<&B>,
+) -> Result<R, anyhow::Error>
+where
+    B: Serialize + ?Sized,
+    R: FromResponse,
+{
+    let url = client.absolute_url(route)?;
+    let response = send_with_retry(&Method::PATCH, &url, || {
+        client.request_with_body(Method::PATCH, &url, body)
+    })
+    .await?;
+    Ok(R::from_response(map_github_error(response).await?).await?)
+}
+
+/// Sends a PUT request.
+pub async fn put<B, R>(
+    client: &ApiClient,
+    route: impl AsRef<str>,
+    body: Option<&B>,
+) -> Result<R, anyhow::Error>
+where
+    B: Serialize + ?Sized,
+    R: FromResponse,
+{
+    let url = client.absolute_url(route)?;
+    let response = send_with_retry(&Method::PUT, &url, || {
+        client.request_with_body(Method::PUT, &url, body)
+    })
+    .await?;
+    Ok(R::from_response(map_github_error(response).await?).await?)
+}
+
+/// Sends a PUT request whose response may have no content, returns `None` when it doesn't.
+pub async fn put_optional<B, R>(
+    client: &ApiClient,
+    route: impl AsRef<str>,
+    body: Option<&B>,
+) -> Result<Option<R>, anyhow::Error>
+where
+    B: Serialize + ?Sized,
+    R: FromResponse,
+{
+    let url = client.absolute_url(route)?;
+    let response = send_with_retry(&Method::PUT, &url, || {
+        client.request_with_body(Method::PUT, &url, body)
+    })
+    .await?;
+    let response = map_github_error(response).await?;
+    if response.status() == StatusCode::NO_CONTENT {
+        return Ok(None);
+    }
+    Ok(Some(R::from_response(response).await?))
+}
+
+/// Sends a request whose response has no content, e.g. `204 No Content`.
+pub async fn send_without_content(
+    client: &ApiClient,
+    method: http::Method,
+    route: impl AsRef<str>,
+) -> Result<(), anyhow::Error> {
+    if ![
+        http::Method::POST,
+        http::Method::PATCH,
+        http::Method::PUT,
+        http::Method::DELETE,
+    ]
+    .contains(&method)
+    {
+        bail!("unsupported method `{}`", method)
+    }
+    let url = client.absolute_url(route)?;
+    let response =
+        send_with_retry(&method, &url, || Ok(client.request(method.clone(), &url))).await?;
+    map_github_error(response).await?;
+    Ok(())
+}
+
+/// Sends a request, waiting and sending it again while it's rate limited, up to
+/// [MAX_RETRIES] times.
+///
+/// `build` builds the request, again for each retry. The last response is returned as is, the
+/// caller maps it into an error. With a cassette, the responses are recorded, or replayed without
+/// sending the request.
+async fn send_with_retry<F>(
+    method: &Method,
+    url: &Url,
+    mut build: F,
+) -> Result<reqwest::Response, anyhow::Error>
+where
+    F: FnMut() -> Result<RequestBuilder, anyhow::Error>,
+{
+    let cassette = Cassette::current();
+    let mut retries = 0;
+    loop {
+        let response = match &cassette {
+            Some(x) if x.is_replaying() => x.replay_response(method, url),
+            Some(x) => {
+                x.record_response(method, url, build()?.send().await?)
+                    .await?
+            }
+            None => build()?.send().await?,
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        match retry_delay(response.status(), response.headers(), now, retries) {
+            Some(delay) if retries < MAX_RETRIES => {
+                eprintln!(
+                    "Rate limited by GitHub, retrying in {}.",
+                    humantime::format_duration(delay)
+                );
+                retries += 1;
+                time::sleep(delay).await;
+            }
+            _ => return Ok(response),
+        }
+    }
+}
+
+/// Finds how long to wait before retrying a response, `None` when it's not rate limited.
+///
+/// `now` is in seconds since the Unix epoch, like `x-ratelimit-reset`.
+fn retry_delay(
+    status: StatusCode,
+    headers: &HeaderMap,
+    now: u64,
+    retries: u32,
+) -> Option<Duration> {
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.parse::<u64>().ok())
+    };
+    if let Some(secs) = header("retry-after") {
+        return Some(Duration::from_secs(secs));
+    }
+    if header("x-ratelimit-remaining") == Some(0) {
+        if let Some(reset) = header("x-ratelimit-reset") {
+            // a second of slack for clock differences
+            return Some(Duration::from_secs(reset.saturating_sub(now) + 1));
+        }
+    }
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Some(SECONDARY_RATE_LIMIT_WAIT * 2u32.pow(retries));
+    }
+    // a forbidden without rate limit headers is a permission error
+    None
+}
+
+#[cfg(test)]
+#[test]
+fn test_retry_delay() {
+    let headers = |xs: &[(&'static str, &str)]| {
+        let mut headers = HeaderMap::new();
+        for (name, value) in xs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    };
+
+    assert_eq!(
+        retry_delay(StatusCode::OK, &headers(&[("retry-after", "5")]), 0, 0),
+        None
+    );
+    assert_eq!(
+        retry_delay(StatusCode::FORBIDDEN, &headers(&[]), 0, 0),
+        None
+    );
+    assert_eq!(
+        retry_delay(
+            StatusCode::FORBIDDEN,
+            &headers(&[("retry-after", "5")]),
+            0,
+            0
+        ),
+        Some(Duration::from_secs(5))
+    );
+    assert_eq!(
+        retry_delay(
+            StatusCode::FORBIDDEN,
+            &headers(&[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", "1060")
+            ]),
+            1000,
+            0
+        ),
+        Some(Duration::from_secs(61))
+    );
+    assert_eq!(
+        retry_delay(
+            StatusCode::FORBIDDEN,
+            &headers(&[("x-ratelimit-remaining", "12")]),
+            1000,
+            0
+        ),
+        None
+    );
+    assert_eq!(
+        retry_delay(StatusCode::TOO_MANY_REQUESTS, &headers(&[]), 0, 2),
+        Some(Duration::from_secs(240))
+    );
+}
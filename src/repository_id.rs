@@ -23,6 +23,88 @@ impl FullRepoId {
     }
 }
 
+impl FullRepoId {
+    /// Parses repository id from a remote URL of any host, e.g. of a GitHub Enterprise Server.
+    ///
+    /// URLs are either `scheme://[user@]host/owner/name[.git]` or `[user@]host:owner/name[.git]`.
+    /// Returns `None` if the URL is not a repository URL.
+    pub fn from_remote_url(url: &str) -> Option<Self> {
+        let (host, path) = match url.split_once("://") {
+            Some((_, rest)) => {
+                let (authority, path) = rest.split_once('/')?;
+                (authority.rsplit('@').next().unwrap_or_default(), path)
+            }
+            None => {
+                let (authority, path) = url.split_once(':')?;
+                if authority.contains('/') {
+                    return None;
+                }
+                (authority.rsplit('@').next().unwrap_or_default(), path)
+            }
+        };
+        if host.is_empty() {
+            return None;
+        }
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        match path.split_once('/') {
+            Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+                Some(Self {
+                    owner: owner.to_owned(),
+                    name: name.to_owned(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_repository_id_from_remote_url() {
+    let expected = Some(FullRepoId {
+        owner: "kafji".to_owned(),
+        name: "shub".to_owned(),
+    });
+    assert_eq!(
+        expected,
+        FullRepoId::from_remote_url("git@github.com:kafji/shub.git")
+    );
+    assert_eq!(
+        expected,
+        FullRepoId::from_remote_url("ssh://git@github.com/kafji/shub.git")
+    );
+    assert_eq!(
+        expected,
+        FullRepoId::from_remote_url("https://github.com/kafji/shub")
+    );
+    assert_eq!(
+        expected,
+        FullRepoId::from_remote_url("git@github.example.com:kafji/shub.git")
+    );
+    assert_eq!(
+        expected,
+        FullRepoId::from_remote_url("https://github.example.com/kafji/shub.git")
+    );
+    assert_eq!(
+        expected,
+        FullRepoId::from_remote_url("ssh://git@github.example.com:2222/kafji/shub")
+    );
+    assert_eq!(
+        None,
+        FullRepoId::from_remote_url("https://github.com/kafji")
+    );
+    assert_eq!(None, FullRepoId::from_remote_url("file:///srv/kafji/shub"));
+    assert_eq!(None, FullRepoId::from_remote_url("/srv/git/kafji/shub"));
+    assert_eq!(
+        Some(FullRepoId {
+            owner: "kafji".to_owned(),
+            name: ".github".to_owned(),
+        }),
+        FullRepoId::from_remote_url("https://github.com/kafji/.github.git")
+    );
+}
+
 impl fmt::Display for FullRepoId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}/{}", self.owner, self.name)
@@ -154,6 +236,69 @@ fn test_parse_partial_repository_id() {
     );
 }
 
+/// Issue or pull request identifier, i.e. `:owner?/:name#:number`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct IssueId {
+    pub repo: PartialRepoId,
+    pub number: u64,
+}
+
+impl FromStr for IssueId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (repo, number) = match s.rsplit_once('#') {
+            Some(x) => x,
+            None => bail!(
+                "Expecting in `:owner?/:name#:number` format, but was `{}`.",
+                s
+            ),
+        };
+        let number = match number.parse() {
+            Ok(x) => x,
+            Err(_) => bail!(
+                "Expecting issue number to be a number, but was `{}`.",
+                number
+            ),
+        };
+        let repo = repo.parse()?;
+        Ok(Self { repo, number })
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_issue_id() {
+    assert_eq!(
+        IssueId {
+            repo: PartialRepoId {
+                owner: "kafji".to_owned().into(),
+                name: "shub".to_owned()
+            },
+            number: 42
+        },
+        "kafji/shub#42".parse().unwrap()
+    );
+    assert_eq!(
+        IssueId {
+            repo: PartialRepoId {
+                owner: None,
+                name: "shub".to_owned()
+            },
+            number: 42
+        },
+        "shub#42".parse().unwrap()
+    );
+    assert_eq!(
+        "Expecting in `:owner?/:name#:number` format, but was `kafji/shub`.",
+        "kafji/shub".parse::<IssueId>().unwrap_err().to_string()
+    );
+    assert_eq!(
+        "Expecting issue number to be a number, but was `x`.",
+        "kafji/shub#x".parse::<IssueId>().unwrap_err().to_string()
+    );
+}
+
 // types ------------------------------
 
 #[derive(Debug, PartialEq)]
@@ -186,6 +331,16 @@ impl IsRepositoryId for RepositoryId<'_> {
     }
 }
 
+impl IsRepositoryId for FullRepoId {
+    fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
 impl<T: IsRepositoryId> IsPartialRepositoryId for T {
     fn owner(&self) -> Option<&str> {
         Some(IsRepositoryId::owner(self))
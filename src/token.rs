@@ -0,0 +1,321 @@
+//! Defines GitHub token sources.
+
+use crate::process;
+use anyhow::{bail, Context, Error};
+use directories_next::BaseDirs;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    io::{self, Write},
+    path::PathBuf,
+    process::Stdio,
+};
+
+/// Keyring service of the token stored by `shub auth login`.
+const KEYRING_SERVICE: &str = "shub";
+
+/// Keyring user of the github.com token stored by `shub auth login`, tokens of other hosts are
+/// stored as `github-token@<host>`.
+const KEYRING_USER: &str = "github-token";
+
+/// Host of github.com, whose API is at `api.github.com`.
+const GITHUB_HOST: &str = "github.com";
+
+/// Source of GitHub token.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum TokenSource {
+    /// `SHUB_TOKEN` environment variable.
+    Env,
+    /// Output of the command line in `SHUB_TOKEN_COMMAND`, e.g. `pass show github/pat`.
+    Command,
+    /// OS keyring, where `shub auth login` stores the token.
+    Keyring,
+    /// gh CLI configuration, or `gh auth token` when gh keeps the token in the keyring.
+    GhCli,
+    /// Git credential helpers, through `git credential fill`.
+    GitCredential,
+}
+
+impl TokenSource {
+    /// Sources in the order they are tried.
+    const ALL: [TokenSource; 5] = [
+        TokenSource::Env,
+        TokenSource::Command,
+        TokenSource::Keyring,
+        TokenSource::GhCli,
+        TokenSource::GitCredential,
+    ];
+
+    /// Gets token of a host from this source, `None` when it has none.
+    ///
+    /// Environment variables apply to every host, they're set for the selected profile.
+    fn get_token(&self, host: &str) -> Result<Option<String>, Error> {
+        match self {
+            TokenSource::Env => Ok(env::var("SHUB_TOKEN").ok()),
+            TokenSource::Command => match env::var("SHUB_TOKEN_COMMAND") {
+                Ok(command_line) => run_token_command(&command_line).map(Some),
+                Err(_) => Ok(None),
+            },
+            TokenSource::Keyring => read_keyring_token(host),
+            TokenSource::GhCli => match read_gh_cli_token(host)? {
+                Some(x) => Ok(Some(x)),
+                None => run_gh_auth_token(host),
+            },
+            TokenSource::GitCredential => fill_git_credential(host),
+        }
+    }
+}
+
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            TokenSource::Env => "SHUB_TOKEN",
+            TokenSource::Command => "SHUB_TOKEN_COMMAND",
+            TokenSource::Keyring => "keyring",
+            TokenSource::GhCli => "gh CLI",
+            TokenSource::GitCredential => "git credential helper",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Gets host whose credentials authenticate to a GitHub API, github.com when none.
+pub fn api_host(api_url: Option<&str>) -> Result<String, Error> {
+    let api_url = match api_url {
+        Some(x) => x,
+        None => return Ok(GITHUB_HOST.to_owned()),
+    };
+    let url = reqwest::Url::parse(api_url)
+        .with_context(|| format!("Invalid GitHub API URL `{api_url}`."))?;
+    match url.host_str() {
+        Some("api.github.com") => Ok(GITHUB_HOST.to_owned()),
+        Some(x) => Ok(x.to_owned()),
+        None => bail!("GitHub API URL `{api_url}` has no host."),
+    }
+}
+
+/// Gets GitHub token of a host from the first source that has one, in the order of
+/// [TokenSource].
+pub fn get_github_token(host: &str) -> Result<String, Error> {
+    find_github_token(host).map(|(token, _)| token)
+}
+
+/// Gets GitHub token of a host and its source from the first source that has one.
+pub fn find_github_token(host: &str) -> Result<(String, TokenSource), Error> {
+    for source in TokenSource::ALL {
+        if let Some(token) = source.get_token(host)? {
+            return Ok((token, source));
+        }
+    }
+    bail!("GitHub token of {host} not found, log in with `shub auth login` or set SHUB_TOKEN.")
+}
+
+/// Stores token of a host in the OS keyring, replacing the stored one.
+pub fn store_keyring_token(host: &str, token: &str) -> Result<(), Error> {
+    keyring::Entry::new(KEYRING_SERVICE, &keyring_user(host))
+        .set_password(token)
+        .context("Failed to store token in the keyring.")
+}
+
+fn keyring_user(host: &str) -> String {
+    if host == GITHUB_HOST {
+        KEYRING_USER.to_owned()
+    } else {
+        format!("{KEYRING_USER}@{host}")
+    }
+}
+
+/// Reads token of a host stored by `shub auth login`.
+///
+/// Returns none when there's no stored token or no keyring, e.g. on a headless machine.
+fn read_keyring_token(host: &str) -> Result<Option<String>, Error> {
+    match keyring::Entry::new(KEYRING_SERVICE, &keyring_user(host)).get_password() {
+        Ok(x) => Ok(Some(x)),
+        Err(keyring::Error::NoEntry)
+        | Err(keyring::Error::NoStorageAccess(_))
+        | Err(keyring::Error::PlatformFailure(_)) => Ok(None),
+        Err(err) => Err(err).context("Failed to read token from the keyring."),
+    }
+}
+
+/// Runs `gh auth token` for a host.
+///
+/// Returns none when gh is not installed or is not logged in to the host.
+fn run_gh_auth_token(host: &str) -> Result<Option<String>, Error> {
+    let output = std::process::Command::new("gh")
+        .args(["auth", "token", "--hostname", host])
+        .stderr(Stdio::null())
+        .output();
+    let output = match output {
+        Ok(x) => x,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let stdout = String::from_utf8(output.stdout)?;
+    Ok(stdout
+        .lines()
+        .next()
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(ToOwned::to_owned))
+}
+
+/// Runs token command and takes the first line of its output as the token.
+pub(crate) fn run_token_command(command_line: &str) -> Result<String, Error> {
+    let output = process::shell(command_line)
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("Failed to run token command `{command_line}`."))?;
+    if !output.status.success() {
+        bail!("Token command `{command_line}` failed, {}.", output.status)
+    }
+    let stdout = String::from_utf8(output.stdout)?;
+    match stdout.lines().next().map(str::trim) {
+        Some(x) if !x.is_empty() => Ok(x.to_owned()),
+        _ => bail!("Token command `{command_line}` printed nothing."),
+    }
+}
+
+/// Asks git credential helpers for the password of a host.
+///
+/// Returns none when git is not installed or no helper has the credential.
+fn fill_git_credential(host: &str) -> Result<Option<String>, Error> {
+    let child = std::process::Command::new("git")
+        .args(["credential", "fill"])
+        // fail instead of prompting when no helper has the credential
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(x) => x,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    child
+        .stdin
+        .take()
+        .expect("stdin should be piped")
+        .write_all(format!("protocol=https\nhost={host}\n\n").as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(parse_git_credential(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+fn parse_git_credential(text: &str) -> Option<String> {
+    text.lines()
+        .filter_map(|x| x.split_once('='))
+        .find(|(k, _)| *k == "password")
+        .map(|(_, v)| v.to_owned())
+}
+
+/// Reads token of a host from gh CLI `hosts.yml`.
+fn read_gh_cli_token(host: &str) -> Result<Option<String>, Error> {
+    let path = match gh_cli_config_dir() {
+        Some(x) => x.join("hosts.yml"),
+        None => return Ok(None),
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(x) => x,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {}.", path.display())),
+    };
+    parse_gh_cli_hosts(&text, host).with_context(|| format!("Failed to parse {}.", path.display()))
+}
+
+/// Finds gh CLI configuration directory the same way gh does.
+fn gh_cli_config_dir() -> Option<PathBuf> {
+    if let Some(x) = env::var_os("GH_CONFIG_DIR") {
+        return Some(x.into());
+    }
+    if let Some(x) = env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(x).join("gh"));
+    }
+    let dirs = BaseDirs::new()?;
+    if cfg!(windows) {
+        Some(dirs.config_dir().join("GitHub CLI"))
+    } else {
+        Some(dirs.home_dir().join(".config").join("gh"))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct GhCliHost {
+    oauth_token: Option<String>,
+}
+
+fn parse_gh_cli_hosts(text: &str, host: &str) -> Result<Option<String>, Error> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    let mut hosts: HashMap<String, GhCliHost> = serde_yaml::from_str(text)?;
+    Ok(hosts.remove(host).and_then(|x| x.oauth_token))
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_git_credential() {
+    let text = "protocol=https\nhost=github.com\nusername=kafji\npassword=ghp_abc\n";
+    assert_eq!(parse_git_credential(text).as_deref(), Some("ghp_abc"));
+    assert_eq!(
+        parse_git_credential("protocol=https\nhost=github.com\n"),
+        None
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_gh_cli_hosts() {
+    let text = "
+github.com:
+    user: kafji
+    oauth_token: gho_abc
+    git_protocol: ssh
+ghe.example.com:
+    oauth_token: gho_def
+";
+    assert_eq!(
+        parse_gh_cli_hosts(text, "github.com").unwrap().as_deref(),
+        Some("gho_abc")
+    );
+    assert_eq!(
+        parse_gh_cli_hosts(text, "ghe.example.com")
+            .unwrap()
+            .as_deref(),
+        Some("gho_def")
+    );
+    assert_eq!(parse_gh_cli_hosts(text, "ghe.example.org").unwrap(), None);
+
+    let text = "
+github.com:
+    user: kafji
+    git_protocol: ssh
+";
+    assert_eq!(parse_gh_cli_hosts(text, "github.com").unwrap(), None);
+
+    assert_eq!(parse_gh_cli_hosts("", "github.com").unwrap(), None);
+}
+
+#[cfg(test)]
+#[test]
+fn test_api_host() {
+    assert_eq!(api_host(None).unwrap(), "github.com");
+    assert_eq!(
+        api_host(Some("https://api.github.com/")).unwrap(),
+        "github.com"
+    );
+    assert_eq!(
+        api_host(Some("https://github.example.com/api/v3/")).unwrap(),
+        "github.example.com"
+    );
+    assert!(api_host(Some("github.example.com")).is_err());
+}
@@ -1,7 +1,12 @@
 //! Defines application domain data types.
 
-use crate::{github_models::GhRepository, repository_id::IsRepositoryId};
+use crate::{
+    github_models::{GhRepository, GhStar},
+    repository_id::IsRepositoryId,
+};
 use anyhow::bail;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::{fmt, str::FromStr};
 use thiserror::Error;
 
@@ -14,6 +19,14 @@ pub struct Repository {
     pub a_fork: bool,
     pub archived: bool,
     pub build_status: Option<BuildStatus>,
+    /// Commit of the default branch the build status was computed for.
+    pub build_status_sha: Option<String>,
+    pub pushed_at: Option<DateTime<Utc>>,
+    /// Number of open pull requests, known after updating the dashboard.
+    pub open_pull_requests: Option<u64>,
+    /// Number of branches with commits not merged to the default branch, known after updating
+    /// the dashboard.
+    pub unmerged_branches: Option<u64>,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
@@ -23,6 +36,83 @@ pub enum BuildStatus {
     InProgress,
 }
 
+/// Build status of a commit, recorded when the dashboard is updated.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BuildStatusRecord {
+    pub sha: String,
+    pub status: BuildStatus,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Repository starred by current user.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct Star {
+    pub owner: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub language: Option<String>,
+    pub starred_at: Option<DateTime<Utc>>,
+    pub topics: Vec<String>,
+}
+
+/// Mutation done to a GitHub resource, recorded so it can be undone.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mutation {
+    pub id: i64,
+    pub kind: MutationKind,
+    /// Identifier of the mutated resource, e.g. `:owner/:name` for a repository.
+    pub target: String,
+    /// Data to undo the mutation with, in JSON.
+    pub data: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Filter and order of issues and pull requests assigned to current user.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TaskFilter {
+    pub kind: Option<TaskKind>,
+    /// Repository identifier, i.e. `:owner/:name`.
+    pub repo: Option<String>,
+    pub sort: Option<TaskSort>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TaskKind {
+    Issue,
+    PullRequest,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TaskSort {
+    Updated,
+    Created,
+}
+
+/// Reference to the commit whose build is polled.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BuildRef {
+    /// Branch, tag, or commit SHA.
+    GitRef(String),
+    /// Head of a pull request, written as `#N`.
+    PullRequest(u64),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MutationKind {
+    UpdateSettings,
+    RenameBranch,
+    UpdateAssignees,
+    UpdateLabels,
+    Unstar,
+    UpdateTopics,
+    Star,
+    SetArchived,
+    RenameRepository,
+    CloseIssue,
+    /// Mutation that can't be undone, recorded so undo stops at it.
+    NotUndoable,
+}
+
 // end: types ------------------------------
 
 // Repository impls ------------------------------
@@ -54,6 +144,10 @@ impl TryFrom<GhRepository> for Repository {
             a_fork: x.fork.unwrap_or_default(),
             archived: x.archived.unwrap_or_default(),
             build_status: None,
+            pushed_at: x.pushed_at,
+            open_pull_requests: None,
+            unmerged_branches: None,
+            build_status_sha: None,
         };
         Ok(s)
     }
@@ -61,6 +155,33 @@ impl TryFrom<GhRepository> for Repository {
 
 // end: Repository impls ------------------------------
 
+// Star impls ------------------------------
+
+impl IsRepositoryId for Star {
+    fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl From<GhStar> for Star {
+    fn from(x: GhStar) -> Self {
+        Self {
+            owner: x.owner,
+            name: x.name,
+            description: x.description,
+            language: x.language,
+            starred_at: Some(x.starred_at),
+            topics: x.topics,
+        }
+    }
+}
+
+// end: Star impls ------------------------------
+
 // BuildStatus impls ------------------------------
 
 impl fmt::Display for BuildStatus {
@@ -98,3 +219,197 @@ impl FromStr for BuildStatus {
 pub struct ParseBuildStatusError(String /* message */);
 
 // end: BuildStatus impls ------------------------------
+
+// TaskFilter impls ------------------------------
+
+impl TaskFilter {
+    /// Search query of open issues and pull requests assigned to current user matching the
+    /// filter.
+    ///
+    /// https://docs.github.com/en/search-github/searching-on-github/searching-issues-and-pull-requests
+    pub fn search_query(&self) -> String {
+        self.query("assignee:@me", self.kind)
+    }
+
+    /// Search query of open pull requests requesting review of current user matching the filter,
+    /// `None` when the filter is for issues.
+    pub fn review_requests_query(&self) -> Option<String> {
+        match self.kind {
+            Some(TaskKind::Issue) => None,
+            _ => Some(self.query("review-requested:@me", Some(TaskKind::PullRequest))),
+        }
+    }
+
+    fn query(&self, qualifier: &str, kind: Option<TaskKind>) -> String {
+        let mut query = vec!["is:open".to_owned(), qualifier.to_owned()];
+        match kind {
+            Some(TaskKind::Issue) => query.push("is:issue".to_owned()),
+            Some(TaskKind::PullRequest) => query.push("is:pr".to_owned()),
+            None => (),
+        }
+        if let Some(repo) = &self.repo {
+            query.push(format!("repo:{repo}"));
+        }
+        query.join(" ")
+    }
+
+    /// Sort parameter of the search, `None` for best match.
+    pub fn search_sort(&self) -> Option<&'static str> {
+        self.sort.map(|x| match x {
+            TaskSort::Updated => "updated",
+            TaskSort::Created => "created",
+        })
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_task_filter_search_query() {
+    assert_eq!(TaskFilter::default().search_query(), "is:open assignee:@me");
+    let filter = TaskFilter {
+        kind: Some(TaskKind::PullRequest),
+        repo: Some("kafji/shub".to_owned()),
+        sort: Some(TaskSort::Updated),
+    };
+    assert_eq!(
+        filter.search_query(),
+        "is:open assignee:@me is:pr repo:kafji/shub"
+    );
+    assert_eq!(filter.search_sort(), Some("updated"));
+    assert_eq!(
+        filter.review_requests_query().unwrap(),
+        "is:open review-requested:@me is:pr repo:kafji/shub"
+    );
+    let filter = TaskFilter {
+        kind: Some(TaskKind::Issue),
+        ..Default::default()
+    };
+    assert_eq!(filter.review_requests_query(), None);
+}
+
+// end: TaskFilter impls ------------------------------
+
+// BuildRef impls ------------------------------
+
+impl FromStr for BuildRef {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            bail!("Expecting a branch, a commit SHA, or `#N`, but was empty.")
+        }
+        match s.strip_prefix('#') {
+            Some(number) => match number.parse() {
+                Ok(x) => Ok(BuildRef::PullRequest(x)),
+                Err(_) => bail!("Expecting pull request number after `#`, but was `{}`.", s),
+            },
+            None => Ok(BuildRef::GitRef(s.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for BuildRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildRef::GitRef(x) => write!(f, "{x}"),
+            BuildRef::PullRequest(x) => write!(f, "#{x}"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_build_ref() {
+    assert_eq!(
+        "main".parse::<BuildRef>().unwrap(),
+        BuildRef::GitRef("main".to_owned())
+    );
+    assert_eq!(
+        "3f2a9c1".parse::<BuildRef>().unwrap(),
+        BuildRef::GitRef("3f2a9c1".to_owned())
+    );
+    assert_eq!(
+        "#42".parse::<BuildRef>().unwrap(),
+        BuildRef::PullRequest(42)
+    );
+    assert!("#main".parse::<BuildRef>().is_err());
+    assert!("".parse::<BuildRef>().is_err());
+}
+
+// end: BuildRef impls ------------------------------
+
+// MutationKind impls ------------------------------
+
+impl fmt::Display for MutationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use MutationKind::*;
+        let s = match self {
+            UpdateSettings => "update_settings",
+            RenameBranch => "rename_branch",
+            UpdateAssignees => "update_assignees",
+            UpdateLabels => "update_labels",
+            Unstar => "unstar",
+            UpdateTopics => "update_topics",
+            Star => "star",
+            SetArchived => "set_archived",
+            RenameRepository => "rename_repository",
+            CloseIssue => "close_issue",
+            NotUndoable => "not_undoable",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for MutationKind {
+    type Err = ParseMutationKindError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use MutationKind::*;
+        let s = match s {
+            "update_settings" => UpdateSettings,
+            "rename_branch" => RenameBranch,
+            "update_assignees" => UpdateAssignees,
+            "update_labels" => UpdateLabels,
+            "unstar" => Unstar,
+            "update_topics" => UpdateTopics,
+            "star" => Star,
+            "set_archived" => SetArchived,
+            "rename_repository" => RenameRepository,
+            "close_issue" => CloseIssue,
+            "not_undoable" => NotUndoable,
+            _ => {
+                let err = ParseMutationKindError(format!("unexpected string, was `{}`", s));
+                return Err(err);
+            }
+        };
+        Ok(s)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct ParseMutationKindError(String /* message */);
+
+#[cfg(test)]
+#[test]
+fn test_mutation_kind_round_trip() {
+    use MutationKind::*;
+    for kind in [
+        UpdateSettings,
+        RenameBranch,
+        UpdateAssignees,
+        UpdateLabels,
+        Unstar,
+        UpdateTopics,
+        Star,
+        SetArchived,
+        RenameRepository,
+        CloseIssue,
+        NotUndoable,
+    ] {
+        assert_eq!(kind.to_string().parse::<MutationKind>().unwrap(), kind);
+    }
+    assert!("delete_repository".parse::<MutationKind>().is_err());
+}
+
+// end: MutationKind impls ------------------------------
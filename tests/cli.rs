@@ -0,0 +1,139 @@
+//! Tests of the command line, replaying GitHub responses from cassettes instead of sending the
+//! requests.
+
+use serde_json::{json, Value};
+use std::{fs, process::Command};
+
+const API_URL: &str = "https://api.github.test/";
+
+/// Recorded interaction, in the format of cassette files.
+fn interaction(method: &str, route: &str, body: Value) -> Value {
+    json!({
+        "method": method,
+        "url": format!("{API_URL}{route}"),
+        "status": 200,
+        "headers": { "content-type": "application/json" },
+        "body": base64::encode(body.to_string()),
+    })
+}
+
+fn user(login: &str) -> Value {
+    let url = format!("{API_URL}users/{login}");
+    json!({
+        "login": login,
+        "id": 1,
+        "node_id": "MDQ6VXNlcjE=",
+        "avatar_url": "https://avatars.github.test/u/1",
+        "gravatar_id": "",
+        "url": url,
+        "html_url": format!("https://github.test/{login}"),
+        "followers_url": format!("{url}/followers"),
+        "following_url": format!("{url}/following{{/other_user}}"),
+        "gists_url": format!("{url}/gists{{/gist_id}}"),
+        "starred_url": format!("{url}/starred{{/owner}}{{/repo}}"),
+        "subscriptions_url": format!("{url}/subscriptions"),
+        "organizations_url": format!("{url}/orgs"),
+        "repos_url": format!("{url}/repos"),
+        "events_url": format!("{url}/events{{/privacy}}"),
+        "received_events_url": format!("{url}/received_events"),
+        "type": "User",
+        "site_admin": false,
+    })
+}
+
+fn repository(owner: &str, name: &str) -> Value {
+    json!({
+        "id": 2,
+        "node_id": "MDEwOlJlcG9zaXRvcnky",
+        "name": name,
+        "full_name": format!("{owner}/{name}"),
+        "owner": user(owner),
+        "private": false,
+        "html_url": format!("https://github.test/{owner}/{name}"),
+        "description": null,
+        "fork": false,
+        "url": format!("{API_URL}repos/{owner}/{name}"),
+        "archived": false,
+        "disabled": false,
+        "default_branch": "main",
+        "created_at": "2022-01-01T00:00:00Z",
+        "updated_at": "2022-08-01T00:00:00Z",
+        "pushed_at": "2022-08-01T00:00:00Z",
+        "stargazers_count": 0,
+        "watchers_count": 0,
+        "forks_count": 0,
+        "open_issues_count": 2,
+        "size": 1,
+        "topics": [],
+    })
+}
+
+#[test]
+fn test_update_dashboard() {
+    let dir = tempfile::tempdir().unwrap();
+    let workspace = dir.path().join("ws");
+    fs::create_dir(&workspace).unwrap();
+
+    let cassette = dir.path().join("cassette.json");
+    let interactions = json!([
+        interaction(
+            "GET",
+            "user/repos?affiliation=owner&sort=updated&direction=desc&per_page=100",
+            json!([repository("kafji", "shub")]),
+        ),
+        // build statuses
+        interaction(
+            "POST",
+            "graphql",
+            json!({ "data": { "r0": { "defaultBranchRef": {
+                "target": { "oid": "c0ffee", "statusCheckRollup": { "state": "SUCCESS" } }
+            } } } }),
+        ),
+        // open pull requests and default branch
+        interaction(
+            "POST",
+            "graphql",
+            json!({ "data": { "r0": {
+                "pullRequests": { "totalCount": 2 },
+                "defaultBranchRef": { "name": "main" },
+            } } }),
+        ),
+        // branches compared to the default branch
+        interaction(
+            "POST",
+            "graphql",
+            json!({ "data": { "r0": { "refs": { "nodes": [
+                { "compare": { "behindBy": 0 } },
+                { "compare": { "behindBy": 3 } },
+            ] } } } }),
+        ),
+    ]);
+    fs::write(&cassette, interactions.to_string()).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_shub"))
+        .args(["--format", "json", "d", "--update"])
+        .env_clear()
+        .env("HOME", dir.path())
+        .env("XDG_CONFIG_HOME", dir.path().join("config"))
+        .env("SHUB_USERNAME", "kafji")
+        .env("SHUB_TOKEN", "token")
+        .env("SHUB_API_URL", API_URL)
+        .env("WORKSPACE_HOME", &workspace)
+        .env("SHUB_CASSETTE", &cassette)
+        .env("SHUB_CASSETTE_MODE", "replay")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let rows: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let row = &rows[0];
+    assert_eq!(row["owner"], "kafji");
+    assert_eq!(row["name"], "shub");
+    assert_eq!(row["build_status"], "success");
+    assert_eq!(row["open_pull_requests"], 2);
+    assert_eq!(row["unmerged_branches"], 1);
+}